@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use serde::{Serialize, Deserialize};
 use crate::node::{Node, NodeId};
+use crate::types::Color;
 
 pub type ComponentId = u64;
 
@@ -9,6 +10,8 @@ pub type ComponentId = u64;
 pub enum VariantPropType {
     Boolean,
     String { options: Vec<String> },
+    /// Themeable color, applied to template fills bound to this property
+    Color,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -19,10 +22,11 @@ pub struct VariantProp {
 }
 
 /// A concrete variant value
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 pub enum VariantValue {
     Boolean(bool),
     String(String),
+    Color(Color),
 }
 
 impl VariantValue {
@@ -30,6 +34,7 @@ impl VariantValue {
         match self {
             VariantValue::Boolean(b) => b.to_string(),
             VariantValue::String(s) => s.clone(),
+            VariantValue::Color(c) => c.to_hex(),
         }
     }
 }
@@ -37,8 +42,12 @@ impl VariantValue {
 /// A variant key: map of property name → value
 pub type VariantKey = HashMap<String, VariantValue>;
 
+/// Color values are bindings, not variant dimensions, so they don't contribute to the key
 fn variant_key_to_string(key: &VariantKey) -> String {
-    let mut parts: Vec<_> = key.iter().map(|(k, v)| format!("{}={}", k, v.to_display())).collect();
+    let mut parts: Vec<_> = key.iter()
+        .filter(|(_, v)| !matches!(v, VariantValue::Color(_)))
+        .map(|(k, v)| format!("{}={}", k, v.to_display()))
+        .collect();
     parts.sort();
     parts.join(",")
 }
@@ -63,6 +72,23 @@ pub struct VariantData {
     pub nodes: Vec<Node>,
 }
 
+/// What an exposed property writes to on its target node
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum ExposedPropKind {
+    Text,
+    Fill,
+    Visible,
+}
+
+/// A template child property that instances can edit directly by a friendly name
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExposedProp {
+    pub name: String,
+    /// Template node ID the property writes to
+    pub target_node_id: NodeId,
+    pub kind: ExposedPropKind,
+}
+
 /// Component definition
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Component {
@@ -77,6 +103,9 @@ pub struct Component {
     pub variants: HashMap<String, VariantData>,
     /// Default variant key string
     pub default_variant_key: String,
+    /// Instance-editable properties on template children
+    #[serde(default)]
+    pub exposed: Vec<ExposedProp>,
 }
 
 impl Component {
@@ -89,6 +118,35 @@ impl Component {
             slots: vec![],
             variants: HashMap::new(),
             default_variant_key: String::new(),
+            exposed: vec![],
+        }
+    }
+
+    /// Give every template node a new id from `alloc`, updating all references (variant roots,
+    /// parent/children links, slots, exposed props). A node shared across variants keeps one id.
+    /// Variant roots lose their parent, which lies outside the template.
+    pub fn remap_node_ids(&mut self, mut alloc: impl FnMut() -> NodeId) {
+        let mut map: HashMap<NodeId, NodeId> = HashMap::new();
+        let mut remap = |id: NodeId| *map.entry(id).or_insert_with(&mut alloc);
+        for variant in self.variants.values_mut() {
+            let old_root = variant.root_node_id;
+            variant.root_node_id = remap(old_root);
+            for node in &mut variant.nodes {
+                node.parent = if node.id == old_root { None } else { node.parent.map(&mut remap) };
+                node.id = remap(node.id);
+                for child in &mut node.children {
+                    *child = remap(*child);
+                }
+            }
+        }
+        for slot in &mut self.slots {
+            slot.placeholder_node_id = remap(slot.placeholder_node_id);
+            for child in &mut slot.default_children {
+                *child = remap(*child);
+            }
+        }
+        for prop in &mut self.exposed {
+            prop.target_node_id = remap(prop.target_node_id);
         }
     }
 
@@ -96,6 +154,32 @@ impl Component {
         self.properties.iter().map(|p| (p.name.clone(), p.default_value.clone())).collect()
     }
 
+    /// Cartesian product of every property's possible values, in property order
+    pub fn variant_matrix(&self) -> Vec<VariantKey> {
+        let mut combos: Vec<VariantKey> = vec![HashMap::new()];
+        for prop in &self.properties {
+            let values: Vec<VariantValue> = match &prop.prop_type {
+                VariantPropType::Boolean => vec![VariantValue::Boolean(false), VariantValue::Boolean(true)],
+                VariantPropType::String { options } => options.iter().cloned().map(VariantValue::String).collect(),
+                VariantPropType::Color => continue,
+            };
+            if values.is_empty() { continue; }
+            combos = combos.into_iter().flat_map(|combo| {
+                values.iter().map(move |v| {
+                    let mut next = combo.clone();
+                    next.insert(prop.name.clone(), v.clone());
+                    next
+                })
+            }).collect();
+        }
+        combos
+    }
+
+    /// Whether a variant is stored for exactly this key (no default fallback)
+    pub fn has_variant(&self, key: &VariantKey) -> bool {
+        self.variants.contains_key(&variant_key_to_string(key))
+    }
+
     pub fn get_variant(&self, key: &VariantKey) -> Option<&VariantData> {
         let key_str = variant_key_to_string(key);
         self.variants.get(&key_str).or_else(|| self.variants.get(&self.default_variant_key))
@@ -108,6 +192,48 @@ impl Component {
         }
         self.variants.insert(key_str, data);
     }
+
+    /// Remove a variant. The default variant can only be removed if another variant
+    /// exists, in which case the default is reassigned. Returns false if nothing was removed.
+    pub fn remove_variant(&mut self, key: &VariantKey) -> bool {
+        let key_str = variant_key_to_string(key);
+        if !self.variants.contains_key(&key_str) {
+            return false;
+        }
+        if key_str == self.default_variant_key && self.variants.len() == 1 {
+            return false;
+        }
+        self.variants.remove(&key_str);
+        if key_str == self.default_variant_key {
+            // Pick the lowest remaining key so the choice is deterministic
+            if let Some(next) = self.variants.keys().min() {
+                self.default_variant_key = next.clone();
+            }
+        }
+        true
+    }
+
+    /// Move a variant to a new key. Returns false if `old` doesn't exist or `new` is taken.
+    pub fn rename_variant_key(&mut self, old: &VariantKey, new: &VariantKey) -> bool {
+        let old_str = variant_key_to_string(old);
+        let new_str = variant_key_to_string(new);
+        if old_str == new_str {
+            return self.variants.contains_key(&old_str);
+        }
+        if self.variants.contains_key(&new_str) {
+            return false;
+        }
+        let mut data = match self.variants.remove(&old_str) {
+            Some(d) => d,
+            None => return false,
+        };
+        data.key = new.clone();
+        self.variants.insert(new_str.clone(), data);
+        if self.default_variant_key == old_str {
+            self.default_variant_key = new_str;
+        }
+        true
+    }
 }
 
 /// Instance overrides: what an instance can customize
@@ -118,8 +244,12 @@ pub struct InstanceData {
     pub variant_values: VariantKey,
     /// Slot fills: slot name → list of child node IDs (in the instance's own scene nodes)
     pub slot_fills: HashMap<String, Vec<NodeId>>,
-    /// Per-node property overrides (node_id_in_template → overridden properties)
+    /// Per-node property overrides (cloned scene node ID → overridden properties). Re-keyed
+    /// through `template_map` when the template is re-cloned, so they survive syncs.
     pub overrides: HashMap<NodeId, NodeOverrides>,
+    /// Template node ID → cloned scene node ID, rebuilt whenever the template is re-cloned
+    #[serde(default)]
+    pub template_map: HashMap<NodeId, NodeId>,
 }
 
 /// Overridable properties on instance children
@@ -137,6 +267,10 @@ pub struct ComponentStore {
     next_id: ComponentId,
 }
 
+impl Default for ComponentStore {
+    fn default() -> Self { Self::new() }
+}
+
 impl ComponentStore {
     pub fn new() -> Self {
         Self {
@@ -160,6 +294,17 @@ impl ComponentStore {
         self.components.get_mut(&id)
     }
 
+    /// Copy a component under a new id and name. Template node ids are left as-is.
+    pub fn duplicate(&mut self, id: ComponentId, name: String) -> Option<ComponentId> {
+        let mut copy = self.components.get(&id)?.clone();
+        let new_id = self.next_id;
+        self.next_id += 1;
+        copy.id = new_id;
+        copy.name = name;
+        self.components.insert(new_id, copy);
+        Some(new_id)
+    }
+
     pub fn remove(&mut self, id: ComponentId) -> Option<Component> {
         self.components.remove(&id)
     }
@@ -183,3 +328,53 @@ impl ComponentStore {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(pairs: &[(&str, &str)]) -> VariantKey {
+        pairs.iter().map(|(k, v)| (k.to_string(), VariantValue::String(v.to_string()))).collect()
+    }
+
+    fn variant(key: VariantKey, root: NodeId) -> VariantData {
+        VariantData { key, root_node_id: root, nodes: vec![] }
+    }
+
+    #[test]
+    fn remove_variant_reassigns_default() {
+        let mut comp = Component::new(1, "Button".to_string());
+        comp.set_variant(key(&[("size", "sm")]), variant(key(&[("size", "sm")]), 1));
+        comp.set_variant(key(&[("size", "lg")]), variant(key(&[("size", "lg")]), 2));
+        assert_eq!(comp.default_variant_key, "size=sm");
+
+        assert!(comp.remove_variant(&key(&[("size", "sm")])));
+        assert_eq!(comp.default_variant_key, "size=lg");
+        assert_eq!(comp.variants.len(), 1);
+
+        // The last remaining (default) variant can't be removed
+        assert!(!comp.remove_variant(&key(&[("size", "lg")])));
+        // Unknown keys are a no-op
+        assert!(!comp.remove_variant(&key(&[("size", "xl")])));
+        assert_eq!(comp.variants.len(), 1);
+    }
+
+    #[test]
+    fn rename_variant_key_moves_entry() {
+        let mut comp = Component::new(1, "Button".to_string());
+        comp.set_variant(key(&[("size", "sm")]), variant(key(&[("size", "sm")]), 1));
+        comp.set_variant(key(&[("size", "lg")]), variant(key(&[("size", "lg")]), 2));
+
+        assert!(comp.rename_variant_key(&key(&[("size", "sm")]), &key(&[("size", "small")])));
+        assert_eq!(comp.default_variant_key, "size=small");
+        let renamed = comp.variants.get("size=small").unwrap();
+        assert_eq!(renamed.root_node_id, 1);
+        assert_eq!(renamed.key, key(&[("size", "small")]));
+        assert!(!comp.variants.contains_key("size=sm"));
+
+        // Renaming onto an existing key is rejected
+        assert!(!comp.rename_variant_key(&key(&[("size", "small")]), &key(&[("size", "lg")])));
+        // Renaming a missing key is rejected
+        assert!(!comp.rename_variant_key(&key(&[("size", "sm")]), &key(&[("size", "md")])));
+    }
+}
@@ -0,0 +1,214 @@
+use crate::node::{Align, Fill, FlexDirection, FlexWrap, FontStyle, Gradient, Justify, LayoutMode, Node, NodeKind, TextAlign, TextDirection};
+use crate::styles::{fill_var_ref, var_ref};
+use crate::types::Color;
+
+/// CSS declarations (property, value) describing a node's box, fill, and auto-layout.
+/// Values that come from a shared style reference its custom property (see `export_css_vars`).
+pub fn css_declarations(node: &Node) -> Vec<(String, String)> {
+    let mut decls: Vec<(String, String)> = vec![];
+    let mut push = |k: &str, v: String| decls.push((k.to_string(), v));
+
+    push("width", px(node.width));
+    push("height", px(node.height));
+    for (prop, limit) in [("min-width", node.min_width), ("max-width", node.max_width), ("min-height", node.min_height), ("max-height", node.max_height)] {
+        if let Some(v) = limit {
+            push(prop, px(v));
+        }
+    }
+    if let NodeKind::Text { font_size, font_family, line_height, text_align, font_weight, font_style, direction, .. } = &node.kind {
+        match (node.top_fill(), &node.fill_style) {
+            (Some(fill), Some(style)) => push("color", fill_var_ref(style, fill.opacity)),
+            (Some(fill), None) => push("color", fill.layer_color().to_css()),
+            (None, _) => {}
+        }
+        match &node.text_style {
+            Some(style) => {
+                push("font-family", var_ref(style, "-font-family"));
+                push("font-size", var_ref(style, "-font-size"));
+                push("font-weight", var_ref(style, "-font-weight"));
+            }
+            None => {
+                push("font-family", format!("\"{}\", sans-serif", font_family));
+                push("font-size", px(*font_size));
+                push("font-weight", font_weight.to_string());
+            }
+        }
+        if *font_style == FontStyle::Italic {
+            push("font-style", "italic".to_string());
+        }
+        match &node.text_style {
+            Some(style) => push("line-height", var_ref(style, "-line-height")),
+            None => push("line-height", line_height.to_string()),
+        }
+        // Alignment is logical (start/end); "start" is the CSS default in either direction
+        match text_align {
+            TextAlign::Left => {}
+            TextAlign::Center => push("text-align", "center".to_string()),
+            TextAlign::Right => push("text-align", "end".to_string()),
+        }
+        if *direction == TextDirection::Rtl {
+            push("direction", "rtl".to_string());
+        }
+    } else if let [fill] = node.fills.as_slice() {
+        match (&fill.image, &fill.gradient) {
+            (Some(src), _) => push("background", format!("url(\"{}\") center / cover", src)),
+            (None, Some(gradient)) => push("background", gradient_css(gradient, fill, node)),
+            (None, None) => match &node.fill_style {
+                Some(style) => push("background", fill_var_ref(style, fill.opacity)),
+                None => push("background", fill.layer_color().to_css()),
+            },
+        }
+    } else if !node.fills.is_empty() {
+        // CSS lists background layers top first; solid layers become flat gradients
+        let layers: Vec<String> = node.fills.iter().rev()
+            .map(|fill| match (&fill.image, &fill.gradient) {
+                (Some(src), _) => format!("url(\"{}\") center / cover", src),
+                (None, Some(gradient)) => gradient_css(gradient, fill, node),
+                (None, None) => {
+                    let c = fill.layer_color().to_css();
+                    format!("linear-gradient({}, {})", c, c)
+                }
+            })
+            .collect();
+        push("background", layers.join(", "));
+    }
+    if matches!(node.kind, NodeKind::Ellipse) {
+        push("border-radius", "50%".to_string());
+    }
+    match &node.stroke {
+        Some(stroke) if matches!(node.kind, NodeKind::Text { .. }) => {
+            push("-webkit-text-stroke", format!("{} {}", px(stroke.width), stroke.color.to_css()));
+        }
+        Some(stroke) => {
+            let style = if stroke.dash.is_empty() { "solid" } else { "dashed" };
+            push("border", format!("{} {} {}", px(stroke.width), style, stroke.color.to_css()));
+        }
+        None => {}
+    }
+    if node.corner_radius > 0.0 && !matches!(node.kind, NodeKind::Ellipse) {
+        push("border-radius", px(node.corner_radius));
+    }
+    if node.opacity < 1.0 {
+        push("opacity", node.opacity.to_string());
+    }
+    if let Some(mode) = node.composite_operation() {
+        push("mix-blend-mode", mode.to_string());
+    }
+    if node.clip_content {
+        push("overflow", "auto".to_string());
+    }
+    if node.flex_grow > 0.0 {
+        push("flex", format!("{} 1 0", node.flex_grow));
+    }
+
+    let layout = &node.layout;
+    match layout.mode {
+        LayoutMode::Flex => {
+            push("display", "flex".to_string());
+            push("flex-direction", match layout.direction {
+                FlexDirection::Row => "row",
+                FlexDirection::Column => "column",
+            }.to_string());
+            push("justify-content", match layout.justify_content {
+                Justify::Start => "flex-start",
+                Justify::Center => "center",
+                Justify::End => "flex-end",
+                Justify::SpaceBetween => "space-between",
+                Justify::SpaceAround => "space-around",
+                Justify::SpaceEvenly => "space-evenly",
+            }.to_string());
+            push("align-items", match layout.align_items {
+                Align::Start => "flex-start",
+                Align::Center => "center",
+                Align::End => "flex-end",
+                Align::Stretch => "stretch",
+            }.to_string());
+            if layout.wrap == FlexWrap::Wrap {
+                push("flex-wrap", "wrap".to_string());
+                if layout.align_content != Justify::Start {
+                    push("align-content", match layout.align_content {
+                        Justify::Start => "flex-start",
+                        Justify::Center => "center",
+                        Justify::End => "flex-end",
+                        Justify::SpaceBetween => "space-between",
+                        Justify::SpaceAround => "space-around",
+                        Justify::SpaceEvenly => "space-evenly",
+                    }.to_string());
+                }
+            }
+        }
+        LayoutMode::Grid => {
+            push("display", "grid".to_string());
+            push("grid-template-columns", format!("repeat({}, 1fr)", layout.grid_columns.max(1)));
+            if layout.grid_rows > 0 {
+                push("grid-template-rows", format!("repeat({}, 1fr)", layout.grid_rows));
+            }
+            if let Some(g) = layout.row_gap {
+                push("row-gap", px(g));
+            }
+            if let Some(g) = layout.column_gap {
+                push("column-gap", px(g));
+            }
+        }
+        LayoutMode::None => {}
+    }
+    if layout.mode != LayoutMode::None {
+        if layout.gap > 0.0 {
+            push("gap", px(layout.gap));
+        }
+        let (t, r, b, l) = (layout.padding_top, layout.padding_right, layout.padding_bottom, layout.padding_left);
+        if t != 0.0 || r != 0.0 || b != 0.0 || l != 0.0 {
+            push("padding", format!("{} {} {} {}", px(t), px(r), px(b), px(l)));
+        }
+    }
+    decls
+}
+
+/// A CSS rule for a node, using its name as the class selector
+pub fn export_css(node: &Node) -> String {
+    let mut out = format!(".{} {{\n", class_name(&node.name));
+    for (k, v) in css_declarations(node) {
+        out.push_str(&format!("  {}: {};\n", k, v));
+    }
+    out.push_str("}\n");
+    out
+}
+
+/// Turn a layer name into a CSS-safe class name ("[C] Primary Button" → "primary-button")
+pub fn class_name(name: &str) -> String {
+    let name = name.trim_start_matches("[C] ").trim_start_matches("[I] ").trim_start_matches("[S] ");
+    let mut out = String::new();
+    for ch in name.chars() {
+        if ch.is_ascii_alphanumeric() {
+            out.push(ch.to_ascii_lowercase());
+        } else if !out.is_empty() && !out.ends_with('-') {
+            out.push('-');
+        }
+    }
+    let out = out.trim_end_matches('-').to_string();
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("node-{}", out)
+    } else {
+        out
+    }
+}
+
+/// CSS for a gradient fill layer over the node's box, with the layer opacity in each stop
+fn gradient_css(gradient: &Gradient, fill: &Fill, node: &Node) -> String {
+    match gradient {
+        Gradient::Radial { stops, .. } => {
+            let (cx, cy, r) = gradient.radial_circle(0.0, 0.0, node.width, node.height);
+            let stops: Vec<String> = stops.iter()
+                .map(|s| {
+                    let color = Color { a: s.color.a * fill.opacity, ..s.color };
+                    format!("{} {}%", color.to_css(), (s.offset.clamp(0.0, 1.0) * 10000.0).round() / 100.0)
+                })
+                .collect();
+            format!("radial-gradient(circle {} at {} {}, {})", px(r), px(cx), px(cy), stops.join(", "))
+        }
+    }
+}
+
+pub fn px(v: f64) -> String {
+    if v == 0.0 { "0".to_string() } else { format!("{}px", (v * 100.0).round() / 100.0) }
+}
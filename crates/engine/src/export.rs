@@ -0,0 +1,56 @@
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{CanvasRenderingContext2d, HtmlCanvasElement};
+use crate::render::Renderer;
+use crate::scene::Scene;
+use crate::transform::Transform;
+use crate::types::{Color, Rect};
+
+/// Largest canvas side browsers reliably allocate; bigger exports are scaled down to fit
+const MAX_CANVAS_SIDE: f64 = 16384.0;
+
+/// Create an offscreen canvas with a 2D context
+pub fn create_canvas(width: u32, height: u32) -> Result<(HtmlCanvasElement, CanvasRenderingContext2d), JsValue> {
+    let document = web_sys::window()
+        .and_then(|w| w.document())
+        .ok_or_else(|| JsValue::from_str("no document"))?;
+    let canvas: HtmlCanvasElement = document.create_element("canvas")?.dyn_into()?;
+    canvas.set_width(width.max(1));
+    canvas.set_height(height.max(1));
+    let ctx: CanvasRenderingContext2d = canvas
+        .get_context("2d")?
+        .ok_or_else(|| JsValue::from_str("no 2d context"))?
+        .dyn_into()?;
+    Ok((canvas, ctx))
+}
+
+/// Rasterize the scene-space rect `bounds` at `scale` onto a canvas of `width`×`height` pixels,
+/// with the content's top-left placed at (`offset_x`, `offset_y`). The canvas stays transparent
+/// unless a `background` is given. Returns a PNG data URL.
+#[allow(clippy::too_many_arguments)]
+pub fn rasterize(scene: &Scene, bounds: Rect, scale: f64, width: u32, height: u32, offset_x: f64, offset_y: f64, background: Option<Color>) -> Result<String, JsValue> {
+    let (canvas, ctx) = create_canvas(width, height)?;
+    if let Some(color) = background {
+        ctx.set_fill_style_str(&color.to_css());
+        ctx.fill_rect(0.0, 0.0, width as f64, height as f64);
+    }
+    let mut renderer = Renderer::new(width as f64, height as f64);
+    renderer.export_mode = true;
+    renderer.viewport = Transform {
+        a: scale, b: 0.0,
+        c: 0.0, d: scale,
+        tx: offset_x - bounds.x * scale,
+        ty: offset_y - bounds.y * scale,
+    };
+    renderer.render_nodes(&ctx, scene);
+    canvas.to_data_url()
+}
+
+/// Rasterize exactly the scene-space `region` at `scale` (reduced if the result would exceed
+/// the max canvas size). Nodes outside the region are cropped by the canvas edge.
+pub fn rasterize_region(scene: &Scene, region: Rect, scale: f64, background: Option<Color>) -> Result<String, JsValue> {
+    let longest = region.width.max(region.height) * scale;
+    let scale = if longest > MAX_CANVAS_SIDE { scale * MAX_CANVAS_SIDE / longest } else { scale };
+    let width = (region.width * scale).ceil() as u32;
+    let height = (region.height * scale).ceil() as u32;
+    rasterize(scene, region, scale, width, height, 0.0, 0.0, background)
+}
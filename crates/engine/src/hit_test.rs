@@ -1,15 +1,53 @@
-use crate::node::NodeId;
+use crate::node::{Node, NodeId};
 use crate::scene::Scene;
-use crate::types::Point;
+use crate::types::{Point, Rect};
 
-pub fn hit_test_handles(scene: &Scene, node_id: NodeId, point: Point, handle_size: f64) -> Option<usize> {
+/// Handle index reported for the rotation knob above a single selected node's top edge
+pub const ROTATION_HANDLE: usize = 8;
+/// Distance (screen px) from the top edge to the rotation knob
+pub const ROTATION_HANDLE_GAP: f64 = 20.0;
+
+/// Corner handle (0-3) or `ROTATION_HANDLE` under `point`. `rotation_gap` is
+/// `ROTATION_HANDLE_GAP` in scene units at the current zoom.
+pub fn hit_test_handles(scene: &Scene, node_id: NodeId, point: Point, handle_size: f64, rotation_gap: f64) -> Option<usize> {
     let node = scene.get_node(node_id)?;
+    let (ox, oy) = scene.scroll_offset(node_id);
+    // Handles are drawn rotated with the node, so test in its unrotated frame
+    let local = node.unrotate_point(Point { x: point.x + ox, y: point.y + oy });
+    if let Some(idx) = hit_test_rect_handles(node.bounds(), local, handle_size) {
+        return Some(idx);
+    }
+    let knob = Point { x: node.x + node.width / 2.0, y: node.y - rotation_gap };
+    if (local.x - knob.x).hypot(local.y - knob.y) < handle_size {
+        return Some(ROTATION_HANDLE);
+    }
+    None
+}
+
+/// Scene-space centers of a node's corner handles, rotated about its pivot like the selection outline
+pub fn node_handle_positions(node: &Node) -> [Point; 4] {
+    let (px, py) = node.pivot();
+    let (sin, cos) = node.rotation.sin_cos();
+    let rotate = |x: f64, y: f64| {
+        let (dx, dy) = (x - px, y - py);
+        Point { x: px + dx * cos - dy * sin, y: py + dx * sin + dy * cos }
+    };
+    [
+        rotate(node.x, node.y),
+        rotate(node.x + node.width, node.y),
+        rotate(node.x, node.y + node.height),
+        rotate(node.x + node.width, node.y + node.height),
+    ]
+}
+
+/// Corner handles of an arbitrary rect, in the same order as node handles
+pub fn hit_test_rect_handles(rect: Rect, point: Point, handle_size: f64) -> Option<usize> {
     let hs = handle_size / 2.0;
     let handles = [
-        Point { x: node.x, y: node.y },
-        Point { x: node.x + node.width, y: node.y },
-        Point { x: node.x, y: node.y + node.height },
-        Point { x: node.x + node.width, y: node.y + node.height },
+        Point { x: rect.x, y: rect.y },
+        Point { x: rect.x + rect.width, y: rect.y },
+        Point { x: rect.x, y: rect.y + rect.height },
+        Point { x: rect.x + rect.width, y: rect.y + rect.height },
     ];
     for (i, h) in handles.iter().enumerate() {
         if (point.x - h.x).abs() < hs && (point.y - h.y).abs() < hs {
@@ -18,3 +56,37 @@ pub fn hit_test_handles(scene: &Scene, node_id: NodeId, point: Point, handle_siz
     }
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::NodeKind;
+
+    #[test]
+    fn handles_follow_rotation_at_45_degrees() {
+        let mut scene = Scene::new();
+        let mut node = Node::new(0, NodeKind::Rect);
+        node.x = 0.0;
+        node.y = 0.0;
+        node.width = 100.0;
+        node.height = 100.0;
+        node.rotation = std::f64::consts::FRAC_PI_4;
+        let id = scene.add_node(node);
+
+        let handles = node_handle_positions(scene.get_node(id).unwrap());
+        let half_diag = 50.0 * std::f64::consts::SQRT_2;
+        let expected = [(50.0, 50.0 - half_diag), (50.0 + half_diag, 50.0), (50.0 - half_diag, 50.0), (50.0, 50.0 + half_diag)];
+        for (h, (ex, ey)) in handles.iter().zip(expected) {
+            assert!((h.x - ex).abs() < 1e-9 && (h.y - ey).abs() < 1e-9);
+        }
+        for (i, h) in handles.iter().enumerate() {
+            assert_eq!(hit_test_handles(&scene, id, *h, 8.0, 20.0), Some(i));
+        }
+        // The unrotated top-left corner is no longer a handle
+        assert_eq!(hit_test_handles(&scene, id, Point { x: 0.0, y: 0.0 }, 8.0, 20.0), None);
+        // The rotation knob sits 20 units "above" the rotated top edge, along the node's up axis
+        let (sin, cos) = std::f64::consts::FRAC_PI_4.sin_cos();
+        let knob = Point { x: 50.0 + 70.0 * sin, y: 50.0 - 70.0 * cos };
+        assert_eq!(hit_test_handles(&scene, id, knob, 8.0, 20.0), Some(ROTATION_HANDLE));
+    }
+}
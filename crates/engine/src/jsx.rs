@@ -0,0 +1,126 @@
+use std::collections::HashSet;
+use crate::component::ComponentStore;
+use crate::css::{css_declarations, px};
+use crate::node::{LayoutMode, NodeId, NodeKind};
+use crate::scene::Scene;
+
+/// Serialize a node subtree as a React function component with inline styles
+pub fn export_jsx(scene: &Scene, components: &ComponentStore, id: NodeId) -> String {
+    let root = match scene.get_node(id) {
+        Some(n) => n,
+        None => return String::new(),
+    };
+    let mut body = String::new();
+    write_node(scene, components, id, Placement::Root, 2, &mut body, &mut HashSet::new());
+    format!(
+        "export default function {}() {{\n  return (\n{}  );\n}}\n",
+        component_name(&root.name),
+        body,
+    )
+}
+
+/// How a node sits inside its parent element
+#[derive(Clone, Copy)]
+enum Placement {
+    Root,
+    /// Child of an auto-layout container, positioned by flex/grid flow
+    Flow,
+    /// Child of a free-form container, offset from the parent's top-left
+    Absolute(f64, f64),
+}
+
+/// Write a node and its descendants; ids already in `visited` are skipped so a child cycle terminates
+fn write_node(scene: &Scene, components: &ComponentStore, id: NodeId, placement: Placement, depth: usize, out: &mut String, visited: &mut HashSet<NodeId>) {
+    let node = match scene.get_node(id) {
+        Some(n) if n.visible && visited.insert(id) => n,
+        _ => return,
+    };
+    let indent = "  ".repeat(depth);
+
+    let mut decls = vec![];
+    match placement {
+        Placement::Root => decls.push(("position".to_string(), "relative".to_string())),
+        Placement::Flow => {}
+        Placement::Absolute(parent_x, parent_y) => {
+            decls.push(("position".to_string(), "absolute".to_string()));
+            decls.push(("left".to_string(), px(node.x - parent_x)));
+            decls.push(("top".to_string(), px(node.y - parent_y)));
+        }
+    }
+
+    if let NodeKind::Instance(data) = &node.kind {
+        let name = components.get(data.component_id)
+            .map(|c| component_name(&c.name))
+            .unwrap_or_else(|| component_name(&node.name));
+        out.push_str(&format!("{}<{} style={} />\n", indent, name, style_object(&decls)));
+        return;
+    }
+
+    decls.extend(css_declarations(node));
+    if let NodeKind::Text { content, .. } = &node.kind {
+        decls.push(("white-space".to_string(), "pre-wrap".to_string()));
+        let text = serde_json::to_string(content).unwrap_or_default();
+        out.push_str(&format!("{}<span style={}>{{{}}}</span>\n", indent, style_object(&decls), text));
+        return;
+    }
+
+    if node.children.is_empty() {
+        out.push_str(&format!("{}<div style={} />\n", indent, style_object(&decls)));
+        return;
+    }
+    if matches!(placement, Placement::Flow) && node.layout.mode == LayoutMode::None {
+        // Free-form containers need a positioning context for their children
+        decls.insert(0, ("position".to_string(), "relative".to_string()));
+    }
+    let child_placement = match node.layout.mode {
+        LayoutMode::None => Placement::Absolute(node.x, node.y),
+        _ => Placement::Flow,
+    };
+    out.push_str(&format!("{}<div style={}>\n", indent, style_object(&decls)));
+    for &child in &node.children {
+        write_node(scene, components, child, child_placement, depth + 1, out, visited);
+    }
+    out.push_str(&format!("{}</div>\n", indent));
+}
+
+/// `{{ width: "10px", backgroundColor: "..." }}`
+fn style_object(decls: &[(String, String)]) -> String {
+    let entries: Vec<String> = decls.iter()
+        .map(|(k, v)| format!("{}: {}", camel_case(k), serde_json::to_string(v).unwrap_or_default()))
+        .collect();
+    format!("{{{{ {} }}}}", entries.join(", "))
+}
+
+fn camel_case(prop: &str) -> String {
+    let mut out = String::new();
+    let mut upper = false;
+    for ch in prop.chars() {
+        if ch == '-' {
+            upper = true;
+        } else if upper {
+            out.push(ch.to_ascii_uppercase());
+            upper = false;
+        } else {
+            out.push(ch);
+        }
+    }
+    out
+}
+
+/// PascalCase identifier from a layer/component name ("[C] primary button" → "PrimaryButton")
+fn component_name(name: &str) -> String {
+    let name = name.trim_start_matches("[C] ").trim_start_matches("[I] ");
+    let mut out = String::new();
+    for word in name.split(|c: char| !c.is_ascii_alphanumeric()).filter(|w| !w.is_empty()) {
+        let mut chars = word.chars();
+        if let Some(first) = chars.next() {
+            out.push(first.to_ascii_uppercase());
+            out.push_str(chars.as_str());
+        }
+    }
+    if out.is_empty() || out.starts_with(|c: char| c.is_ascii_digit()) {
+        format!("Node{}", out)
+    } else {
+        out
+    }
+}
@@ -1,5 +1,7 @@
+use std::collections::HashSet;
 use crate::node::*;
 use crate::scene::Scene;
+use crate::types::{Point, Rect};
 
 /// Run layout on all nodes with layout.mode != None.
 /// This repositions children based on the parent's layout settings.
@@ -12,6 +14,73 @@ pub fn compute_layouts(scene: &mut Scene) {
     for id in ids {
         compute_node_layout(scene, id);
     }
+    route_connectors(scene);
+}
+
+/// Re-route every connector between the current bounds of its endpoints
+pub fn route_connectors(scene: &mut Scene) {
+    let ids: Vec<NodeId> = scene.all_node_ids().into_iter().filter(|&id| {
+        scene.get_node(id).map(|n| matches!(n.kind, NodeKind::Connector { .. })).unwrap_or(false)
+    }).collect();
+
+    for id in ids {
+        let (from, to, kind) = match scene.get_node(id).map(|n| &n.kind) {
+            Some(NodeKind::Connector { from, to, kind, .. }) => (*from, *to, kind.clone()),
+            _ => continue,
+        };
+        let (Some(a), Some(b)) = (scene.get_node(from), scene.get_node(to)) else { continue };
+        let route = route_between(a.rotated_bounds(), b.rotated_bounds(), &kind);
+        if let Some(node) = scene.get_node_mut(id) {
+            node.x = 0.0;
+            node.y = 0.0;
+            if let NodeKind::Connector { points, .. } = &mut node.kind {
+                *points = route;
+            }
+            node.normalize_path();
+        }
+    }
+}
+
+/// Scene-space route from the edge of `a` to the edge of `b`
+fn route_between(a: Rect, b: Rect, kind: &ConnectorKind) -> Vec<Point> {
+    let ca = Point { x: a.x + a.width / 2.0, y: a.y + a.height / 2.0 };
+    let cb = Point { x: b.x + b.width / 2.0, y: b.y + b.height / 2.0 };
+    match kind {
+        ConnectorKind::Straight => vec![edge_toward(a, ca, cb), edge_toward(b, cb, ca)],
+        ConnectorKind::Orthogonal => {
+            let (dx, dy) = (cb.x - ca.x, cb.y - ca.y);
+            if dx.abs() >= dy.abs() {
+                // Leave through the facing vertical sides, elbow halfway between them
+                let sx = if dx >= 0.0 { a.x + a.width } else { a.x };
+                let ex = if dx >= 0.0 { b.x } else { b.x + b.width };
+                let mx = (sx + ex) / 2.0;
+                vec![
+                    Point { x: sx, y: ca.y }, Point { x: mx, y: ca.y },
+                    Point { x: mx, y: cb.y }, Point { x: ex, y: cb.y },
+                ]
+            } else {
+                let sy = if dy >= 0.0 { a.y + a.height } else { a.y };
+                let ey = if dy >= 0.0 { b.y } else { b.y + b.height };
+                let my = (sy + ey) / 2.0;
+                vec![
+                    Point { x: ca.x, y: sy }, Point { x: ca.x, y: my },
+                    Point { x: cb.x, y: my }, Point { x: cb.x, y: ey },
+                ]
+            }
+        }
+    }
+}
+
+/// Where the ray from `center` toward `target` leaves the box `r`
+fn edge_toward(r: Rect, center: Point, target: Point) -> Point {
+    let (dx, dy) = (target.x - center.x, target.y - center.y);
+    let tx = if dx != 0.0 { (r.width / 2.0) / dx.abs() } else { f64::INFINITY };
+    let ty = if dy != 0.0 { (r.height / 2.0) / dy.abs() } else { f64::INFINITY };
+    let t = tx.min(ty);
+    if !t.is_finite() {
+        return center;
+    }
+    Point { x: center.x + dx * t, y: center.y + dy * t }
 }
 
 fn compute_node_layout(scene: &mut Scene, parent_id: NodeId) {
@@ -44,107 +113,164 @@ fn compute_flex(scene: &mut Scene, layout: &Layout, px: f64, py: f64, pw: f64, p
 
     // Collect child sizes
     let mut child_sizes: Vec<(NodeId, f64, f64)> = vec![];
+    let mut grows: Vec<f64> = vec![];
     for &cid in children {
         if let Some(child) = scene.get_node(cid) {
-            if !child.visible { continue; }
+            if !child.visible || child.layout_absolute { continue; }
             child_sizes.push((cid, child.width, child.height));
+            grows.push(child.flex_grow.max(0.0));
         }
     }
 
     if child_sizes.is_empty() { return; }
 
-    let n = child_sizes.len() as f64;
+    let avail_main = if is_row { content_w } else { content_h };
+    let avail_cross = if is_row { content_h } else { content_w };
+    let main_of = |&(_, w, h): &(NodeId, f64, f64)| if is_row { w } else { h };
+    let cross_of = |&(_, w, h): &(NodeId, f64, f64)| if is_row { h } else { w };
 
-    // Total size along main axis
-    let total_main: f64 = if is_row {
-        child_sizes.iter().map(|(_, w, _)| w).sum::<f64>() + gap * (n - 1.0)
+    // Break into lines where the next child would overflow the main axis
+    let mut lines: Vec<std::ops::Range<usize>> = vec![];
+    let mut line_start = 0;
+    let mut line_main = 0.0;
+    for (i, item) in child_sizes.iter().enumerate() {
+        let size = main_of(item);
+        if layout.wrap == FlexWrap::Wrap && i > line_start && line_main + gap + size > avail_main {
+            lines.push(line_start..i);
+            line_start = i;
+            line_main = size;
+        } else {
+            line_main += if i > line_start { gap + size } else { size };
+        }
+    }
+    lines.push(line_start..child_sizes.len());
+
+    // A single line spans the whole cross axis; wrapped lines are as thick as their largest child
+    let line_cross: Vec<f64> = if lines.len() == 1 {
+        vec![avail_cross]
     } else {
-        child_sizes.iter().map(|(_, _, h)| h).sum::<f64>() + gap * (n - 1.0)
+        lines.iter().map(|r| child_sizes[r.clone()].iter().map(cross_of).fold(0.0_f64, f64::max)).collect()
     };
 
-    let avail_main = if is_row { content_w } else { content_h };
-    let avail_cross = if is_row { content_h } else { content_w };
-
-    // Main axis start position based on justify
-    let mut main_pos = match layout.justify_content {
-        Justify::Start => 0.0,
-        Justify::Center => (avail_main - total_main) / 2.0,
-        Justify::End => avail_main - total_main,
-        Justify::SpaceBetween => 0.0,
-        Justify::SpaceAround => 0.0,
-        Justify::SpaceEvenly => 0.0,
+    // Spread lines along the cross axis by align_content
+    let k = lines.len() as f64;
+    let total_cross = line_cross.iter().sum::<f64>() + gap * (k - 1.0);
+    let free_cross = avail_cross - total_cross;
+    let (mut cross_pos, line_gap) = match layout.align_content {
+        Justify::Start => (0.0, gap),
+        Justify::Center => (free_cross / 2.0, gap),
+        Justify::End => (free_cross, gap),
+        Justify::SpaceBetween if k > 1.0 => (0.0, gap + free_cross / (k - 1.0)),
+        Justify::SpaceBetween => (0.0, gap),
+        Justify::SpaceAround => (free_cross / k / 2.0, gap + free_cross / k),
+        Justify::SpaceEvenly => (free_cross / (k + 1.0), gap + free_cross / (k + 1.0)),
     };
 
-    // Calculate spacing for distribute modes
-    let extra_gap = match layout.justify_content {
-        Justify::SpaceBetween if n > 1.0 => {
-            let total_child = if is_row {
-                child_sizes.iter().map(|(_, w, _)| w).sum::<f64>()
-            } else {
-                child_sizes.iter().map(|(_, _, h)| h).sum::<f64>()
+    for (range, cross) in lines.into_iter().zip(line_cross) {
+        let origin = if is_row {
+            Point { x: content_x, y: content_y + cross_pos }
+        } else {
+            Point { x: content_x + cross_pos, y: content_y }
+        };
+        layout_flex_line(scene, layout, &child_sizes[range.clone()], &grows[range], origin, avail_main, cross);
+        cross_pos += cross + line_gap;
+    }
+}
+
+/// Lay out one flex line whose main axis starts at `origin` and which is `line_cross` thick
+fn layout_flex_line(scene: &mut Scene, layout: &Layout, items: &[(NodeId, f64, f64)], grows: &[f64], origin: Point, avail_main: f64, line_cross: f64) {
+    let is_row = layout.direction == FlexDirection::Row;
+    let gap = layout.gap;
+    let mut child_sizes = items.to_vec();
+    let n = child_sizes.len() as f64;
+
+    // Growing children split the free space by weight instead of keeping their own size
+    // (a zero flex basis), so they also shrink back when the parent does. A child that hits
+    // its min/max is frozen there and the rest is shared again among the others.
+    let mut frozen: Vec<bool> = grows.iter().map(|g| *g <= 0.0).collect();
+    loop {
+        let weight: f64 = grows.iter().zip(&frozen).filter(|(_, f)| !**f).map(|(g, _)| g).sum();
+        if weight <= 0.0 { break; }
+        let fixed: f64 = child_sizes.iter().zip(&frozen)
+            .filter(|(_, f)| **f)
+            .map(|((_, w, h), _)| if is_row { *w } else { *h })
+            .sum();
+        let free = (avail_main - fixed - gap * (n - 1.0)).max(0.0);
+        let mut clamped = false;
+        for (i, (cid, w, h)) in child_sizes.iter_mut().enumerate() {
+            if frozen[i] { continue; }
+            let share = free * grows[i] / weight;
+            let size = match scene.get_node(*cid) {
+                Some(c) if is_row => c.clamp_width(share),
+                Some(c) => c.clamp_height(share),
+                None => share,
             };
-            (avail_main - total_child) / (n - 1.0)
-        },
+            if size != share {
+                frozen[i] = true;
+                clamped = true;
+            }
+            if is_row { *w = size.max(1.0); } else { *h = size.max(1.0); }
+        }
+        if !clamped { break; }
+    }
+
+    // Total size along main axis
+    let total_child: f64 = if is_row {
+        child_sizes.iter().map(|(_, w, _)| w).sum::<f64>()
+    } else {
+        child_sizes.iter().map(|(_, _, h)| h).sum::<f64>()
+    };
+    let total_main = total_child + gap * (n - 1.0);
+
+    // Main axis start position and spacing based on justify
+    let (mut main_pos, use_gap) = match layout.justify_content {
+        Justify::Start => (0.0, gap),
+        Justify::Center => ((avail_main - total_main) / 2.0, gap),
+        Justify::End => (avail_main - total_main, gap),
+        Justify::SpaceBetween if n > 1.0 => (0.0, (avail_main - total_child) / (n - 1.0)),
+        Justify::SpaceBetween => (0.0, gap),
         Justify::SpaceAround => {
-            let total_child = if is_row {
-                child_sizes.iter().map(|(_, w, _)| w).sum::<f64>()
-            } else {
-                child_sizes.iter().map(|(_, _, h)| h).sum::<f64>()
-            };
             let space = (avail_main - total_child) / n;
-            main_pos = space / 2.0;
-            space
-        },
+            (space / 2.0, space)
+        }
         Justify::SpaceEvenly => {
-            let total_child = if is_row {
-                child_sizes.iter().map(|(_, w, _)| w).sum::<f64>()
-            } else {
-                child_sizes.iter().map(|(_, _, h)| h).sum::<f64>()
-            };
             let space = (avail_main - total_child) / (n + 1.0);
-            main_pos = space;
-            space
-        },
-        _ => gap,
-    };
-
-    let use_gap = match layout.justify_content {
-        Justify::SpaceBetween | Justify::SpaceAround | Justify::SpaceEvenly => extra_gap,
-        _ => gap,
+            (space, space)
+        }
     };
 
     for (i, &(cid, cw, ch)) in child_sizes.iter().enumerate() {
         let child_main = if is_row { cw } else { ch };
         let child_cross = if is_row { ch } else { cw };
 
-        // Cross axis position based on align
+        // Cross axis position within the line based on align
         let cross_pos = match layout.align_items {
             Align::Start => 0.0,
-            Align::Center => (avail_cross - child_cross) / 2.0,
-            Align::End => avail_cross - child_cross,
+            Align::Center => (line_cross - child_cross) / 2.0,
+            Align::End => line_cross - child_cross,
             Align::Stretch => 0.0,
         };
 
         let (new_x, new_y) = if is_row {
-            (content_x + main_pos, content_y + cross_pos)
+            (origin.x + main_pos, origin.y + cross_pos)
         } else {
-            (content_x + cross_pos, content_y + main_pos)
+            (origin.x + cross_pos, origin.y + main_pos)
         };
 
-        // Apply stretch
+        // Apply grown size and stretch
         if let Some(child) = scene.get_node_mut(cid) {
             child.x = new_x;
             child.y = new_y;
+            if grows[i] > 0.0 {
+                if is_row { child.width = cw; } else { child.height = ch; }
+            }
             if layout.align_items == Align::Stretch {
-                if is_row { child.height = avail_cross; }
-                else { child.width = avail_cross; }
+                if is_row { child.height = child.clamp_height(line_cross); }
+                else { child.width = child.clamp_width(line_cross); }
             }
         }
 
-        main_pos += child_main;
-        if i < child_sizes.len() - 1 {
-            main_pos += use_gap;
-        }
+        main_pos += child_main + use_gap;
     }
 }
 
@@ -155,37 +281,186 @@ fn compute_grid(scene: &mut Scene, layout: &Layout, px: f64, py: f64, pw: f64, p
     let content_h = ph - layout.padding_top - layout.padding_bottom;
 
     let cols = layout.grid_columns.max(1) as usize;
-    let gap = layout.gap;
+    let col_gap = layout.column_gap.unwrap_or(layout.gap);
+    let row_gap = layout.row_gap.unwrap_or(layout.gap);
 
-    let col_w = (content_w - gap * (cols as f64 - 1.0)) / cols as f64;
+    let col_w = (content_w - col_gap * (cols as f64 - 1.0)) / cols as f64;
+    // Fixed rows split the content height evenly; otherwise each row fits its tallest child
+    let fixed_row_h = (layout.grid_rows > 0).then(|| {
+        let rows = layout.grid_rows as f64;
+        ((content_h - row_gap * (rows - 1.0)) / rows).max(1.0)
+    });
 
     let mut visible_children: Vec<(NodeId, f64)> = vec![];
     for &cid in children {
         if let Some(child) = scene.get_node(cid) {
-            if !child.visible { continue; }
+            if !child.visible || child.layout_absolute { continue; }
             visible_children.push((cid, child.height));
         }
     }
 
-    for (i, &(cid, ch)) in visible_children.iter().enumerate() {
+    for (i, &(cid, _)) in visible_children.iter().enumerate() {
         let col = i % cols;
         let row = i / cols;
 
         // Calculate row Y by summing previous rows
-        let mut row_y = 0.0;
-        for r in 0..row {
-            let row_h = visible_children.iter().skip(r * cols).take(cols)
-                .map(|(_, h)| *h).fold(0.0_f64, f64::max);
-            row_y += row_h + gap;
-        }
+        let row_y = match fixed_row_h {
+            Some(h) => row as f64 * (h + row_gap),
+            None => (0..row).map(|r| {
+                visible_children.iter().skip(r * cols).take(cols)
+                    .map(|(_, h)| *h).fold(0.0_f64, f64::max) + row_gap
+            }).sum(),
+        };
 
-        let x = content_x + col as f64 * (col_w + gap);
+        let x = content_x + col as f64 * (col_w + col_gap);
         let y = content_y + row_y;
 
         if let Some(child) = scene.get_node_mut(cid) {
             child.x = x;
             child.y = y;
-            child.width = col_w; // Grid children fill column width
+            child.width = child.clamp_width(col_w); // Grid children fill column width
+            if let Some(h) = fixed_row_h {
+                child.height = child.clamp_height(h);
+            }
+        }
+    }
+}
+
+/// Reposition the children of a free-form frame after it changed from `old` bounds to its
+/// current bounds, following each child's constraints. Resized child frames propagate further.
+pub fn apply_constraints(scene: &mut Scene, parent_id: NodeId, old: Rect) {
+    constrain_children(scene, parent_id, old, &mut HashSet::new());
+}
+
+/// `apply_constraints` body; `visited` stops a corrupted tree with a child cycle from recursing forever
+fn constrain_children(scene: &mut Scene, parent_id: NodeId, old: Rect, visited: &mut HashSet<NodeId>) {
+    if !visited.insert(parent_id) {
+        return;
+    }
+    let (new, children) = match scene.get_node(parent_id) {
+        Some(n) if n.layout.mode == LayoutMode::None => (n.bounds(), n.children.clone()),
+        _ => return,
+    };
+    for child_id in children {
+        let (child_old, constraints) = match scene.get_node(child_id) {
+            Some(c) => (c.bounds(), c.constraints.clone()),
+            None => continue,
+        };
+        let (x, width) = constrain_axis(&constraints.horizontal, child_old.x, child_old.width, old.x, old.width, new.x, new.width);
+        let (y, height) = constrain_axis(&constraints.vertical, child_old.y, child_old.height, old.y, old.height, new.y, new.height);
+        if constraints.horizontal == Constraint::Scale || constraints.vertical == Constraint::Scale {
+            // Scaled children zoom with the frame, contents included
+            scene.scale_subtree(child_id, child_old, Rect { x, y, width, height });
+            continue;
+        }
+        scene.translate_subtree(child_id, x - child_old.x, y - child_old.y);
+        if width != child_old.width || height != child_old.height {
+            let moved = Rect { x, y, ..child_old };
+            scene.resize_node(child_id, width, height);
+            constrain_children(scene, child_id, moved, visited);
         }
     }
 }
+
+/// New (position, size) of a child along one axis given the parent's old and new span
+fn constrain_axis(constraint: &Constraint, pos: f64, size: f64, old_pos: f64, old_size: f64, new_pos: f64, new_size: f64) -> (f64, f64) {
+    let offset = pos - old_pos;
+    match constraint {
+        Constraint::Start => (new_pos + offset, size),
+        Constraint::End => (new_pos + new_size - (old_size - offset), size),
+        Constraint::Stretch => (new_pos + offset, (size + new_size - old_size).max(1.0)),
+        Constraint::Center => (new_pos + offset + (new_size - old_size) / 2.0, size),
+        Constraint::Scale if old_size > 0.0 => {
+            let k = new_size / old_size;
+            (new_pos + offset * k, (size * k).max(1.0))
+        }
+        Constraint::Scale => (new_pos + offset, size),
+    }
+}
+
+/// Max deviation (px) tolerated when deciding whether children sit in a clean row/column
+const INFER_TOLERANCE: f64 = 1.0;
+
+/// Detect whether a free-form container's visible children form a single row or column
+/// with consistent gaps. Returns the equivalent flex layout and the children in flow order.
+pub fn infer_flex(scene: &Scene, parent_id: NodeId) -> Option<(Layout, Vec<NodeId>)> {
+    let parent = scene.get_node(parent_id)?;
+    if parent.layout.mode != LayoutMode::None {
+        return None;
+    }
+    let mut items: Vec<(NodeId, f64, f64, f64, f64)> = parent.children.iter()
+        .filter_map(|&id| scene.get_node(id))
+        .filter(|n| n.visible)
+        .map(|n| (n.id, n.x, n.y, n.width, n.height))
+        .collect();
+    if items.len() < 2 {
+        return None;
+    }
+
+    let mut result = None;
+    for direction in [FlexDirection::Row, FlexDirection::Column] {
+        let is_row = direction == FlexDirection::Row;
+        // (main start, main size, cross start, cross size)
+        let axes = |&(_, x, y, w, h): &(NodeId, f64, f64, f64, f64)| {
+            if is_row { (x, w, y, h) } else { (y, h, x, w) }
+        };
+        items.sort_by(|a, b| axes(a).0.total_cmp(&axes(b).0));
+
+        // Every child must share part of the cross axis, or this isn't a single line
+        let cross_start = items.iter().map(|i| axes(i).2).fold(f64::NEG_INFINITY, f64::max);
+        let cross_end = items.iter().map(|i| axes(i).2 + axes(i).3).fold(f64::INFINITY, f64::min);
+        if cross_start >= cross_end {
+            continue;
+        }
+
+        let gaps: Vec<f64> = items.windows(2)
+            .map(|w| axes(&w[1]).0 - (axes(&w[0]).0 + axes(&w[0]).1))
+            .collect();
+        let min_gap = gaps.iter().cloned().fold(f64::INFINITY, f64::min);
+        let max_gap = gaps.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+        if min_gap < -INFER_TOLERANCE || max_gap - min_gap > INFER_TOLERANCE {
+            continue;
+        }
+        let gap = (gaps.iter().sum::<f64>() / gaps.len() as f64).max(0.0);
+
+        let aligned = |f: &dyn Fn(f64, f64) -> f64| {
+            let vals: Vec<f64> = items.iter().map(|i| f(axes(i).2, axes(i).3)).collect();
+            let lo = vals.iter().cloned().fold(f64::INFINITY, f64::min);
+            let hi = vals.iter().cloned().fold(f64::NEG_INFINITY, f64::max);
+            hi - lo <= INFER_TOLERANCE
+        };
+        let align_items = if aligned(&|start, _| start) {
+            Align::Start
+        } else if aligned(&|start, size| start + size / 2.0) {
+            Align::Center
+        } else if aligned(&|start, size| start + size) {
+            Align::End
+        } else {
+            Align::Start
+        };
+
+        let min_x = items.iter().map(|i| i.1).fold(f64::INFINITY, f64::min);
+        let min_y = items.iter().map(|i| i.2).fold(f64::INFINITY, f64::min);
+        let max_x = items.iter().map(|i| i.1 + i.3).fold(f64::NEG_INFINITY, f64::max);
+        let max_y = items.iter().map(|i| i.2 + i.4).fold(f64::NEG_INFINITY, f64::max);
+        let layout = Layout {
+            mode: LayoutMode::Flex,
+            direction,
+            align_items,
+            gap,
+            padding_top: (min_y - parent.y).max(0.0),
+            padding_right: (parent.x + parent.width - max_x).max(0.0),
+            padding_bottom: (parent.y + parent.height - max_y).max(0.0),
+            padding_left: (min_x - parent.x).max(0.0),
+            ..parent.layout.clone()
+        };
+        result = Some((layout, items.iter().map(|i| i.0).collect::<Vec<_>>()));
+        break;
+    }
+    let (layout, mut order) = result?;
+
+    // Hidden children keep their slots at the end so nothing is dropped
+    let hidden: Vec<NodeId> = parent.children.iter().filter(|id| !order.contains(id)).cloned().collect();
+    order.extend(hidden);
+    Some((layout, order))
+}
@@ -6,10 +6,24 @@ mod render;
 mod hit_test;
 pub mod component;
 mod layout;
+mod export;
+mod css;
+mod jsx;
+mod svg;
+mod styles;
 
 use wasm_bindgen::prelude::*;
 use web_sys::CanvasRenderingContext2d;
-use crate::node::{Node, NodeKind, Fill, Stroke, LayoutMode, FlexDirection, Align, Justify, FlexWrap, TextSizing, TextAlign, FontStyle};
+use crate::node::{Node, NodeKind, ConnectorKind, Constraint, Constraints, Effect, ExportFormat, ExportSetting, Fill, Gradient, GradientStop, Stroke, LayoutMode, FlexDirection, Align, Justify, FlexWrap, TextSizing, TextAlign, TextDirection, FontStyle, ListStyle};
+
+fn copy_text_style_into(node: &mut Node, style: &TextStyle) {
+    if let NodeKind::Text { font_family, font_size, font_weight, line_height, .. } = &mut node.kind {
+        *font_family = style.font_family.clone();
+        *font_size = style.font_size;
+        *font_weight = style.font_weight;
+        *line_height = style.line_height;
+    }
+}
 
 fn parse_align(s: &str) -> Align {
     match s {
@@ -30,10 +44,11 @@ fn parse_justify(s: &str) -> Justify {
         _ => Justify::Start,
     }
 }
-use crate::scene::Scene;
+use crate::scene::{Axis, Guide, Scene};
+use crate::styles::{StyleStore, TextStyle};
 use crate::render::Renderer;
-use crate::types::{Color, Point};
-use crate::component::{ComponentStore, VariantProp, VariantPropType, VariantValue, VariantData, VariantKey, SlotDef, InstanceData, NodeOverrides};
+use crate::types::{Color, Point, Rect};
+use crate::component::{ComponentStore, VariantProp, VariantPropType, VariantValue, VariantData, VariantKey, SlotDef, InstanceData, NodeOverrides, ExposedProp, ExposedPropKind};
 use crate::node::Note;
 
 #[wasm_bindgen]
@@ -44,6 +59,15 @@ pub struct Engine {
     components: ComponentStore,
     undo_stack: Vec<String>,
     redo_stack: Vec<String>,
+    selection_sets: std::collections::HashMap<String, Vec<u64>>,
+    styles: StyleStore,
+    path_edit_mode: bool,
+    /// Set when web fonts finish loading; cached text metrics are dropped on the next render
+    fonts_dirty: bool,
+    /// Why the last batch call skipped input, or empty
+    last_error: String,
+    /// Grid size positions snap to when moving (0 = off)
+    snap_grid: f64,
 }
 
 #[wasm_bindgen]
@@ -58,13 +82,24 @@ impl Engine {
             components: ComponentStore::new(),
             undo_stack: Vec::new(),
             redo_stack: Vec::new(),
+            selection_sets: std::collections::HashMap::new(),
+            styles: StyleStore::default(),
+            path_edit_mode: false,
+            fonts_dirty: false,
+            last_error: String::new(),
+            snap_grid: 0.0,
         }
     }
 
     pub fn render(&mut self, ctx: &CanvasRenderingContext2d) {
+        if self.fonts_dirty {
+            self.renderer.invalidate_text_metrics();
+            self.fonts_dirty = false;
+        }
         self.renderer.measure_text_nodes(ctx, &mut self.scene);
+        self.renderer.measure_images(&mut self.scene);
         layout::compute_layouts(&mut self.scene);
-        self.renderer.render(ctx, &self.scene, self.editing_node);
+        self.renderer.render(ctx, &self.scene, self.editing_node, self.path_edit_mode);
     }
 
     // =============================================
@@ -73,7 +108,17 @@ impl Engine {
 
     /// Save current scene state to undo stack. Call before any mutation.
     pub fn push_undo(&mut self) {
-        let snapshot = serde_json::to_string(&self.scene.export()).unwrap_or_default();
+        let snapshot = self.undo_snapshot();
+        self.push_undo_snapshot(snapshot);
+    }
+
+    /// The scene as `push_undo` records it, for operations that decide afterwards whether they
+    /// changed anything
+    fn undo_snapshot(&self) -> String {
+        serde_json::to_string(&self.scene.export()).unwrap_or_default()
+    }
+
+    fn push_undo_snapshot(&mut self, snapshot: String) {
         // Deduplicate: skip if identical to top of stack
         if self.undo_stack.last().map(|s| s.as_str()) == Some(snapshot.as_str()) {
             return;
@@ -134,10 +179,81 @@ impl Engine {
         !self.redo_stack.is_empty()
     }
 
+    /// Total number of history states (undo entries + current + redo entries)
+    pub fn history_len(&self) -> usize {
+        self.undo_stack.len() + 1 + self.redo_stack.len()
+    }
+
+    /// Index of the current state within the history (0 = oldest)
+    pub fn history_position(&self) -> usize {
+        self.undo_stack.len()
+    }
+
+    /// Jump to a history state by index, stepping through undo/redo. Returns true if moved.
+    pub fn go_to_history(&mut self, index: usize) -> bool {
+        if index >= self.history_len() || index == self.history_position() {
+            return false;
+        }
+        while self.history_position() > index {
+            if !self.undo() { break; }
+        }
+        while self.history_position() < index {
+            if !self.redo() { break; }
+        }
+        true
+    }
+
+    /// Export undo/redo stacks as JSON so the host can persist them (e.g. localStorage)
+    pub fn export_history(&self) -> String {
+        serde_json::json!({
+            "undo": self.undo_stack,
+            "redo": self.redo_stack,
+        }).to_string()
+    }
+
+    /// Restore undo/redo stacks from `export_history` output. Keeps at most 100 entries per
+    /// stack. Returns false, leaving history untouched, if any entry isn't a valid scene.
+    pub fn import_history(&mut self, json: &str) -> bool {
+        let val: serde_json::Value = match serde_json::from_str(json) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        let read_stack = |key: &str| -> Option<Vec<String>> {
+            serde_json::from_value(val.get(key)?.clone()).ok()
+        };
+        let (mut undo, mut redo) = match (read_stack("undo"), read_stack("redo")) {
+            (Some(u), Some(r)) => (u, r),
+            _ => return false,
+        };
+        if !undo.iter().chain(&redo).all(|s| serde_json::from_str::<crate::scene::SceneData>(s).is_ok()) {
+            return false;
+        }
+        // Cap at 100 entries each, dropping the states furthest from the current one
+        for stack in [&mut undo, &mut redo] {
+            if stack.len() > 100 {
+                stack.drain(..stack.len() - 100);
+            }
+        }
+        self.undo_stack = undo;
+        self.redo_stack = redo;
+        true
+    }
+
     pub fn set_editing(&mut self, id: Option<u64>) {
         self.editing_node = id;
     }
 
+    /// Toggle interpolation of scaled image fills (off for crisp pixel art)
+    pub fn set_image_smoothing(&mut self, enabled: bool) {
+        self.renderer.set_image_smoothing(enabled);
+    }
+
+    /// Call once web fonts finish loading (`document.fonts.ready`): the next render
+    /// re-measures every text node and recomputes layout with the real font metrics
+    pub fn mark_fonts_dirty(&mut self) {
+        self.fonts_dirty = true;
+    }
+
     pub fn resize(&mut self, width: f64, height: f64) {
         self.renderer.canvas_width = width;
         self.renderer.canvas_height = height;
@@ -157,6 +273,23 @@ impl Engine {
         self.scene.add_node(node)
     }
 
+    /// Add a regular polygon with `sides` (at least 3) inscribed in the given box
+    pub fn add_polygon(&mut self, x: f64, y: f64, w: f64, h: f64, sides: u32) -> u64 {
+        let mut node = Node::new(0, NodeKind::Polygon { sides: sides.max(3) });
+        node.x = x; node.y = y; node.width = w; node.height = h;
+        node.name = format!("Polygon {}", self.scene.node_count() + 1);
+        self.scene.add_node(node)
+    }
+
+    /// Add a star with `points` tips (at least 3); `inner_ratio` is the inner radius as a
+    /// fraction of the outer one
+    pub fn add_star(&mut self, x: f64, y: f64, w: f64, h: f64, points: u32, inner_ratio: f64) -> u64 {
+        let mut node = Node::new(0, NodeKind::Star { points: points.max(3), inner_radius_ratio: inner_ratio.clamp(0.0, 1.0) });
+        node.x = x; node.y = y; node.width = w; node.height = h;
+        node.name = format!("Star {}", self.scene.node_count() + 1);
+        self.scene.add_node(node)
+    }
+
     pub fn add_text(&mut self, x: f64, y: f64, content: &str, font_size: f64) -> u64 {
         let mut node = Node::new(0, NodeKind::Text {
             content: content.to_string(),
@@ -166,12 +299,16 @@ impl Engine {
             text_align: TextAlign::default(),
             font_weight: 400,
             font_style: FontStyle::default(),
+            paragraph_gap: 0.0,
+            list_style: ListStyle::default(),
+            stroke_under: false,
+            direction: TextDirection::Ltr,
         });
         node.x = x; node.y = y;
         node.width = content.len() as f64 * font_size * 0.6;
         node.height = font_size * 1.2;
         node.name = format!("Text {}", self.scene.node_count() + 1);
-        node.fill = Some(Fill { color: Color::black() });
+        node.fills = vec![Fill::solid(Color::black())];
         self.scene.add_node(node)
     }
 
@@ -179,38 +316,447 @@ impl Engine {
         let mut node = Node::new(0, NodeKind::Frame);
         node.x = x; node.y = y; node.width = w; node.height = h;
         node.name = format!("Frame {}", self.scene.node_count() + 1);
-        node.fill = Some(Fill { color: Color::white() });
+        node.fills = vec![Fill::solid(Color::white())];
+        self.scene.add_node(node)
+    }
+
+    /// Place a raster image (data URL or object URL) at (x, y) drawn at w × h. The natural size
+    /// stays 0 until the next render finds the image decoded. Returns 0 for an empty src or
+    /// non-positive size.
+    pub fn add_image(&mut self, x: f64, y: f64, src: &str, w: f64, h: f64) -> u64 {
+        if src.is_empty() || w <= 0.0 || h <= 0.0 {
+            return 0;
+        }
+        let mut node = Node::new(0, NodeKind::Image { src: src.to_string(), natural_width: 0.0, natural_height: 0.0 });
+        node.x = x; node.y = y; node.width = w; node.height = h;
+        node.name = format!("Image {}", self.scene.node_count() + 1);
+        node.fills.clear();
+        node.aspect_locked = true;
+        self.scene.add_node(node)
+    }
+
+    /// Add a path from a JSON array of scene-space points: [{"x":0,"y":0}, ...]
+    pub fn add_path(&mut self, points_json: &str, closed: bool) -> u64 {
+        let points: Vec<Point> = match serde_json::from_str(points_json) {
+            Ok(p) => p,
+            Err(_) => return 0,
+        };
+        if points.is_empty() {
+            return 0;
+        }
+        let mut node = Node::new(0, NodeKind::Path { points, closed });
+        node.x = 0.0; node.y = 0.0;
+        node.normalize_path();
+        node.name = format!("Path {}", self.scene.node_count() + 1);
+        if !closed {
+            node.fills.clear();
+        }
+        node.stroke = Some(Stroke { color: Color::black(), width: 2.0, dash: vec![] });
         self.scene.add_node(node)
     }
 
+    /// Add a straight line from (x1, y1) to (x2, y2), without arrowheads
+    pub fn add_line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64) -> u64 {
+        let (dx, dy) = (x2 - x1, y2 - y1);
+        let mut node = Node::new(0, NodeKind::Line { x2: dx, y2: dy, arrow_start: false, arrow_end: false });
+        node.x = x1.min(x2); node.y = y1.min(y2);
+        node.width = dx.abs().max(1.0); node.height = dy.abs().max(1.0);
+        node.name = format!("Line {}", self.scene.node_count() + 1);
+        node.fills.clear();
+        node.stroke = Some(Stroke { color: Color::black(), width: 2.0, dash: vec![] });
+        self.scene.add_node(node)
+    }
+
+    /// Toggle the arrowheads at a line's start and end
+    pub fn set_line_arrows(&mut self, id: u64, start: bool, end: bool) -> bool {
+        match self.scene.get_node_mut(id).map(|n| &mut n.kind) {
+            Some(NodeKind::Line { arrow_start, arrow_end, .. }) => {
+                *arrow_start = start;
+                *arrow_end = end;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Connect two nodes with an arrow routed between their edges ("straight" or "orthogonal").
+    /// Returns 0 if either endpoint is missing or they're the same node.
+    pub fn add_connector(&mut self, from: u64, to: u64, kind: &str) -> u64 {
+        if from == to || self.scene.get_node(from).is_none() || self.scene.get_node(to).is_none() {
+            return 0;
+        }
+        let kind = match kind {
+            "orthogonal" => ConnectorKind::Orthogonal,
+            _ => ConnectorKind::Straight,
+        };
+        let mut node = Node::new(0, NodeKind::Connector { from, to, kind, points: vec![] });
+        node.name = format!("Connector {}", self.scene.node_count() + 1);
+        node.fills.clear();
+        node.stroke = Some(Stroke { color: Color::black(), width: 2.0, dash: vec![] });
+        let id = self.scene.add_node(node);
+        layout::route_connectors(&mut self.scene);
+        id
+    }
+
+    /// Create many nodes in one call from a JSON array of specs:
+    /// [{"kind":"rect"|"ellipse"|"frame"|"text", "x", "y", "width", "height",
+    ///   "fill"?: "#hex", "parent"?: id, "name"?, "content"?, "font_size"?}, ...]
+    /// Returns the created ids in order as JSON. Invalid specs are skipped and described in
+    /// `last_error`. One undo step.
+    pub fn add_nodes_batch(&mut self, specs_json: &str) -> String {
+        self.last_error.clear();
+        let specs: Vec<NodeSpec> = match serde_json::from_str(specs_json) {
+            Ok(s) => s,
+            Err(e) => {
+                self.last_error = format!("invalid specs: {}", e);
+                return "[]".to_string();
+            }
+        };
+        let before = self.undo_snapshot();
+        let mut ids = vec![];
+        let mut errors = vec![];
+        for (i, spec) in specs.iter().enumerate() {
+            let fill = match spec.fill.as_deref().map(Color::from_hex) {
+                Some(None) => {
+                    errors.push(format!("spec {}: bad fill color", i));
+                    continue;
+                }
+                other => other.flatten(),
+            };
+            if let Some(pid) = spec.parent {
+                if self.scene.get_node(pid).is_none() {
+                    errors.push(format!("spec {}: unknown parent {}", i, pid));
+                    continue;
+                }
+            }
+            let sized = spec.width > 0.0 && spec.height > 0.0;
+            let id = match spec.kind.as_str() {
+                "rect" if sized => self.add_rect(spec.x, spec.y, spec.width, spec.height),
+                "ellipse" if sized => self.add_ellipse(spec.x, spec.y, spec.width, spec.height),
+                "frame" if sized => self.add_frame(spec.x, spec.y, spec.width, spec.height),
+                "text" => self.add_text(spec.x, spec.y, spec.content.as_deref().unwrap_or(""), spec.font_size.unwrap_or(16.0)),
+                "rect" | "ellipse" | "frame" => {
+                    errors.push(format!("spec {}: width and height must be positive", i));
+                    continue;
+                }
+                other => {
+                    errors.push(format!("spec {}: unknown kind \"{}\"", i, other));
+                    continue;
+                }
+            };
+            if let Some(node) = self.scene.get_node_mut(id) {
+                if let Some(color) = fill {
+                    node.set_top_fill_color(color);
+                }
+                if let Some(name) = &spec.name {
+                    node.name = name.clone();
+                }
+            }
+            if spec.parent.is_some() {
+                self.scene.reparent(id, spec.parent);
+            }
+            ids.push(id);
+        }
+        if !ids.is_empty() {
+            self.push_undo_snapshot(before);
+        }
+        self.last_error = errors.join("; ");
+        serde_json::to_string(&ids).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Why the last batch call skipped some of its input ("" if it didn't)
+    pub fn last_error(&self) -> String {
+        self.last_error.clone()
+    }
+
     pub fn remove_node(&mut self, id: u64) {
         self.scene.remove_node(id);
     }
 
-    pub fn move_node(&mut self, id: u64, dx: f64, dy: f64) {
+    /// Snap moved positions to multiples of `size` and draw the canvas grid at that size;
+    /// 0 (or less) turns snapping off. Returns false (leaving the grid as is) for NaN or infinity.
+    pub fn set_snap_grid(&mut self, size: f64) -> bool {
+        if !size.is_finite() {
+            return false;
+        }
+        self.snap_grid = size.max(0.0);
+        self.renderer.grid_size = (self.snap_grid > 0.0).then_some(self.snap_grid);
+        true
+    }
+
+    /// Round a coordinate to the snap grid, if one is set
+    fn snap(&self, v: f64) -> f64 {
+        if self.snap_grid > 0.0 { (v / self.snap_grid).round() * self.snap_grid } else { v }
+    }
+
+    /// Move a node by (dx, dy), landing its top-left on the snap grid when one is set.
+    /// Locked nodes are left untouched unless `force` is set.
+    pub fn move_node(&mut self, id: u64, dx: f64, dy: f64, force: bool) -> bool {
+        let Some(node) = self.scene.get_node(id) else {
+            return false;
+        };
+        let (x, y) = (node.x, node.y);
+        let (dx, dy) = (self.snap(x + dx) - x, self.snap(y + dy) - y);
+        self.move_node_raw(id, dx, dy, force)
+    }
+
+    /// Move a node by exactly (dx, dy), ignoring the snap grid.
+    /// Locked nodes are left untouched unless `force` is set.
+    pub fn move_node_raw(&mut self, id: u64, dx: f64, dy: f64, force: bool) -> bool {
+        if !force && self.scene.is_locked(id) {
+            return false;
+        }
         self.scene.move_node(id, dx, dy);
+        true
     }
 
-    pub fn resize_node(&mut self, id: u64, w: f64, h: f64) {
+    /// Resize a node. Locked nodes are left untouched unless `force` is set.
+    pub fn resize_node(&mut self, id: u64, w: f64, h: f64, force: bool) -> bool {
+        if !force && self.scene.is_locked(id) {
+            return false;
+        }
+        let old = match self.scene.get_node(id) {
+            Some(node) => node.bounds(),
+            None => return false,
+        };
         self.scene.resize_node(id, w, h);
+        // Free-form frames reflow their children by constraints
+        if matches!(self.scene.get_node(id).map(|n| &n.kind), Some(NodeKind::Frame)) {
+            layout::apply_constraints(&mut self.scene, id, old);
+        }
+        true
+    }
+
+    /// Resize like `resize_node`, but an aspect-locked node keeps its current width:height
+    /// ratio, following whichever dimension changed more.
+    pub fn resize_node_keep_ratio(&mut self, id: u64, w: f64, h: f64) -> bool {
+        let Some(node) = self.scene.get_node(id) else {
+            return false;
+        };
+        let (mut w, mut h) = (w, h);
+        if node.aspect_locked && node.width > 0.0 && node.height > 0.0 {
+            let ratio = node.width / node.height;
+            if (w / node.width - 1.0).abs() >= (h / node.height - 1.0).abs() {
+                h = w / ratio;
+            } else {
+                w = h * ratio;
+            }
+        }
+        self.resize_node(id, w, h, false)
+    }
+
+    /// Lock or unlock proportional resizing for a node
+    pub fn set_aspect_locked(&mut self, id: u64, locked: bool) {
+        if let Some(node) = self.scene.get_node_mut(id) {
+            node.aspect_locked = locked;
+        }
     }
 
-    pub fn set_node_position(&mut self, id: u64, x: f64, y: f64) {
+    /// Apply an interactive resize: drag handle `handle_index` (0-3 corners TL, TR, BL, BR;
+    /// 4-7 edges top, right, bottom, left) to a scene-space pointer position. Rotated nodes are
+    /// resized in their own frame; min/max sizes and frame constraints apply as for `resize_node`.
+    /// On unrotated nodes the dragged edges snap to the snap grid and the opposite ones stay put.
+    /// Locked nodes (or children of locked ones) are left untouched unless `force` is set.
+    #[allow(clippy::too_many_arguments)]
+    pub fn resize_from_handle(&mut self, id: u64, handle_index: usize, scene_x: f64, scene_y: f64, keep_ratio: bool, from_center: bool, force: bool) -> bool {
+        if !force && self.scene.is_locked(id) {
+            return false;
+        }
+        let Some(node) = self.scene.get_node(id) else {
+            return false;
+        };
+        let old = node.bounds();
+        let (old_pivot, origin, rotation) = (node.pivot(), node.transform_origin, node.rotation);
+        let keep_ratio = keep_ratio || node.aspect_locked;
+        let local = if rotation == 0.0 {
+            Point { x: self.snap(scene_x), y: self.snap(scene_y) }
+        } else {
+            node.unrotate_point(Point { x: scene_x, y: scene_y })
+        };
+        let Some(new) = transform::resize_from_handle(old, handle_index, local, keep_ratio, from_center) else {
+            return false;
+        };
+        // Min/max limits win over the pointer; re-place so the anchored edge stays put
+        let (w, h) = (node.clamp_width(new.width), node.clamp_height(new.height));
+        let Some(new) = transform::place_resized(old, handle_index, from_center, w, h) else {
+            return false;
+        };
         if let Some(node) = self.scene.get_node_mut(id) {
-            node.x = x;
-            node.y = y;
+            node.x = new.x;
+            node.y = new.y;
+            node.width = new.width;
+            node.height = new.height;
+        }
+        if matches!(self.scene.get_node(id).map(|n| &n.kind), Some(NodeKind::Frame)) {
+            layout::apply_constraints(&mut self.scene, id, old);
+        }
+        // The box was resized in the node's unrotated frame, but the pivot moved with it, which
+        // would swing the anchored corner on screen. Shift by how the pivot move looks rotated.
+        if rotation != 0.0 {
+            let (dx, dy) = (new.x + new.width * origin.0 - old_pivot.0, new.y + new.height * origin.1 - old_pivot.1);
+            let (sin, cos) = rotation.sin_cos();
+            self.scene.translate_subtree(id, dx * cos - dy * sin - dx, dx * sin + dy * cos - dy);
+        }
+        true
+    }
+
+    /// Set a node's position, snapped to the snap grid when one is set.
+    /// Locked nodes are left untouched unless `force` is set.
+    pub fn set_node_position(&mut self, id: u64, x: f64, y: f64, force: bool) -> bool {
+        if !force && self.scene.is_locked(id) {
+            return false;
+        }
+        let (x, y) = (self.snap(x), self.snap(y));
+        match self.scene.get_node_mut(id) {
+            Some(node) => {
+                node.x = x;
+                node.y = y;
+                true
+            }
+            None => false,
         }
     }
 
     pub fn set_fill_color(&mut self, id: u64, r: u8, g: u8, b: u8, a: f64) {
         if let Some(node) = self.scene.get_node_mut(id) {
-            node.fill = Some(Fill { color: Color { r, g, b, a } });
+            node.set_top_fill_color(Color { r, g, b, a });
+            node.fill_style = None;
+        }
+    }
+
+    /// `set_fill_color` from a `#rgb`, `#rrggbb`, or `#rrggbbaa` string. False if it doesn't parse.
+    pub fn set_fill_hex(&mut self, id: u64, hex: &str) -> bool {
+        match Color::from_hex(hex) {
+            Some(c) if self.scene.get_node(id).is_some() => {
+                self.set_fill_color(id, c.r, c.g, c.b, c.a);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// `set_stroke` from a hex color string. False if it doesn't parse.
+    pub fn set_stroke_hex(&mut self, id: u64, hex: &str, width: f64) -> bool {
+        match Color::from_hex(hex) {
+            Some(c) if self.scene.get_node(id).is_some() => {
+                self.set_stroke(id, c.r, c.g, c.b, c.a, width);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Paint the top fill with a radial gradient. `stops_json` is [{"offset":0,"color":"#hex"}, ...];
+    /// the center (cx, cy) is in 0..1 node space and `radius` a fraction of the larger side.
+    pub fn set_fill_radial_gradient(&mut self, id: u64, stops_json: &str, cx: f64, cy: f64, radius: f64) -> bool {
+        let raw: Vec<serde_json::Value> = match serde_json::from_str(stops_json) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        let stops: Option<Vec<GradientStop>> = raw.iter().map(|s| {
+            let offset = s.get("offset")?.as_f64()?;
+            let color = Color::from_hex(s.get("color")?.as_str()?)?;
+            Some(GradientStop { offset, color })
+        }).collect();
+        let stops = match stops {
+            Some(s) if !s.is_empty() && radius > 0.0 => s,
+            _ => return false,
+        };
+        let Some(node) = self.scene.get_node_mut(id) else { return false };
+        if node.fills.is_empty() {
+            node.fills.push(Fill::solid(Color::transparent()));
         }
+        let fill = node.fills.last_mut().unwrap();
+        fill.image = None;
+        fill.gradient = Some(Gradient::Radial { stops, center: Point { x: cx, y: cy }, radius });
+        // Hand-edited fills drop their style link so restyling doesn't undo the edit
+        node.fill_style = None;
+        true
     }
 
     pub fn set_stroke(&mut self, id: u64, r: u8, g: u8, b: u8, a: f64, width: f64) {
         if let Some(node) = self.scene.get_node_mut(id) {
-            node.stroke = Some(Stroke { color: Color { r, g, b, a }, width });
+            let dash = node.stroke.take().map(|s| s.dash).unwrap_or_default();
+            node.stroke = Some(Stroke { color: Color { r, g, b, a }, width, dash });
+        }
+    }
+
+    /// Add a solid fill layer on top of the existing ones. Returns its index.
+    pub fn add_fill(&mut self, id: u64, r: u8, g: u8, b: u8, a: f64) -> i32 {
+        match self.scene.get_node_mut(id) {
+            Some(node) => {
+                node.fills.push(Fill::solid(Color { r, g, b, a }));
+                node.fill_style = None;
+                node.fills.len() as i32 - 1
+            }
+            None => -1,
+        }
+    }
+
+    /// Remove the fill layer at `index`
+    pub fn remove_fill(&mut self, id: u64, index: usize) -> bool {
+        match self.scene.get_node_mut(id) {
+            Some(node) if index < node.fills.len() => {
+                node.fills.remove(index);
+                node.fill_style = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Set one fill layer's opacity (0..1), independent of the node's opacity
+    pub fn set_fill_opacity(&mut self, id: u64, index: usize, opacity: f64) -> bool {
+        match self.scene.get_node_mut(id) {
+            Some(node) if index < node.fills.len() => {
+                node.fills[index].opacity = opacity.clamp(0.0, 1.0);
+                node.fill_style = None;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Set the stroke dash pattern from a JSON array of lengths ("[]" for solid)
+    pub fn set_stroke_dash(&mut self, id: u64, dash_json: &str) -> bool {
+        let dash: Vec<f64> = match serde_json::from_str(dash_json) {
+            Ok(d) => d,
+            Err(_) => return false,
+        };
+        match self.scene.get_node_mut(id).and_then(|n| n.stroke.as_mut()) {
+            Some(stroke) => {
+                stroke.dash = dash;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Rotate a node by `delta` radians about its transform origin
+    pub fn rotate_node(&mut self, id: u64, delta: f64) {
+        if let Some(node) = self.scene.get_node_mut(id) {
+            node.rotation += delta;
+        }
+    }
+
+    /// Set the rotation pivot in normalized node space (0..1); (0.5, 0.5) is the center
+    pub fn set_transform_origin(&mut self, id: u64, ox: f64, oy: f64) {
+        if let Some(node) = self.scene.get_node_mut(id) {
+            node.transform_origin = (ox.clamp(0.0, 1.0), oy.clamp(0.0, 1.0));
+        }
+    }
+
+    /// Set rotation in radians, about the node's transform origin
+    pub fn set_rotation(&mut self, id: u64, radians: f64) {
+        if let Some(node) = self.scene.get_node_mut(id) {
+            node.rotation = radians;
+        }
+    }
+
+    /// Set rotation (radians), snapped to the nearest multiple of `snap_degrees` (0 = no snapping)
+    pub fn set_rotation_snapped(&mut self, id: u64, radians: f64, snap_degrees: f64) {
+        if let Some(node) = self.scene.get_node_mut(id) {
+            node.rotation = transform::snap_angle(radians, snap_degrees);
         }
     }
 
@@ -220,23 +766,98 @@ impl Engine {
         }
     }
 
+    pub fn set_corner_smoothing(&mut self, id: u64, smoothing: f64) {
+        if let Some(node) = self.scene.get_node_mut(id) {
+            node.corner_smoothing = smoothing.clamp(0.0, 1.0);
+        }
+    }
+
     pub fn set_opacity(&mut self, id: u64, opacity: f64) {
         if let Some(node) = self.scene.get_node_mut(id) {
             node.opacity = opacity.clamp(0.0, 1.0);
         }
     }
 
+    /// Set how a node blends with what's beneath it ("normal", "multiply", "screen", "overlay", ...).
+    /// Unknown modes are ignored and return false.
+    pub fn set_blend_mode(&mut self, id: u64, mode: &str) -> bool {
+        if !node::BLEND_MODES.contains(&mode) {
+            return false;
+        }
+        match self.scene.get_node_mut(id) {
+            Some(node) => {
+                node.blend_mode = mode.to_string();
+                true
+            }
+            None => false,
+        }
+    }
+
     pub fn set_node_name(&mut self, id: u64, name: &str) {
         if let Some(node) = self.scene.get_node_mut(id) {
             node.name = name.to_string();
         }
     }
 
+    /// Rename nodes from a pattern with `{n}` (1-based position in `ids_json`), `{kind}`, and
+    /// `{name}` (current name) tokens. One undo step; returns the number renamed.
+    pub fn rename_by_pattern(&mut self, ids_json: &str, pattern: &str) -> usize {
+        let ids: Vec<u64> = serde_json::from_str(ids_json).unwrap_or_default();
+        let names: Vec<(u64, String)> = ids.iter().enumerate()
+            .filter_map(|(i, &id)| {
+                let node = self.scene.get_node(id)?;
+                let name = pattern
+                    .replace("{n}", &(i + 1).to_string())
+                    .replace("{kind}", node.kind_label())
+                    .replace("{name}", &node.name);
+                Some((id, name))
+            })
+            .collect();
+        if names.is_empty() {
+            return 0;
+        }
+        self.push_undo();
+        for (id, name) in &names {
+            if let Some(node) = self.scene.get_node_mut(*id) {
+                node.name = name.clone();
+            }
+        }
+        names.len()
+    }
+
+    /// Name every node whose name is empty or still the raw kind debug string as "{Kind} {n}",
+    /// numbering per kind after any existing "{Kind} N" names. Returns how many were renamed.
+    pub fn auto_name_unnamed(&mut self) -> usize {
+        let ids = self.scene.all_node_ids();
+        let mut next: std::collections::HashMap<&'static str, u32> = std::collections::HashMap::new();
+        let mut unnamed = vec![];
+        for &id in &ids {
+            let Some(node) = self.scene.get_node(id) else { continue };
+            let kind = node.kind_label();
+            if node.name.trim().is_empty() || node.name == format!("{:?}", node.kind) {
+                unnamed.push(id);
+            } else if let Some(n) = node.name.strip_prefix(kind).and_then(|rest| rest.strip_prefix(' ')?.parse::<u32>().ok()) {
+                let entry = next.entry(kind).or_insert(1);
+                *entry = (*entry).max(n + 1);
+            }
+        }
+        for &id in &unnamed {
+            if let Some(node) = self.scene.get_node_mut(id) {
+                let kind = node.kind_label();
+                let n = next.entry(kind).or_insert(1);
+                node.name = format!("{} {}", kind, n);
+                *n += 1;
+            }
+        }
+        unnamed.len()
+    }
+
     pub fn set_text_content(&mut self, id: u64, content: &str) {
         if let Some(node) = self.scene.get_node_mut(id) {
             if let NodeKind::Text { content: ref mut c, .. } = node.kind {
                 *c = content.to_string();
             }
+            node.estimate_text_height();
         }
     }
 
@@ -245,6 +866,7 @@ impl Engine {
             if let NodeKind::Text { ref mut font_size, .. } = node.kind {
                 *font_size = size;
             }
+            node.estimate_text_height();
         }
     }
 
@@ -268,6 +890,27 @@ impl Engine {
         }
     }
 
+    /// Allow clicking nodes that paint nothing (zero opacity, transparent fill, no stroke)
+    pub fn set_hit_invisible(&mut self, enabled: bool) {
+        self.scene.hit_invisible = enabled;
+    }
+
+    /// Clip a frame's children to its bounds (required for scrolling)
+    pub fn set_clip_content(&mut self, id: u64, clip: bool) {
+        if let Some(node) = self.scene.get_node_mut(id) {
+            node.clip_content = clip;
+            if !clip {
+                node.scroll_x = 0.0;
+                node.scroll_y = 0.0;
+            }
+        }
+    }
+
+    /// Scroll a clipped frame's content by (dx, dy), clamped to the overflow extent
+    pub fn scroll_frame(&mut self, id: u64, dx: f64, dy: f64) -> bool {
+        self.scene.scroll_frame(id, dx, dy)
+    }
+
     pub fn select(&mut self, id: u64) {
         self.scene.selection = vec![id];
     }
@@ -286,39 +929,578 @@ impl Engine {
         self.scene.selection.clone()
     }
 
-    pub fn hit_test(&self, screen_x: f64, screen_y: f64) -> Option<u64> {
-        let (sx, sy) = self.renderer.screen_to_scene(screen_x, screen_y);
-        self.scene.hit_test(Point { x: sx, y: sy })
+    /// Marquee select: replace the selection with the topmost visible, unlocked nodes fully
+    /// inside (`contain`) or touching the screen-space rectangle at (x, y) sized w × h
+    /// (negative sizes drag up/left). Returns the new selection.
+    pub fn select_in_rect(&mut self, x: f64, y: f64, w: f64, h: f64, contain: bool) -> Vec<u64> {
+        let (ax, ay) = self.renderer.screen_to_scene(x, y);
+        let (bx, by) = self.renderer.screen_to_scene(x + w, y + h);
+        let rect = Rect::from_two_points(Point { x: ax, y: ay }, Point { x: bx, y: by });
+        self.scene.selection = self.scene.nodes_in_rect(rect, contain);
+        self.scene.selection.clone()
     }
 
-    pub fn hit_test_handle(&self, screen_x: f64, screen_y: f64) -> i32 {
-        let (sx, sy) = self.renderer.screen_to_scene(screen_x, screen_y);
-        let handle_size = 8.0 / self.renderer.viewport.a;
-        for &id in &self.scene.selection {
-            if let Some(idx) = hit_test::hit_test_handles(&self.scene, id, Point { x: sx, y: sy }, handle_size) {
-                return idx as i32;
+    /// Replace the selection with every visible, unlocked node matching `id` on one attribute:
+    /// "fill" (top fill color), "stroke" (color and width), "kind", or "size". Colors compare
+    /// approximately. Returns the new selection as a JSON id array ("[]" if `id` or `by` is unknown).
+    pub fn select_similar(&mut self, id: u64, by: &str) -> String {
+        let Some(target) = self.scene.get_node(id) else { return "[]".to_string() };
+        let fill = |n: &Node| n.top_fill().filter(|f| f.image.is_none() && f.gradient.is_none()).map(|f| f.layer_color());
+        let matches: Box<dyn Fn(&Node) -> bool> = match by {
+            "fill" => {
+                let want = fill(target);
+                Box::new(move |n| match (fill(n), want) {
+                    (Some(a), Some(b)) => a.approx_eq(b),
+                    (a, b) => a.is_none() && b.is_none(),
+                })
             }
-        }
-        -1
+            "stroke" => {
+                let want = target.stroke.clone();
+                Box::new(move |n| match (&n.stroke, &want) {
+                    (Some(a), Some(b)) => a.color.approx_eq(b.color) && (a.width - b.width).abs() < 0.01,
+                    (a, b) => a.is_none() && b.is_none(),
+                })
+            }
+            "kind" => {
+                let want = target.kind_label();
+                Box::new(move |n| n.kind_label() == want)
+            }
+            "size" => {
+                let (w, h) = (target.width, target.height);
+                Box::new(move |n| (n.width - w).abs() < 0.5 && (n.height - h).abs() < 0.5)
+            }
+            _ => return "[]".to_string(),
+        };
+        self.scene.selection = self.scene.render_order().into_iter()
+            .filter(|&nid| !self.scene.is_locked(nid))
+            .filter(|&nid| self.scene.get_node(nid).map(|n| n.visible && matches(n)).unwrap_or(false))
+            .collect();
+        serde_json::to_string(&self.scene.selection).unwrap_or_else(|_| "[]".to_string())
     }
 
-    pub fn zoom(&mut self, delta: f64, cx: f64, cy: f64) {
-        self.renderer.zoom(delta, cx, cy);
+    /// Selected nodes that batch operations may touch: unlocked, and not already
+    /// moved along with a selected ancestor
+    fn editable_selection(&self) -> Vec<u64> {
+        let unlocked: Vec<u64> = self.scene.selection.iter()
+            .cloned()
+            .filter(|&id| self.scene.get_node(id).is_some() && !self.scene.is_locked(id))
+            .collect();
+        unlocked.iter()
+            .cloned()
+            .filter(|&id| !self.scene.has_ancestor_in(id, &unlocked))
+            .collect()
     }
 
-    pub fn pan(&mut self, dx: f64, dy: f64) {
-        self.renderer.pan(dx, dy);
+    /// Unlocked selected nodes whose position isn't owned by a parent's auto-layout
+    fn free_selection(&self) -> Vec<u64> {
+        self.editable_selection().into_iter()
+            .filter(|&id| {
+                let Some(node) = self.scene.get_node(id) else { return false };
+                let parent = node.parent.and_then(|p| self.scene.get_node(p));
+                node.layout_absolute || parent.map(|p| p.layout.mode == LayoutMode::None).unwrap_or(true)
+            })
+            .collect()
     }
 
-    /// Center the viewport on a scene-space point
-    pub fn pan_to(&mut self, scene_x: f64, scene_y: f64) {
-        let zoom = self.renderer.viewport.a;
-        let cx = self.renderer.canvas_width / 2.0;
-        let cy = self.renderer.canvas_height / 2.0;
+    /// Move every unlocked selected node (and its children) by (dx, dy)
+    pub fn move_selection(&mut self, dx: f64, dy: f64) {
+        for id in self.editable_selection() {
+            self.scene.translate_subtree(id, dx, dy);
+        }
+    }
+
+    /// Delete every unlocked selected node. Locked nodes stay selected. Returns the number removed.
+    pub fn delete_selection(&mut self) -> usize {
+        let ids = self.editable_selection();
+        for &id in &ids {
+            self.scene.remove_node(id);
+        }
+        self.scene.selection.retain(|id| !ids.contains(id));
+        ids.len()
+    }
+
+    /// Align unlocked selected nodes to their combined bounds: "left", "center" (or
+    /// "center-horizontal"), "right", "top", "middle" (or "center-vertical"), "bottom".
+    /// A single node aligns within its parent's content box instead. One undo step.
+    pub fn align_selection(&mut self, mode: &str) -> bool {
+        let mode = match mode {
+            "center-horizontal" => "center",
+            "center-vertical" => "middle",
+            "left" | "center" | "right" | "top" | "middle" | "bottom" => mode,
+            _ => return false,
+        };
+        let ids = self.editable_selection();
+        let boxes: Vec<(u64, Rect)> = ids.iter()
+            .filter_map(|&id| self.scene.get_node(id).map(|n| (id, n.rotated_bounds())))
+            .collect();
+        let bounds = match boxes.as_slice() {
+            [] => return false,
+            [(id, _)] => {
+                let parent = self.scene.get_node(*id).and_then(|n| n.parent).and_then(|p| self.scene.get_node(p));
+                match parent {
+                    Some(p) => Rect {
+                        x: p.x + p.layout.padding_left,
+                        y: p.y + p.layout.padding_top,
+                        width: p.width - p.layout.padding_left - p.layout.padding_right,
+                        height: p.height - p.layout.padding_top - p.layout.padding_bottom,
+                    },
+                    None => return false,
+                }
+            }
+            _ => boxes.iter().map(|(_, b)| *b).reduce(|a, b| a.union(&b)).unwrap(),
+        };
+        self.push_undo();
+        for (id, b) in boxes {
+            let (dx, dy) = match mode {
+                "left" => (bounds.x - b.x, 0.0),
+                "center" => (bounds.x + (bounds.width - b.width) / 2.0 - b.x, 0.0),
+                "right" => (bounds.x + bounds.width - b.width - b.x, 0.0),
+                "top" => (0.0, bounds.y - b.y),
+                "middle" => (0.0, bounds.y + (bounds.height - b.height) / 2.0 - b.y),
+                _ => (0.0, bounds.y + bounds.height - b.height - b.y),
+            };
+            self.scene.translate_subtree(id, dx, dy);
+        }
+        true
+    }
+
+    /// Resize the selection's combined bounds to (w, h), scaling each unlocked node's position
+    /// and size proportionally. `anchor` is the corner that stays fixed, in handle order:
+    /// 0 top-left, 1 top-right, 2 bottom-left, 3 bottom-right. Auto-layout children are skipped.
+    pub fn resize_selection(&mut self, w: f64, h: f64, anchor: u8) -> bool {
+        let ids = self.free_selection();
+        let old = match ids.iter().filter_map(|&id| self.scene.get_node(id)).map(|n| n.rotated_bounds()).reduce(|a, b| a.union(&b)) {
+            Some(b) if w > 0.0 && h > 0.0 => b,
+            _ => return false,
+        };
+        let x = if anchor == 1 || anchor == 3 { old.x + old.width - w } else { old.x };
+        let y = if anchor >= 2 { old.y + old.height - h } else { old.y };
+        let new = Rect { x, y, width: w, height: h };
+        for id in ids {
+            self.scene.scale_subtree(id, old, new);
+        }
+        true
+    }
+
+    /// Rotate unlocked selected nodes together around a shared pivot (scene space), defaulting
+    /// to the center of the selection bounds. Each node orbits the pivot and turns by `delta`.
+    pub fn rotate_selection(&mut self, delta: f64, pivot_x: Option<f64>, pivot_y: Option<f64>) -> bool {
+        let ids = self.editable_selection();
+        let bounds = match ids.iter().filter_map(|&id| self.scene.get_node(id)).map(|n| n.rotated_bounds()).reduce(|a, b| a.union(&b)) {
+            Some(b) => b,
+            None => return false,
+        };
+        let px = pivot_x.unwrap_or(bounds.x + bounds.width / 2.0);
+        let py = pivot_y.unwrap_or(bounds.y + bounds.height / 2.0);
+        for id in ids {
+            self.scene.rotate_subtree(id, px, py, delta);
+        }
+        true
+    }
+
+    /// Space unlocked selected nodes so the edge gaps between them are equal, keeping the
+    /// outermost two in place: "horizontal" or "vertical". Nodes are ordered by their centers
+    /// along the axis. Needs at least three nodes.
+    pub fn distribute_selection(&mut self, axis: &str) -> bool {
+        let horizontal = match axis {
+            "horizontal" => true,
+            "vertical" => false,
+            _ => return false,
+        };
+        let mut boxes: Vec<(u64, Rect)> = self.editable_selection().iter()
+            .filter_map(|&id| self.scene.get_node(id).map(|n| (id, n.rotated_bounds())))
+            .collect();
+        if boxes.len() < 3 {
+            return false;
+        }
+        let start = |b: &Rect| if horizontal { b.x } else { b.y };
+        let size = |b: &Rect| if horizontal { b.width } else { b.height };
+        let center = |b: &Rect| start(b) + size(b) / 2.0;
+        boxes.sort_by(|a, b| center(&a.1).total_cmp(&center(&b.1)));
+
+        let first = boxes[0].1;
+        let last = boxes[boxes.len() - 1].1;
+        let span = start(&last) + size(&last) - start(&first);
+        let total: f64 = boxes.iter().map(|(_, b)| size(b)).sum();
+        let gap = (span - total) / (boxes.len() - 1) as f64;
+
+        let mut cursor = start(&first);
+        for (id, b) in &boxes {
+            let delta = cursor - start(b);
+            if horizontal {
+                self.scene.translate_subtree(*id, delta, 0.0);
+            } else {
+                self.scene.translate_subtree(*id, 0.0, delta);
+            }
+            cursor += size(b) + gap;
+        }
+        true
+    }
+
+    /// Pack unlocked selected nodes along an axis ("horizontal" or "vertical") with exactly
+    /// `spacing` px between them, in their current order, starting at the first node.
+    /// Auto-layout children are skipped. One undo step.
+    pub fn distribute_selection_fixed(&mut self, axis: &str, spacing: f64) -> bool {
+        let horizontal = match axis {
+            "horizontal" => true,
+            "vertical" => false,
+            _ => return false,
+        };
+        let mut boxes: Vec<(u64, Rect)> = self.free_selection().iter()
+            .filter_map(|&id| self.scene.get_node(id).map(|n| (id, n.rotated_bounds())))
+            .collect();
+        if boxes.len() < 2 {
+            return false;
+        }
+        let start = |b: &Rect| if horizontal { b.x } else { b.y };
+        let size = |b: &Rect| if horizontal { b.width } else { b.height };
+        boxes.sort_by(|a, b| start(&a.1).total_cmp(&start(&b.1)));
+
+        self.push_undo();
+        let mut cursor = start(&boxes[0].1);
+        for (id, b) in &boxes {
+            let delta = cursor - start(b);
+            if horizontal {
+                self.scene.translate_subtree(*id, delta, 0.0);
+            } else {
+                self.scene.translate_subtree(*id, 0.0, delta);
+            }
+            cursor += size(b) + spacing;
+        }
+        true
+    }
+
+    // =============================================
+    // Selection Sets
+    // =============================================
+
+    /// Save the current selection under a name (overwrites an existing set)
+    pub fn save_selection_set(&mut self, name: &str) {
+        self.selection_sets.insert(name.to_string(), self.scene.selection.clone());
+    }
+
+    /// Restore a saved selection, skipping nodes that no longer exist. Returns false if unknown.
+    pub fn restore_selection_set(&mut self, name: &str) -> bool {
+        if let Some(ids) = self.selection_sets.get(name) {
+            self.scene.selection = ids.iter().copied()
+                .filter(|id| self.scene.get_node(*id).is_some())
+                .collect();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// List saved selection sets as JSON: [{ name, ids }]
+    pub fn list_selection_sets(&self) -> String {
+        let mut names: Vec<_> = self.selection_sets.keys().collect();
+        names.sort();
+        let list: Vec<_> = names.iter().map(|name| {
+            serde_json::json!({
+                "name": name,
+                "ids": self.selection_sets[*name],
+            })
+        }).collect();
+        serde_json::to_string(&list).unwrap_or_default()
+    }
+
+    /// Delete a saved selection set. Returns false if unknown.
+    pub fn delete_selection_set(&mut self, name: &str) -> bool {
+        self.selection_sets.remove(name).is_some()
+    }
+
+    // =============================================
+    // Shared Styles
+    // =============================================
+
+    /// Create or update a color style; nodes using it are repainted
+    pub fn set_color_style(&mut self, name: &str, r: u8, g: u8, b: u8, a: f64) {
+        let color = Color { r, g, b, a };
+        self.styles.colors.insert(name.to_string(), color);
+        for id in self.scene.all_node_ids() {
+            if let Some(node) = self.scene.get_node_mut(id) {
+                if node.fill_style.as_deref() == Some(name) {
+                    node.set_top_fill_color(color);
+                }
+            }
+        }
+    }
+
+    /// Create or update a text style from JSON { font_family, font_size, font_weight?, line_height? };
+    /// text nodes using it are updated. Returns false for invalid JSON.
+    pub fn set_text_style(&mut self, name: &str, json: &str) -> bool {
+        let style: TextStyle = match serde_json::from_str(json) {
+            Ok(s) => s,
+            Err(_) => return false,
+        };
+        for id in self.scene.all_node_ids() {
+            if let Some(node) = self.scene.get_node_mut(id) {
+                if node.text_style.as_deref() == Some(name) {
+                    copy_text_style_into(node, &style);
+                }
+            }
+        }
+        self.styles.texts.insert(name.to_string(), style);
+        true
+    }
+
+    /// Paint a node's top fill with a color style and keep the link. Returns false for unknown ids/styles.
+    pub fn apply_color_style(&mut self, id: u64, name: &str) -> bool {
+        let (Some(&color), Some(node)) = (self.styles.colors.get(name), self.scene.get_node_mut(id)) else {
+            return false;
+        };
+        node.set_top_fill_color(color);
+        node.fill_style = Some(name.to_string());
+        true
+    }
+
+    /// Apply a text style to a text node and keep the link. Returns false for unknown ids/styles.
+    pub fn apply_text_style(&mut self, id: u64, name: &str) -> bool {
+        let (Some(style), Some(node)) = (self.styles.texts.get(name), self.scene.get_node_mut(id)) else {
+            return false;
+        };
+        if !matches!(node.kind, NodeKind::Text { .. }) {
+            return false;
+        }
+        copy_text_style_into(node, style);
+        node.text_style = Some(name.to_string());
+        true
+    }
+
+    /// Shared styles as JSON: { colors: { name: Color }, texts: { name: TextStyle } }
+    pub fn list_styles(&self) -> String {
+        serde_json::to_string(&self.styles).unwrap_or_default()
+    }
+
+    // =============================================
+    // Guides
+    // =============================================
+
+    /// Add a ruler guide at a scene-space position; axis is "x" (vertical line) or "y".
+    /// Returns its index, or -1 for an unknown axis.
+    pub fn add_guide(&mut self, axis: &str, position: f64) -> i32 {
+        let axis = match axis {
+            "x" => Axis::X,
+            "y" => Axis::Y,
+            _ => return -1,
+        };
+        self.scene.guides.push(Guide { axis, position });
+        self.scene.guides.len() as i32 - 1
+    }
+
+    pub fn remove_guide(&mut self, index: usize) -> bool {
+        if index >= self.scene.guides.len() {
+            return false;
+        }
+        self.scene.guides.remove(index);
+        true
+    }
+
+    /// Guides as JSON: [{ axis, position }]
+    pub fn list_guides(&self) -> String {
+        serde_json::to_string(&self.scene.guides).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Topmost frame/instance/group under a screen point that could receive a drop, skipping
+    /// the dragged nodes (`exclude_ids_json`) and their descendants
+    pub fn frame_at_point(&self, screen_x: f64, screen_y: f64, exclude_ids_json: &str) -> Option<u64> {
+        let exclude: Vec<u64> = serde_json::from_str(exclude_ids_json).unwrap_or_default();
+        let (sx, sy) = self.renderer.screen_to_scene(screen_x, screen_y);
+        self.scene.frame_at_point(Point { x: sx, y: sy }, &exclude)
+    }
+
+    /// Highlight a container as the pending drop target (None to clear)
+    pub fn set_drop_target(&mut self, id: Option<u64>) {
+        self.renderer.drop_target = id;
+    }
+
+    pub fn hit_test(&self, screen_x: f64, screen_y: f64) -> Option<u64> {
+        let (sx, sy) = self.renderer.screen_to_scene(screen_x, screen_y);
+        self.scene.hit_test(Point { x: sx, y: sy })
+    }
+
+    /// Like `hit_test`, ignoring the ids in `exclude_json` (and, with `descendants`, their children)
+    pub fn hit_test_excluding(&self, screen_x: f64, screen_y: f64, exclude_json: &str, descendants: bool) -> Option<u64> {
+        let exclude: Vec<u64> = serde_json::from_str(exclude_json).unwrap_or_default();
+        let (sx, sy) = self.renderer.screen_to_scene(screen_x, screen_y);
+        self.scene.hit_test_excluding(Point { x: sx, y: sy }, &exclude, descendants)
+    }
+
+    pub fn hit_test_handle(&self, screen_x: f64, screen_y: f64) -> i32 {
+        let (sx, sy) = self.renderer.screen_to_scene(screen_x, screen_y);
+        let handle_size = 8.0 / self.renderer.viewport.a;
+        // Multi-selections share one set of handles on the combined bounds
+        if self.scene.selection.len() > 1 {
+            return self.scene.selection_bounds()
+                .and_then(|b| hit_test::hit_test_rect_handles(b, Point { x: sx, y: sy }, handle_size))
+                .map(|idx| idx as i32)
+                .unwrap_or(-1);
+        }
+        for &id in &self.scene.selection {
+            let rotation_gap = hit_test::ROTATION_HANDLE_GAP / self.renderer.viewport.a;
+            if let Some(idx) = hit_test::hit_test_handles(&self.scene, id, Point { x: sx, y: sy }, handle_size, rotation_gap) {
+                return idx as i32;
+            }
+        }
+        -1
+    }
+
+    /// Screen-space centers of a node's corner handles (rotated with the node) as JSON [{x, y}], or "null"
+    pub fn get_handle_positions(&self, id: u64) -> String {
+        let Some(node) = self.scene.get_node(id) else {
+            return "null".to_string();
+        };
+        let (ox, oy) = self.scene.scroll_offset(id);
+        let points: Vec<Point> = hit_test::node_handle_positions(node).iter()
+            .map(|p| self.renderer.viewport.apply(Point { x: p.x - ox, y: p.y - oy }))
+            .collect();
+        serde_json::to_string(&points).unwrap_or_else(|_| "null".to_string())
+    }
+
+    /// Combined rotation-aware bounds of the selection as JSON {x, y, width, height}, or None if empty
+    pub fn get_selection_bounds(&self) -> Option<String> {
+        self.scene.selection_bounds().map(|b| serde_json::to_string(&b).unwrap_or_default())
+    }
+
+    /// Zoom and pan so the selection fills the canvas with `padding` screen px around it.
+    /// Returns false if nothing is selected.
+    pub fn zoom_to_selection(&mut self, padding: f64) -> bool {
+        match self.scene.selection_bounds() {
+            Some(bounds) => {
+                self.renderer.fit_rect(bounds, padding);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Zoom and pan so every visible root node (with its descendants) fits the canvas with
+    /// `padding` screen px around it. An empty scene resets to 100% with the origin centered.
+    pub fn zoom_to_fit(&mut self, padding: f64) {
+        match self.scene.content_bounds() {
+            Some(bounds) => self.renderer.fit_rect(bounds, padding),
+            None => {
+                let v = &mut self.renderer.viewport;
+                v.a = 1.0;
+                v.d = 1.0;
+                v.tx = self.renderer.canvas_width / 2.0;
+                v.ty = self.renderer.canvas_height / 2.0;
+            }
+        }
+    }
+
+    /// Arrange loose nodes into a grid of `columns`, in reading order, spaced by `gap`.
+    /// Nodes inside auto-layout parents are ignored. Records one undo step.
+    pub fn tidy_up(&mut self, ids_json: &str, columns: u32, gap: f64) -> bool {
+        let ids: Vec<u64> = serde_json::from_str(ids_json).unwrap_or_default();
+        let mut nodes: Vec<Rect> = vec![];
+        let mut order: Vec<u64> = vec![];
+        for id in ids {
+            let node = match self.scene.get_node(id) {
+                Some(n) => n,
+                None => continue,
+            };
+            let in_layout = node.parent
+                .and_then(|p| self.scene.get_node(p))
+                .map(|p| p.layout.mode != LayoutMode::None)
+                .unwrap_or(false);
+            if in_layout || node.locked || order.contains(&id) {
+                continue;
+            }
+            nodes.push(node.bounds());
+            order.push(id);
+        }
+        if nodes.is_empty() {
+            return false;
+        }
+
+        // Reading order: bucket into rows by vertical overlap, then left-to-right
+        let mut items: Vec<(u64, Rect)> = order.into_iter().zip(nodes).collect();
+        items.sort_by(|a, b| a.1.y.total_cmp(&b.1.y));
+        let mut rows: Vec<Vec<(u64, Rect)>> = vec![];
+        for item in items {
+            match rows.last_mut() {
+                Some(row) if item.1.y < row[0].1.y + row[0].1.height / 2.0 => row.push(item),
+                _ => rows.push(vec![item]),
+            }
+        }
+        let sorted: Vec<(u64, Rect)> = rows.into_iter()
+            .flat_map(|mut row| {
+                row.sort_by(|a, b| a.1.x.total_cmp(&b.1.x));
+                row
+            })
+            .collect();
+
+        let columns = (columns.max(1) as usize).min(sorted.len());
+        let row_count = sorted.len().div_ceil(columns);
+        let mut col_widths = vec![0.0f64; columns];
+        let mut row_heights = vec![0.0f64; row_count];
+        for (i, (_, b)) in sorted.iter().enumerate() {
+            col_widths[i % columns] = col_widths[i % columns].max(b.width);
+            row_heights[i / columns] = row_heights[i / columns].max(b.height);
+        }
+        let origin_x = sorted.iter().map(|(_, b)| b.x).fold(f64::INFINITY, f64::min);
+        let origin_y = sorted.iter().map(|(_, b)| b.y).fold(f64::INFINITY, f64::min);
+
+        self.push_undo();
+        for (i, (id, b)) in sorted.iter().enumerate() {
+            let (col, row) = (i % columns, i / columns);
+            let x = origin_x + col_widths[..col].iter().map(|w| w + gap).sum::<f64>();
+            let y = origin_y + row_heights[..row].iter().map(|h| h + gap).sum::<f64>();
+            self.scene.translate_subtree(*id, x - b.x, y - b.y);
+        }
+        true
+    }
+
+    pub fn zoom(&mut self, delta: f64, cx: f64, cy: f64) {
+        self.renderer.zoom(delta, cx, cy);
+        self.renderer.clamp_pan(self.scene.content_bounds());
+    }
+
+    pub fn pan(&mut self, dx: f64, dy: f64) {
+        self.renderer.pan(dx, dy);
+        self.renderer.clamp_pan(self.scene.content_bounds());
+    }
+
+    /// Keep at least `margin` screen px of the content visible while panning/zooming
+    pub fn set_pan_limit(&mut self, enabled: bool, margin: f64) {
+        self.renderer.set_pan_limit(enabled, margin);
+        self.renderer.clamp_pan(self.scene.content_bounds());
+    }
+
+    /// Center the viewport on a scene-space point
+    pub fn pan_to(&mut self, scene_x: f64, scene_y: f64) {
+        let zoom = self.renderer.viewport.a;
+        let cx = self.renderer.canvas_width / 2.0;
+        let cy = self.renderer.canvas_height / 2.0;
         self.renderer.viewport.tx = cx - scene_x * zoom;
         self.renderer.viewport.ty = cy - scene_y * zoom;
     }
 
+    /// Current zoom and pan as JSON { a, tx, ty }, for saving alongside the document
+    pub fn export_viewport(&self) -> String {
+        let v = &self.renderer.viewport;
+        serde_json::json!({ "a": v.a, "tx": v.tx, "ty": v.ty }).to_string()
+    }
+
+    /// Restore zoom and pan from `export_viewport` JSON. Returns false for invalid JSON.
+    pub fn import_viewport(&mut self, json: &str) -> bool {
+        let value: serde_json::Value = match serde_json::from_str(json) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        let (Some(a), Some(tx), Some(ty)) = (value["a"].as_f64(), value["tx"].as_f64(), value["ty"].as_f64()) else {
+            return false;
+        };
+        let zoom = a.clamp(0.1, 10.0);
+        let v = &mut self.renderer.viewport;
+        v.a = zoom;
+        v.d = zoom;
+        v.tx = tx;
+        v.ty = ty;
+        true
+    }
+
     pub fn get_zoom(&self) -> f64 {
         self.renderer.viewport.a
     }
@@ -343,6 +1525,61 @@ impl Engine {
         self.scene.get_node(id).map(|n| serde_json::to_string(n).unwrap_or_default())
     }
 
+    /// Geometry the renderer drew a node with in the last frame, as JSON
+    /// { x, y, width, height, rotation, bounds: {x, y, width, height} }, or "null".
+    /// Positions are as the last render's layout pass left them, with clipped-frame scrolling
+    /// applied; `bounds` is the rotation-aware box.
+    pub fn get_computed_bounds(&self, id: u64) -> String {
+        let Some(node) = self.scene.get_node(id) else {
+            return "null".to_string();
+        };
+        let (ox, oy) = self.scene.scroll_offset(id);
+        let b = node.rotated_bounds();
+        serde_json::json!({
+            "x": node.x - ox,
+            "y": node.y - oy,
+            "width": node.width,
+            "height": node.height,
+            "rotation": node.rotation,
+            "bounds": Rect { x: b.x - ox, y: b.y - oy, ..b },
+        }).to_string()
+    }
+
+    /// Draw a node above all its siblings
+    pub fn bring_to_front(&mut self, id: u64) -> bool {
+        self.restack(id, |_, last| last)
+    }
+
+    /// Draw a node below all its siblings
+    pub fn send_to_back(&mut self, id: u64) -> bool {
+        self.restack(id, |_, _| 0)
+    }
+
+    /// Swap a node with the sibling drawn just above it
+    pub fn bring_forward(&mut self, id: u64) -> bool {
+        self.restack(id, |i, last| (i + 1).min(last))
+    }
+
+    /// Swap a node with the sibling drawn just below it
+    pub fn send_backward(&mut self, id: u64) -> bool {
+        self.restack(id, |i, _| i.saturating_sub(1))
+    }
+
+    /// Move a node within its siblings to `target(current, last)`. False if it didn't move.
+    fn restack(&mut self, id: u64, target: impl Fn(usize, usize) -> usize) -> bool {
+        let Some(index) = self.scene.sibling_index(id) else { return false };
+        let count = match self.scene.get_node(id).and_then(|n| n.parent) {
+            Some(pid) => self.scene.get_children_of(pid).len(),
+            None => self.scene.root_children().len(),
+        };
+        let to = target(index, count - 1);
+        if to == index {
+            return false;
+        }
+        self.scene.move_to_index(id, to);
+        true
+    }
+
     pub fn get_layer_list(&self) -> String {
         let layers: Vec<_> = self.scene.render_order().iter()
             .filter_map(|&id| {
@@ -371,18 +1608,63 @@ impl Engine {
 
     /// Export entire scene as JSON
     pub fn export_scene(&self) -> String {
-        serde_json::to_string(&self.scene.export()).unwrap_or_default()
+        let mut data = self.scene.export();
+        data.selection_sets = self.selection_sets.clone();
+        data.styles = self.styles.clone();
+        serde_json::to_string(&data).unwrap_or_default()
     }
 
     /// Import scene from JSON, replacing current scene
     pub fn import_scene(&mut self, json: &str) -> bool {
-        match serde_json::from_str::<crate::scene::SceneData>(json) {
-            Ok(data) => {
-                self.scene = crate::scene::Scene::import(data);
-                true
-            }
-            Err(_) => false,
+        self.try_import_scene(json).is_ok()
+    }
+
+    /// Import scene from JSON like `import_scene`, reporting the outcome as JSON:
+    /// {"ok":true,"version":n} or {"ok":false,"error":"...","line":n,"column":n}
+    /// (line/column only for parse errors). The current scene is kept on failure.
+    pub fn import_scene_result(&mut self, json: &str) -> String {
+        let result = match self.try_import_scene(json) {
+            Ok(version) => serde_json::json!({ "ok": true, "version": version }),
+            Err(ImportError::Parse(e)) => serde_json::json!({
+                "ok": false,
+                "error": e.to_string(),
+                "line": e.line(),
+                "column": e.column(),
+            }),
+            Err(ImportError::Version(v)) => serde_json::json!({
+                "ok": false,
+                "error": format!("scene version {} is newer than supported version {}", v, crate::scene::SCENE_VERSION),
+            }),
+        };
+        result.to_string()
+    }
+
+    /// Parse, version-check and load a scene document; returns the version it was saved with
+    fn try_import_scene(&mut self, json: &str) -> Result<u32, ImportError> {
+        let mut data: crate::scene::SceneData = serde_json::from_str(json).map_err(ImportError::Parse)?;
+        let version = data.version;
+        if version > crate::scene::SCENE_VERSION {
+            return Err(ImportError::Version(version));
         }
+        self.selection_sets = std::mem::take(&mut data.selection_sets);
+        self.styles = std::mem::take(&mut data.styles);
+        self.scene = crate::scene::Scene::import(data);
+        Ok(version)
+    }
+
+    /// Export a node's box, fill, and auto-layout as a CSS rule (empty string if not found)
+    pub fn export_css(&self, id: u64) -> String {
+        self.scene.get_node(id).map(css::export_css).unwrap_or_default()
+    }
+
+    /// Shared color and text styles as CSS custom properties on `:root`
+    pub fn export_css_vars(&self) -> String {
+        styles::export_css_vars(&self.styles)
+    }
+
+    /// Export a frame subtree as a React component with inline styles
+    pub fn export_jsx(&self, id: u64) -> String {
+        jsx::export_jsx(&self.scene, &self.components, id)
     }
 
     // === Frame Tools ===
@@ -400,9 +1682,9 @@ impl Engine {
 
     /// Get full subtree of a frame (recursive) as JSON
     pub fn get_frame_tree(&self, id: u64) -> String {
-        fn collect(scene: &crate::scene::Scene, id: u64) -> serde_json::Value {
-            if let Some(node) = scene.get_node(id) {
-                let children: Vec<_> = node.children.iter().map(|&cid| collect(scene, cid)).collect();
+        fn collect(scene: &crate::scene::Scene, id: u64, visited: &mut std::collections::HashSet<u64>) -> serde_json::Value {
+            if let Some(node) = scene.get_node(id).filter(|_| visited.insert(id)) {
+                let children: Vec<_> = node.children.iter().map(|&cid| collect(scene, cid, visited)).collect();
                 let mut val = serde_json::to_value(node).unwrap_or_default();
                 if let Some(obj) = val.as_object_mut() {
                     obj.insert("_children".to_string(), serde_json::Value::Array(children));
@@ -412,18 +1694,217 @@ impl Engine {
                 serde_json::Value::Null
             }
         }
-        serde_json::to_string(&collect(&self.scene, id)).unwrap_or_default()
+        serde_json::to_string(&collect(&self.scene, id, &mut std::collections::HashSet::new())).unwrap_or_default()
     }
 
-    /// Move a node into a frame (reparent)
-    pub fn reparent_node(&mut self, node_id: u64, new_parent: Option<u64>) {
+    /// Move a node into a frame (reparent). Locked nodes stay put unless `force` is set.
+    pub fn reparent_node(&mut self, node_id: u64, new_parent: Option<u64>, force: bool) -> bool {
+        if !force && self.scene.is_locked(node_id) {
+            return false;
+        }
         self.scene.reparent(node_id, new_parent);
+        true
+    }
+
+    /// Rasterize a scene-space rectangle (not a node) at `scale` as a PNG data URL,
+    /// e.g. for slices. Returns an empty string for non-positive sizes.
+    pub fn export_png_region(&self, x: f64, y: f64, w: f64, h: f64, scale: f64) -> String {
+        if w <= 0.0 || h <= 0.0 || scale <= 0.0 {
+            return String::new();
+        }
+        let region = Rect { x, y, width: w, height: h };
+        export::rasterize_region(&self.scene, region, scale, None).unwrap_or_default()
+    }
+
+    /// Rasterize the whole scene (or only the selection's bounds) at `scale` device pixels per
+    /// scene unit as a PNG data URL, e.g. 2 for retina. The image is cropped to the visible
+    /// content and transparent unless `white_background` is set. Empty string if there is nothing to export.
+    pub fn export_png(&self, scale: f64, selection_only: bool, white_background: bool) -> String {
+        if scale <= 0.0 {
+            return String::new();
+        }
+        let bounds = if selection_only { self.scene.selection_bounds() } else { self.scene.content_bounds() };
+        let Some(region) = bounds.filter(|b| b.width > 0.0 && b.height > 0.0) else {
+            return String::new();
+        };
+        let background = white_background.then(Color::white);
+        export::rasterize_region(&self.scene, region, scale, background).unwrap_or_default()
+    }
+
+    /// Rasterize a node's subtree at `scale` and replace it with a single image-filled rect
+    /// of the same bounds. Returns the new node id, or 0 on failure. Undo restores the original.
+    pub fn flatten_to_image(&mut self, id: u64, scale: f64) -> u64 {
+        let (bounds, name, parent, opacity) = match self.scene.get_node(id) {
+            Some(n) => (n.bounds(), n.name.clone(), n.parent, n.opacity),
+            None => return 0,
+        };
+        let scale = if scale > 0.0 { scale } else { 1.0 };
+
+        let mut subtree = self.subtree_scene(id);
+        if let Some(root) = subtree.get_node_mut(id) {
+            root.opacity = 1.0; // opacity is kept on the flattened node instead
+        }
+        let w = (bounds.width * scale).ceil() as u32;
+        let h = (bounds.height * scale).ceil() as u32;
+        let src = match export::rasterize(&subtree, bounds, scale, w, h, 0.0, 0.0, None) {
+            Ok(s) => s,
+            Err(_) => return 0,
+        };
+
+        self.push_undo();
+        self.renderer.register_image(&src);
+        let index = self.scene.sibling_index(id);
+        let mut image_node = Node::new(0, NodeKind::Rect);
+        image_node.name = name;
+        image_node.x = bounds.x;
+        image_node.y = bounds.y;
+        image_node.width = bounds.width;
+        image_node.height = bounds.height;
+        image_node.opacity = opacity;
+        image_node.parent = parent;
+        image_node.fills = vec![Fill::image(src)];
+        self.scene.remove_node(id);
+        let new_id = self.scene.add_node(image_node);
+        if let Some(index) = index {
+            self.scene.move_to_index(new_id, index);
+        }
+        new_id
+    }
+
+    /// A standalone scene holding only a node and its descendants (node becomes a root)
+    fn subtree_scene(&self, id: u64) -> Scene {
+        let mut nodes = self.deep_clone_subtree(id);
+        if let Some(root) = nodes.first_mut() {
+            root.parent = None;
+        }
+        Scene::import(crate::scene::SceneData {
+            version: crate::scene::SCENE_VERSION,
+            nodes,
+            root_children: vec![id],
+            next_id: 0,
+            selection_sets: std::collections::HashMap::new(),
+            guides: vec![],
+            styles: crate::styles::StyleStore::default(),
+        })
     }
 
-    /// Duplicate a node (shallow copy)
+    /// Export the whole scene as an SVG document. With `outline_text`, text is replaced by
+    /// font-independent placeholder shapes instead of `<text>` elements.
+    pub fn export_svg(&self, outline_text: bool) -> String {
+        svg::export_svg(&self.scene, self.scene.root_children(), svg::SvgOptions { outline_text })
+    }
+
+    /// Export a single frame's subtree as SVG, clipped to the frame's bounds.
+    /// Empty string if the id isn't a frame.
+    pub fn export_frame_svg(&self, frame_id: u64) -> String {
+        let bounds = match self.scene.get_node(frame_id) {
+            Some(n) if matches!(n.kind, NodeKind::Frame) => n.bounds(),
+            _ => return String::new(),
+        };
+        let subtree = self.subtree_scene(frame_id);
+        svg::export_svg_clipped(&subtree, frame_id, bounds, svg::SvgOptions::default())
+    }
+
+    /// Rasterize a single frame's subtree at `scale` as a PNG data URL, cropped to the frame's
+    /// bounds. Empty string if the id isn't a frame.
+    pub fn export_frame_image(&self, frame_id: u64, scale: f64) -> String {
+        let bounds = match self.scene.get_node(frame_id) {
+            Some(n) if matches!(n.kind, NodeKind::Frame) && scale > 0.0 => n.bounds(),
+            _ => return String::new(),
+        };
+        let subtree = self.subtree_scene(frame_id);
+        export::rasterize_region(&subtree, bounds, scale, None).unwrap_or_default()
+    }
+
+    // =============================================
+    // Export Settings
+    // =============================================
+
+    /// Add an export preset from JSON `{format: "png"|"svg", scale, suffix}`
+    pub fn add_export_setting(&mut self, id: u64, json: &str) -> bool {
+        let setting: ExportSetting = match serde_json::from_str(json) {
+            Ok(s) => s,
+            Err(_) => return false,
+        };
+        match self.scene.get_node_mut(id) {
+            Some(node) => {
+                node.export_settings.push(setting);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Remove the export preset at `index`
+    pub fn remove_export_setting(&mut self, id: u64, index: usize) -> bool {
+        match self.scene.get_node_mut(id) {
+            Some(node) if index < node.export_settings.len() => {
+                node.export_settings.remove(index);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Run every export preset on a node. Returns a JSON array of `{suffix, format, data_url}`
+    /// (presets that fail to render are skipped).
+    pub fn run_exports(&self, id: u64) -> String {
+        let node = match self.scene.get_node(id) {
+            Some(n) => n,
+            None => return "[]".to_string(),
+        };
+        let subtree = self.subtree_scene(id);
+        let mut results = vec![];
+        for setting in &node.export_settings {
+            let data_url = match setting.format {
+                ExportFormat::Png => {
+                    let bounds = node.bounds();
+                    let scale = if setting.scale > 0.0 { setting.scale } else { 1.0 };
+                    match export::rasterize_region(&subtree, bounds, scale, None) {
+                        Ok(url) => url,
+                        Err(_) => continue,
+                    }
+                }
+                ExportFormat::Svg => {
+                    let svg = svg::export_svg(&subtree, &[id], svg::SvgOptions::default());
+                    format!("data:image/svg+xml;charset=utf-8,{}", String::from(js_sys::encode_uri_component(&svg)))
+                }
+            };
+            results.push(serde_json::json!({
+                "suffix": setting.suffix,
+                "format": setting.format,
+                "data_url": data_url,
+            }));
+        }
+        serde_json::to_string(&results).unwrap_or_default()
+    }
+
+    /// Duplicate a node with its whole subtree, offset by (20, 20) under a distinct name
+    /// ("Rect 3" → "Rect 4"). Descendants keep their positions relative to the copy.
+    /// This is the duplicate the UI should use. One undo step.
+    pub fn duplicate_node_deep(&mut self, id: u64) -> u64 {
+        let (name, parent) = match self.scene.get_node(id) {
+            Some(node) => (self.scene.copy_name(&node.name), node.parent),
+            None => return 0,
+        };
+        self.push_undo();
+        let nodes = self.scene.subtree_nodes(id);
+        let Some(&copy) = self.scene.insert_subtrees(&[nodes], parent, 20.0, 20.0).first() else {
+            return 0;
+        };
+        if let Some(node) = self.scene.get_node_mut(copy) {
+            node.name = name;
+        }
+        layout::route_connectors(&mut self.scene);
+        copy
+    }
+
+    /// Duplicate a node (shallow copy, no children) under a distinct name ("Rect 3" → "Rect 4").
+    /// Kept for compatibility; prefer `duplicate_node_deep`.
     pub fn duplicate_node(&mut self, id: u64) -> u64 {
         if let Some(node) = self.scene.get_node(id) {
             let mut new_node = node.clone();
+            new_node.name = self.scene.copy_name(&node.name);
             new_node.x += 20.0;
             new_node.y += 20.0;
             new_node.parent = node.parent;
@@ -434,6 +1915,89 @@ impl Engine {
         }
     }
 
+    /// Serialize the selected subtrees (each node with all its descendants) to a JSON clipboard
+    /// buffer for `paste`. Nodes already inside another selected node aren't repeated.
+    pub fn copy_selection(&self) -> String {
+        let selected = &self.scene.selection;
+        let subtrees: Vec<Vec<Node>> = selected.iter()
+            .filter(|&&id| {
+                let mut parent = self.scene.get_node(id).and_then(|n| n.parent);
+                while let Some(p) = parent {
+                    if selected.contains(&p) {
+                        return false;
+                    }
+                    parent = self.scene.get_node(p).and_then(|n| n.parent);
+                }
+                true
+            })
+            .map(|&id| self.scene.subtree_nodes(id))
+            .filter(|nodes| !nodes.is_empty())
+            .collect();
+        let mut components = std::collections::HashMap::new();
+        let mut styles = StyleStore::default();
+        for node in subtrees.iter().flatten() {
+            if let NodeKind::Instance(data) = &node.kind {
+                if let Some(comp) = self.components.get(data.component_id) {
+                    components.insert(data.component_id, comp.name.clone());
+                }
+            }
+            if let Some((name, color)) = node.fill_style.as_ref().and_then(|n| self.styles.colors.get_key_value(n)) {
+                styles.colors.insert(name.clone(), *color);
+            }
+            if let Some((name, text)) = node.text_style.as_ref().and_then(|n| self.styles.texts.get_key_value(n)) {
+                styles.texts.insert(name.clone(), text.clone());
+            }
+        }
+        serde_json::to_string(&Clipboard { subtrees, components, styles }).unwrap_or_default()
+    }
+
+    /// Insert a `copy_selection` buffer (possibly from another document) at the top level with
+    /// fresh ids, offset by (dx, dy), and select the pasted nodes. One undo step.
+    /// Roots whose name is taken get a copy name. Instances whose component isn't in this
+    /// document become plain frames; shared styles the nodes use are added if missing.
+    /// Returns the new root ids; empty for an invalid buffer.
+    pub fn paste(&mut self, json: &str, dx: f64, dy: f64) -> Vec<u64> {
+        let clipboard: Clipboard = match serde_json::from_str(json) {
+            Ok(c) => c,
+            Err(_) => return vec![],
+        };
+        let before = self.undo_snapshot();
+        let ids = self.scene.insert_subtrees(&clipboard.subtrees, None, dx, dy);
+        if ids.is_empty() {
+            return ids;
+        }
+        self.push_undo_snapshot(before);
+        for &id in &ids {
+            let name = match self.scene.get_node(id) {
+                Some(node) if self.scene.name_taken(&node.name, id) => self.scene.copy_name(&node.name),
+                _ => continue,
+            };
+            if let Some(node) = self.scene.get_node_mut(id) {
+                node.name = name;
+            }
+        }
+        for (name, color) in clipboard.styles.colors {
+            self.styles.colors.entry(name).or_insert(color);
+        }
+        for (name, text) in clipboard.styles.texts {
+            self.styles.texts.entry(name).or_insert(text);
+        }
+        let pasted: Vec<u64> = ids.iter().flat_map(|&id| self.scene.subtree_ids(id)).collect();
+        for id in pasted {
+            let linked = match self.scene.get_node(id).map(|n| &n.kind) {
+                Some(NodeKind::Instance(data)) => self.components.get(data.component_id)
+                    .is_some_and(|c| clipboard.components.get(&data.component_id) == Some(&c.name)),
+                _ => true,
+            };
+            if !linked {
+                self.detach_instance_node(id);
+            }
+        }
+        self.scene.selection = ids.clone();
+        layout::route_connectors(&mut self.scene);
+        ids
+    }
+
     /// Get all frames (nodes of kind Frame)
     pub fn get_frames(&self) -> String {
         let frames: Vec<_> = self.scene.all_node_ids().iter()
@@ -472,10 +2036,121 @@ impl Engine {
                     } else {
                         None
                     }
-                })
-            })
-            .collect();
-        serde_json::to_string(&results).unwrap_or_default()
+                })
+            })
+            .collect();
+        serde_json::to_string(&results).unwrap_or_default()
+    }
+
+    // =============================================
+    // Path Editing
+    // =============================================
+
+    /// Toggle edit-path mode (draws vertex handles on selected paths)
+    pub fn set_path_edit_mode(&mut self, enabled: bool) {
+        self.path_edit_mode = enabled;
+    }
+
+    pub fn is_path_edit_mode(&self) -> bool {
+        self.path_edit_mode
+    }
+
+    /// Apply an edit to a path's points (in scene space), then refit its bounds
+    fn edit_path_points(&mut self, id: u64, edit: impl FnOnce(&mut Vec<Point>, f64, f64) -> bool) -> bool {
+        let node = match self.scene.get_node_mut(id) {
+            Some(n) => n,
+            None => return false,
+        };
+        let (ox, oy) = (node.x, node.y);
+        let changed = match &mut node.kind {
+            NodeKind::Path { points, .. } => edit(points, ox, oy),
+            _ => false,
+        };
+        if changed {
+            node.normalize_path();
+        }
+        changed
+    }
+
+    /// Append a vertex at a scene-space position
+    pub fn add_path_point(&mut self, id: u64, x: f64, y: f64) -> bool {
+        self.edit_path_points(id, |points, ox, oy| {
+            points.push(Point { x: x - ox, y: y - oy });
+            true
+        })
+    }
+
+    /// Replace all vertices from a JSON array of scene-space points: [{"x":0,"y":0}, ...]
+    pub fn set_path_points(&mut self, id: u64, points_json: &str) -> bool {
+        let new: Vec<Point> = match serde_json::from_str(points_json) {
+            Ok(p) => p,
+            Err(_) => return false,
+        };
+        if new.is_empty() {
+            return false;
+        }
+        self.edit_path_points(id, |points, ox, oy| {
+            *points = new.iter().map(|p| Point { x: p.x - ox, y: p.y - oy }).collect();
+            true
+        })
+    }
+
+    /// Move a vertex to a scene-space position
+    pub fn move_path_point(&mut self, id: u64, index: usize, x: f64, y: f64) -> bool {
+        self.edit_path_points(id, |points, ox, oy| {
+            match points.get_mut(index) {
+                Some(p) => {
+                    *p = Point { x: x - ox, y: y - oy };
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+
+    /// Remove a vertex. A path keeps at least one point.
+    pub fn remove_path_point(&mut self, id: u64, index: usize) -> bool {
+        self.edit_path_points(id, |points, _, _| {
+            if index < points.len() && points.len() > 1 {
+                points.remove(index);
+                true
+            } else {
+                false
+            }
+        })
+    }
+
+    /// Get a path's vertices in scene space as JSON: [{x, y}]
+    pub fn get_path_points(&self, id: u64) -> String {
+        match self.scene.get_node(id) {
+            Some(node) => match &node.kind {
+                NodeKind::Path { points, .. } => {
+                    let abs: Vec<Point> = points.iter().map(|p| Point { x: node.x + p.x, y: node.y + p.y }).collect();
+                    serde_json::to_string(&abs).unwrap_or_default()
+                }
+                _ => "[]".to_string(),
+            },
+            None => "[]".to_string(),
+        }
+    }
+
+    /// Index of the selected path vertex under a screen point, or -1
+    pub fn hit_test_path_point(&self, screen_x: f64, screen_y: f64) -> i32 {
+        let (sx, sy) = self.renderer.screen_to_scene(screen_x, screen_y);
+        let radius = 6.0 / self.renderer.viewport.a;
+        for &id in &self.scene.selection {
+            if let Some(node) = self.scene.get_node(id) {
+                if let NodeKind::Path { points, .. } = &node.kind {
+                    let local = node.unrotate_point(Point { x: sx, y: sy });
+                    for (i, p) in points.iter().enumerate() {
+                        if (node.x + p.x - local.x).hypot(node.y + p.y - local.y) <= radius {
+                            return i as i32;
+                        }
+                    }
+                }
+            }
+        }
+        -1
     }
 
     // =============================================
@@ -510,14 +2185,80 @@ impl Engine {
         comp_id
     }
 
-    fn deep_clone_subtree(&self, id: u64) -> Vec<Node> {
-        let mut result = vec![];
-        if let Some(node) = self.scene.get_node(id) {
-            result.push(node.clone());
-            for &child_id in &node.children {
-                result.extend(self.deep_clone_subtree(child_id));
+    /// Rename a component. The source frame becomes "[C] name"; instances still showing the
+    /// default "[I] old" label follow. Returns false for unknown ids.
+    pub fn rename_component(&mut self, comp_id: u64, name: &str) -> bool {
+        let (old_name, source_id) = match self.components.get_mut(comp_id) {
+            Some(comp) => {
+                let source_id = comp.variants.get(&comp.default_variant_key).map(|v| v.root_node_id);
+                (std::mem::replace(&mut comp.name, name.to_string()), source_id)
+            }
+            None => return false,
+        };
+        if let Some(node) = source_id.and_then(|id| self.scene.get_node_mut(id)) {
+            node.name = format!("[C] {}", name);
+        }
+        let old_label = format!("[I] {}", old_name);
+        for id in self.instances_of(comp_id) {
+            if let Some(node) = self.scene.get_node_mut(id) {
+                if node.name == old_label {
+                    node.name = format!("[I] {}", name);
+                }
+            }
+        }
+        true
+    }
+
+    /// Copy a component (properties, slots, variants) under a new name. Template nodes get
+    /// fresh ids so the copy never shares ids with the original. Variants whose source frame is
+    /// on the canvas get a copy of it, placed to the right, as the duplicate's own source.
+    /// Returns 0 for unknown ids.
+    pub fn duplicate_component(&mut self, comp_id: u64, new_name: &str) -> u64 {
+        let new_id = match self.components.duplicate(comp_id, new_name.to_string()) {
+            Some(id) => id,
+            None => return 0,
+        };
+        let on_canvas: std::collections::HashSet<String> = self.components.get(comp_id)
+            .map(|c| c.variants.iter()
+                .filter(|(_, v)| self.scene.get_node(v.root_node_id).is_some())
+                .map(|(k, _)| k.clone())
+                .collect())
+            .unwrap_or_default();
+        let offset = self.components.get(comp_id)
+            .and_then(|c| c.variants.values().filter_map(|v| self.scene.get_node(v.root_node_id)).map(|n| n.rotated_bounds()).reduce(|a, b| a.union(&b)))
+            .map(|b| b.width + 40.0)
+            .unwrap_or(0.0);
+        let scene = &mut self.scene;
+        let Some(comp) = self.components.get_mut(new_id) else {
+            return 0;
+        };
+        comp.remap_node_ids(|| scene.reserve_id());
+        let default_key = comp.default_variant_key.clone();
+        for (key, variant) in comp.variants.iter_mut().filter(|(k, _)| on_canvas.contains(*k)) {
+            for node in &mut variant.nodes {
+                node.x += offset;
+                if node.id == variant.root_node_id && *key == default_key {
+                    node.name = format!("[C] {}", new_name);
+                }
             }
+            scene.add_reserved_nodes(&variant.nodes);
         }
+        new_id
+    }
+
+    pub fn set_component_description(&mut self, comp_id: u64, text: &str) -> bool {
+        match self.components.get_mut(comp_id) {
+            Some(comp) => {
+                comp.description = text.to_string();
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn deep_clone_subtree(&self, id: u64) -> Vec<Node> {
+        let mut result = vec![];
+        self.scene.walk_subtree(id, &mut std::collections::HashSet::new(), &mut |node| result.push(node.clone()));
         result
     }
 
@@ -541,6 +2282,11 @@ impl Engine {
                 .unwrap_or(options.first().map(|s| s.as_str()).unwrap_or(""))
                 .to_string();
             (VariantPropType::String { options }, VariantValue::String(def))
+        } else if prop.get("type").and_then(|t| t.as_str()) == Some("color") {
+            let def = prop.get("default").and_then(|d| d.as_str())
+                .and_then(Color::from_hex)
+                .unwrap_or(Color::black());
+            (VariantPropType::Color, VariantValue::Color(def))
         } else {
             return false;
         };
@@ -579,6 +2325,34 @@ impl Engine {
         }
     }
 
+    /// Remove a variant by key. Refuses to remove the only (default) variant.
+    pub fn remove_variant(&mut self, comp_id: u64, key_json: &str) -> bool {
+        let key: VariantKey = match serde_json::from_str(key_json) {
+            Ok(k) => k,
+            Err(_) => return false,
+        };
+        match self.components.get_mut(comp_id) {
+            Some(comp) => comp.remove_variant(&key),
+            None => false,
+        }
+    }
+
+    /// Re-key an existing variant. Returns false if the old key is missing or the new key is taken.
+    pub fn rename_variant_key(&mut self, comp_id: u64, old_key_json: &str, new_key_json: &str) -> bool {
+        let old_key: VariantKey = match serde_json::from_str(old_key_json) {
+            Ok(k) => k,
+            Err(_) => return false,
+        };
+        let new_key: VariantKey = match serde_json::from_str(new_key_json) {
+            Ok(k) => k,
+            Err(_) => return false,
+        };
+        match self.components.get_mut(comp_id) {
+            Some(comp) => comp.rename_variant_key(&old_key, &new_key),
+            None => false,
+        }
+    }
+
     /// Add a slot definition to a component
     pub fn add_slot(&mut self, comp_id: u64, slot_name: &str, placeholder_node_id: u64) -> bool {
         if let Some(comp) = self.components.get_mut(comp_id) {
@@ -598,6 +2372,140 @@ impl Engine {
         }
     }
 
+    /// Remove a slot definition from a component, reverting its placeholder to a Frame
+    /// and clearing matching slot fills on instances. Returns false if the slot doesn't exist.
+    pub fn remove_slot(&mut self, comp_id: u64, slot_name: &str) -> bool {
+        let placeholder_id = match self.components.get_mut(comp_id) {
+            Some(comp) => {
+                let idx = match comp.slots.iter().position(|s| s.name == slot_name) {
+                    Some(i) => i,
+                    None => return false,
+                };
+                let slot = comp.slots.remove(idx);
+                // Revert the placeholder inside variant templates too
+                for variant in comp.variants.values_mut() {
+                    for node in variant.nodes.iter_mut().filter(|n| n.id == slot.placeholder_node_id) {
+                        if matches!(node.kind, NodeKind::Slot { .. }) {
+                            node.kind = NodeKind::Frame;
+                            node.name = node.name.trim_start_matches("[S] ").to_string();
+                        }
+                    }
+                }
+                slot.placeholder_node_id
+            }
+            None => return false,
+        };
+
+        if let Some(node) = self.scene.get_node_mut(placeholder_id) {
+            if matches!(node.kind, NodeKind::Slot { .. }) {
+                node.kind = NodeKind::Frame;
+                node.name = node.name.trim_start_matches("[S] ").to_string();
+            }
+        }
+
+        for id in self.scene.all_node_ids() {
+            if let Some(node) = self.scene.get_node_mut(id) {
+                if let NodeKind::Instance(data) = &mut node.kind {
+                    if data.component_id == comp_id {
+                        data.slot_fills.remove(slot_name);
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    /// Bind a template node's fill to a color variant property. Instances then take that
+    /// node's fill color from their value for the property.
+    pub fn bind_fill_to_prop(&mut self, comp_id: u64, node_id: u64, prop_name: &str) -> bool {
+        let comp = match self.components.get_mut(comp_id) {
+            Some(c) => c,
+            None => return false,
+        };
+        if !comp.properties.iter().any(|p| p.name == prop_name && matches!(p.prop_type, VariantPropType::Color)) {
+            return false;
+        }
+        let mut found = false;
+        for variant in comp.variants.values_mut() {
+            for node in variant.nodes.iter_mut().filter(|n| n.id == node_id) {
+                if let Some(fill) = node.fills.last_mut() {
+                    fill.bound_prop = Some(prop_name.to_string());
+                    found = true;
+                }
+            }
+        }
+        if let Some(fill) = self.scene.get_node_mut(node_id).and_then(|n| n.fills.last_mut()) {
+            fill.bound_prop = Some(prop_name.to_string());
+        }
+        found
+    }
+
+    /// Make a template node visible only for matching variant values, e.g. `{"hasIcon":{"Boolean":true}}`.
+    /// Pass "null" to clear the condition.
+    pub fn set_visible_when(&mut self, comp_id: u64, node_id: u64, condition_json: &str) -> bool {
+        let condition: Option<VariantKey> = match serde_json::from_str(condition_json) {
+            Ok(c) => c,
+            Err(_) => return false,
+        };
+        let comp = match self.components.get_mut(comp_id) {
+            Some(c) => c,
+            None => return false,
+        };
+        let mut found = false;
+        for variant in comp.variants.values_mut() {
+            for node in variant.nodes.iter_mut().filter(|n| n.id == node_id) {
+                node.visible_when = condition.clone();
+                found = true;
+            }
+        }
+        if let Some(node) = self.scene.get_node_mut(node_id) {
+            node.visible_when = condition;
+        }
+        found
+    }
+
+    /// Show/hide instance children according to their `visible_when` conditions
+    fn apply_visibility_conditions(&mut self, instance_id: u64) {
+        let (comp_id, mut values) = match self.scene.get_node(instance_id).map(|n| &n.kind) {
+            Some(NodeKind::Instance(data)) => (data.component_id, data.variant_values.clone()),
+            _ => return,
+        };
+        // Props missing from the instance's values fall back to their defaults
+        if let Some(comp) = self.components.get(comp_id) {
+            for (name, value) in comp.default_key() {
+                values.entry(name).or_insert(value);
+            }
+        }
+        let mut stack = self.scene.get_children_of(instance_id);
+        while let Some(id) = stack.pop() {
+            if let Some(node) = self.scene.get_node_mut(id) {
+                if let Some(cond) = &node.visible_when {
+                    node.visible = cond.iter().all(|(k, v)| values.get(k) == Some(v));
+                }
+                stack.extend(node.children.iter().copied());
+            }
+        }
+    }
+
+    /// Apply an instance's color property values to fills bound to those properties
+    fn apply_color_bindings(&mut self, instance_id: u64) {
+        let values = match self.scene.get_node(instance_id).map(|n| &n.kind) {
+            Some(NodeKind::Instance(data)) => data.variant_values.clone(),
+            _ => return,
+        };
+        let mut stack = vec![instance_id];
+        while let Some(id) = stack.pop() {
+            if let Some(node) = self.scene.get_node_mut(id) {
+                for fill in node.fills.iter_mut() {
+                    if let Some(VariantValue::Color(c)) = fill.bound_prop.as_ref().and_then(|p| values.get(p)) {
+                        fill.color = *c;
+                    }
+                }
+                stack.extend(node.children.iter().copied());
+            }
+        }
+    }
+
     /// Create an instance of a component at (x, y).
     /// Deep-clones the default variant's template into the scene.
     pub fn create_instance(&mut self, comp_id: u64, x: f64, y: f64) -> u64 {
@@ -621,6 +2529,7 @@ impl Engine {
             variant_values: default_key,
             slot_fills: std::collections::HashMap::new(),
             overrides: std::collections::HashMap::new(),
+            template_map: std::collections::HashMap::new(),
         })));
         instance_root.name = format!("[I] {}", comp.name);
 
@@ -628,9 +2537,10 @@ impl Engine {
         if let Some(template_root) = variant.nodes.first() {
             instance_root.width = template_root.width;
             instance_root.height = template_root.height;
-            instance_root.fill = template_root.fill.clone();
+            instance_root.fills = template_root.fills.clone();
             instance_root.stroke = template_root.stroke.clone();
             instance_root.corner_radius = template_root.corner_radius;
+            instance_root.corner_smoothing = template_root.corner_smoothing;
             instance_root.layout = template_root.layout.clone();
         }
 
@@ -640,17 +2550,34 @@ impl Engine {
         let root_id = self.scene.add_node(instance_root);
 
         // Deep-clone template children into scene as children of instance root
+        let mut template_map = std::collections::HashMap::new();
         if let Some(template_root) = variant.nodes.first() {
             let offset_x = x - template_root.x;
             let offset_y = y - template_root.y;
-            self.clone_template_children(template_root, &variant.nodes, root_id, offset_x, offset_y);
+            template_map.insert(template_root.id, root_id);
+            self.clone_template_children(template_root, &variant.nodes, root_id, offset_x, offset_y, &mut template_map);
         }
+        self.set_template_map(root_id, template_map);
+        self.apply_color_bindings(root_id);
+        self.apply_visibility_conditions(root_id);
 
         root_id
     }
 
-    fn clone_template_children(&mut self, template_parent: &Node, all_nodes: &[Node], scene_parent: u64, dx: f64, dy: f64) {
+    fn set_template_map(&mut self, instance_id: u64, template_map: std::collections::HashMap<u64, u64>) {
+        if let Some(node) = self.scene.get_node_mut(instance_id) {
+            if let NodeKind::Instance(data) = &mut node.kind {
+                data.template_map = template_map;
+            }
+        }
+    }
+
+    fn clone_template_children(&mut self, template_parent: &Node, all_nodes: &[Node], scene_parent: u64, dx: f64, dy: f64, template_map: &mut std::collections::HashMap<u64, u64>) {
         for &child_id in &template_parent.children {
+            // Already copied means the template has a child cycle
+            if template_map.contains_key(&child_id) {
+                continue;
+            }
             if let Some(template_child) = all_nodes.iter().find(|n| n.id == child_id) {
                 let mut new_node = template_child.clone();
                 // Only offset if parent doesn't have layout (layout will reposition)
@@ -663,8 +2590,18 @@ impl Engine {
                 }
                 new_node.parent = Some(scene_parent);
                 new_node.children = vec![];
+                // Slot contents of a nested instance belong to the template's scene, not this copy
+                if let NodeKind::Instance(data) = &mut new_node.kind {
+                    data.slot_fills.clear();
+                }
                 let new_id = self.scene.add_node(new_node);
-                self.clone_template_children(template_child, all_nodes, new_id, dx, dy);
+                template_map.insert(child_id, new_id);
+                // Nested instances are re-instantiated from their own component so they pick up
+                // its current template; fall back to a plain copy if that component is gone
+                if matches!(template_child.kind, NodeKind::Instance(_)) && self.reclone_instance(new_id) {
+                    continue;
+                }
+                self.clone_template_children(template_child, all_nodes, new_id, dx, dy, template_map);
             }
         }
     }
@@ -698,6 +2635,19 @@ impl Engine {
             None => return false,
         };
 
+        // Update instance variant values
+        if let Some(node) = self.scene.get_node_mut(instance_id) {
+            if let NodeKind::Instance(data) = &mut node.kind {
+                data.variant_values = key;
+            }
+        }
+        self.rebuild_instance(instance_id, &variant);
+        true
+    }
+
+    /// Replace an instance's children with a fresh clone of a variant template, keeping the
+    /// instance's position
+    fn rebuild_instance(&mut self, instance_id: u64, variant: &VariantData) {
         // Remove old children
         if let Some(node) = self.scene.get_node(instance_id) {
             let old_children = node.children.clone();
@@ -710,32 +2660,163 @@ impl Engine {
         let (x, y) = if let Some(node) = self.scene.get_node(instance_id) {
             (node.x, node.y)
         } else {
-            return false;
+            return;
         };
 
-        // Update instance variant values
         if let Some(node) = self.scene.get_node_mut(instance_id) {
-            if let NodeKind::Instance(data) = &mut node.kind {
-                data.variant_values = key;
-            }
             // Update geometry + layout from new variant
             if let Some(template_root) = variant.nodes.first() {
                 node.width = template_root.width;
                 node.height = template_root.height;
-                node.fill = template_root.fill.clone();
+                node.fills = template_root.fills.clone();
                 node.stroke = template_root.stroke.clone();
                 node.corner_radius = template_root.corner_radius;
+                node.corner_smoothing = template_root.corner_smoothing;
                 node.layout = template_root.layout.clone();
             }
         }
 
         // Clone new variant's children
+        let mut template_map = std::collections::HashMap::new();
         if let Some(template_root) = variant.nodes.first() {
             let dx = x - template_root.x;
             let dy = y - template_root.y;
-            self.clone_template_children(template_root, &variant.nodes, instance_id, dx, dy);
+            template_map.insert(template_root.id, instance_id);
+            self.clone_template_children(template_root, &variant.nodes, instance_id, dx, dy, &mut template_map);
+        }
+        self.set_template_map(instance_id, template_map);
+        self.apply_color_bindings(instance_id);
+        self.apply_visibility_conditions(instance_id);
+    }
+
+    /// Push a component's current state to every instance: each variant template is
+    /// re-captured from its source frame, then every instance re-clones its variant and
+    /// re-applies its overrides and slot contents. Returns the number of instances updated.
+    pub fn sync_instances(&mut self, comp_id: u64) -> u32 {
+        let comp = match self.components.get_mut(comp_id) {
+            Some(c) => c,
+            None => return 0,
+        };
+        let sources: Vec<(String, u64)> = comp.variants.iter().map(|(k, v)| (k.clone(), v.root_node_id)).collect();
+        for (key, root_id) in sources {
+            if self.scene.get_node(root_id).is_none() {
+                continue;
+            }
+            let nodes = self.deep_clone_subtree(root_id);
+            if let Some(variant) = self.components.get_mut(comp_id).and_then(|c| c.variants.get_mut(&key)) {
+                variant.nodes = nodes;
+            }
+        }
+
+        let mut synced = 0;
+        for instance_id in self.instances_of(comp_id) {
+            // Nested instances may already have been rebuilt away by their outer instance
+            let slot_fills = match self.scene.get_node(instance_id).map(|n| &n.kind) {
+                Some(NodeKind::Instance(data)) => data.slot_fills.clone(),
+                _ => continue,
+            };
+            // Lift slot contents out so the rebuild doesn't delete them
+            for &id in slot_fills.values().flatten() {
+                self.scene.reparent(id, None);
+            }
+            if !self.reclone_instance(instance_id) {
+                continue;
+            }
+            let mut kept = std::collections::HashMap::new();
+            for (slot_name, ids) in slot_fills {
+                // Contents of a slot the template no longer has stay on the canvas at top level
+                if let Some(slot_id) = self.find_slot_in_children(instance_id, &slot_name) {
+                    for &id in &ids {
+                        self.scene.reparent(id, Some(slot_id));
+                    }
+                    kept.insert(slot_name, ids);
+                }
+            }
+            if let Some(NodeKind::Instance(data)) = self.scene.get_node_mut(instance_id).map(|n| &mut n.kind) {
+                data.slot_fills = kept;
+            }
+            synced += 1;
         }
+        synced
+    }
+
+    /// Re-clone an instance from its component's current variant template and re-apply its
+    /// overrides. Returns false if the component or variant is missing, or if the instance sits
+    /// inside another instance of the same component (which would recurse forever).
+    fn reclone_instance(&mut self, instance_id: u64) -> bool {
+        let data = match self.scene.get_node(instance_id).map(|n| &n.kind) {
+            Some(NodeKind::Instance(data)) => (**data).clone(),
+            _ => return false,
+        };
+        let mut ancestor = self.scene.get_node(instance_id).and_then(|n| n.parent);
+        while let Some(id) = ancestor {
+            let node = match self.scene.get_node(id) {
+                Some(n) => n,
+                None => break,
+            };
+            if matches!(&node.kind, NodeKind::Instance(d) if d.component_id == data.component_id) {
+                return false;
+            }
+            ancestor = node.parent;
+        }
+        let variant = match self.components.get(data.component_id).and_then(|c| c.get_variant(&data.variant_values)) {
+            Some(v) => v.clone(),
+            None => return false,
+        };
+        // Overrides are keyed by scene node; carry them across by template node instead
+        let to_template: std::collections::HashMap<u64, u64> = data.template_map.iter().map(|(&t, &s)| (s, t)).collect();
+        let overrides: Vec<(u64, NodeOverrides)> = data.overrides.iter()
+            .filter_map(|(scene_id, o)| to_template.get(scene_id).map(|&t| (t, o.clone())))
+            .collect();
+        // Nodes inside nested instances aren't in our template map: key their overrides by the
+        // nested instance's template id plus the id in the nested component's template. `true`
+        // marks ones stored on this instance rather than on the nested one.
+        let mut nested: Vec<(u64, u64, NodeOverrides, bool)> = vec![];
+        for (&outer_template, &scene_id) in &data.template_map {
+            let inner = match self.scene.get_node(scene_id).map(|n| &n.kind) {
+                Some(NodeKind::Instance(inner)) if scene_id != instance_id => inner,
+                _ => continue,
+            };
+            let inner_to_template: std::collections::HashMap<u64, u64> = inner.template_map.iter().map(|(&t, &s)| (s, t)).collect();
+            let stored = inner.overrides.iter().map(|(s, o)| (s, o, false))
+                .chain(data.overrides.iter().filter(|(s, _)| !to_template.contains_key(s)).map(|(s, o)| (s, o, true)));
+            for (s, o, on_outer) in stored {
+                if let Some(&inner_template) = inner_to_template.get(s) {
+                    nested.push((outer_template, inner_template, o.clone(), on_outer));
+                }
+            }
+        }
+
+        self.rebuild_instance(instance_id, &variant);
 
+        let template_map = match self.scene.get_node(instance_id).map(|n| &n.kind) {
+            Some(NodeKind::Instance(data)) => data.template_map.clone(),
+            _ => return false,
+        };
+        let mut remapped = std::collections::HashMap::new();
+        for (template_id, o) in overrides {
+            if let Some(&target) = template_map.get(&template_id) {
+                self.apply_node_overrides(target, &o);
+                remapped.insert(target, o);
+            }
+        }
+        for (outer_template, inner_template, o, on_outer) in nested {
+            let Some(&nested_id) = template_map.get(&outer_template) else { continue };
+            let target = match self.scene.get_node(nested_id).map(|n| &n.kind) {
+                Some(NodeKind::Instance(inner)) => inner.template_map.get(&inner_template).copied(),
+                _ => None,
+            };
+            let Some(target) = target else { continue };
+            self.apply_node_overrides(target, &o);
+            if on_outer {
+                remapped.insert(target, o);
+            } else if let Some(NodeKind::Instance(inner)) = self.scene.get_node_mut(nested_id).map(|n| &mut n.kind) {
+                inner.overrides.insert(target, o);
+            }
+        }
+        if let Some(NodeKind::Instance(data)) = self.scene.get_node_mut(instance_id).map(|n| &mut n.kind) {
+            data.overrides = remapped;
+        }
         true
     }
 
@@ -762,22 +2843,17 @@ impl Engine {
     }
 
     fn find_slot_in_children(&self, parent_id: u64, slot_name: &str) -> Option<u64> {
-        if let Some(node) = self.scene.get_node(parent_id) {
-            for &child_id in &node.children {
-                if let Some(child) = self.scene.get_node(child_id) {
-                    if let NodeKind::Slot { slot_name: ref sn } = child.kind {
-                        if sn == slot_name {
-                            return Some(child_id);
-                        }
-                    }
-                    // Recurse
-                    if let Some(found) = self.find_slot_in_children(child_id, slot_name) {
-                        return Some(found);
+        let mut found = None;
+        self.scene.walk_subtree(parent_id, &mut std::collections::HashSet::new(), &mut |node| {
+            if found.is_none() && node.id != parent_id {
+                if let NodeKind::Slot { slot_name: ref sn } = node.kind {
+                    if sn == slot_name {
+                        found = Some(node.id);
                     }
                 }
             }
-        }
-        None
+        });
+        found
     }
 
     /// List all components
@@ -792,6 +2868,7 @@ impl Engine {
                         "type": match &p.prop_type {
                             VariantPropType::Boolean => "boolean".to_string(),
                             VariantPropType::String { options } => format!("string({})", options.join("|")),
+                            VariantPropType::Color => "color".to_string(),
                         },
                         "default": p.default_value.to_display(),
                     })
@@ -803,6 +2880,63 @@ impl Engine {
         serde_json::to_string(&list).unwrap_or_default()
     }
 
+    /// Get every combination of variant property values as JSON:
+    /// [{ key, label, explicit }] where `explicit` is false if the combination falls back to the default variant
+    pub fn get_variant_matrix(&self, comp_id: u64) -> String {
+        let comp = match self.components.get(comp_id) {
+            Some(c) => c,
+            None => return "[]".to_string(),
+        };
+        let matrix: Vec<_> = comp.variant_matrix().iter().map(|key| {
+            let mut label: Vec<_> = comp.properties.iter()
+                .filter_map(|p| key.get(&p.name).map(|v| format!("{}={}", p.name, v.to_display())))
+                .collect();
+            if label.is_empty() { label.push("default".to_string()); }
+            serde_json::json!({
+                "key": key,
+                "label": label.join(", "),
+                "explicit": comp.has_variant(key),
+            })
+        }).collect();
+        serde_json::to_string(&matrix).unwrap_or_default()
+    }
+
+    /// Render a component's default variant into a `size`×`size` PNG data URL, scaled to contain.
+    /// Returns an empty string if the component has no template or rendering fails.
+    pub fn get_component_thumbnail(&self, comp_id: u64, size: u32) -> String {
+        let comp = match self.components.get(comp_id) {
+            Some(c) => c,
+            None => return String::new(),
+        };
+        let variant = match comp.get_variant(&comp.default_key()) {
+            Some(v) => v,
+            None => return String::new(),
+        };
+        let root = match variant.nodes.first() {
+            Some(n) => n,
+            None => return String::new(),
+        };
+        let mut nodes = variant.nodes.clone();
+        nodes[0].parent = None;
+        let mut scene = Scene::import(crate::scene::SceneData {
+            version: crate::scene::SCENE_VERSION,
+            nodes,
+            root_children: vec![root.id],
+            next_id: 0,
+            selection_sets: std::collections::HashMap::new(),
+            guides: vec![],
+            styles: crate::styles::StyleStore::default(),
+        });
+        layout::compute_layouts(&mut scene);
+
+        let bounds = root.bounds();
+        let size = size.max(1);
+        let scale = (size as f64 / bounds.width.max(1.0)).min(size as f64 / bounds.height.max(1.0));
+        let offset_x = (size as f64 - bounds.width * scale) / 2.0;
+        let offset_y = (size as f64 - bounds.height * scale) / 2.0;
+        export::rasterize(&scene, bounds, scale, size, size, offset_x, offset_y, None).unwrap_or_default()
+    }
+
     /// Get component detail
     pub fn get_component(&self, comp_id: u64) -> String {
         match self.components.get(comp_id) {
@@ -811,36 +2945,154 @@ impl Engine {
         }
     }
 
-    /// Override a text property in an instance child
+    /// Override text, fill (`fill_hex`) or visibility of an instance child.
+    /// Returns false for bad JSON or an unparseable fill color.
     pub fn set_instance_override(&mut self, instance_id: u64, target_node_id: u64, override_json: &str) -> bool {
         let overrides: Result<NodeOverrides, _> = serde_json::from_str(override_json);
         let overrides = match overrides {
             Ok(o) => o,
             Err(_) => return false,
         };
+        if overrides.fill_hex.as_deref().is_some_and(|hex| Color::from_hex(hex).is_none()) {
+            return false;
+        }
 
-        // Apply text override directly to the scene node
-        if let Some(text) = &overrides.text {
-            if let Some(node) = self.scene.get_node_mut(target_node_id) {
-                if let NodeKind::Text { content, .. } = &mut node.kind {
-                    *content = text.clone();
-                }
+        self.apply_node_overrides(target_node_id, &overrides);
+
+        // Store override in instance data
+        if let Some(node) = self.scene.get_node_mut(instance_id) {
+            if let NodeKind::Instance(data) = &mut node.kind {
+                data.overrides.insert(target_node_id, overrides);
             }
         }
 
+        true
+    }
+
+    /// Apply stored override values directly to an instance's scene node
+    fn apply_node_overrides(&mut self, target_node_id: u64, overrides: &NodeOverrides) {
+        let Some(node) = self.scene.get_node_mut(target_node_id) else { return };
+        if let Some(text) = &overrides.text {
+            if let NodeKind::Text { content, .. } = &mut node.kind {
+                *content = text.clone();
+            }
+        }
         if let Some(vis) = overrides.visible {
-            if let Some(node) = self.scene.get_node_mut(target_node_id) {
-                node.visible = vis;
+            node.visible = vis;
+        }
+        if let Some(color) = overrides.fill_hex.as_deref().and_then(Color::from_hex) {
+            node.set_top_fill_color(color);
+            node.fill_style = None;
+        }
+    }
+
+    // =============================================
+    // Exposed Properties
+    // =============================================
+
+    /// Expose a template child's text/fill/visibility for direct editing on instances.
+    /// `kind` is "text", "fill", or "visible". Replaces an existing exposed prop with the same name.
+    pub fn add_exposed_property(&mut self, comp_id: u64, name: &str, target_node_id: u64, kind: &str) -> bool {
+        let kind = match kind {
+            "text" => ExposedPropKind::Text,
+            "fill" => ExposedPropKind::Fill,
+            "visible" => ExposedPropKind::Visible,
+            _ => return false,
+        };
+        let comp = match self.components.get_mut(comp_id) {
+            Some(c) => c,
+            None => return false,
+        };
+        let in_template = comp.variants.values().any(|v| v.nodes.iter().any(|n| n.id == target_node_id));
+        if !in_template {
+            return false;
+        }
+        comp.exposed.retain(|e| e.name != name);
+        comp.exposed.push(ExposedProp {
+            name: name.to_string(),
+            target_node_id,
+            kind,
+        });
+        true
+    }
+
+    /// List a component's exposed properties as JSON: [{ name, target_node_id, kind }]
+    pub fn get_exposed_properties(&self, comp_id: u64) -> String {
+        match self.components.get(comp_id) {
+            Some(c) => serde_json::to_string(&c.exposed).unwrap_or_default(),
+            None => "[]".to_string(),
+        }
+    }
+
+    /// Set an exposed property on an instance. `value_json` is a JSON string for text,
+    /// a hex color string for fill, or a bool for visible. Stored as an instance override.
+    pub fn set_exposed_property(&mut self, instance_id: u64, name: &str, value_json: &str) -> bool {
+        let value: serde_json::Value = match serde_json::from_str(value_json) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        let (comp_id, template_map) = match self.scene.get_node(instance_id).map(|n| &n.kind) {
+            Some(NodeKind::Instance(data)) => (data.component_id, data.template_map.clone()),
+            _ => return false,
+        };
+        let prop = match self.components.get(comp_id).and_then(|c| c.exposed.iter().find(|e| e.name == name)) {
+            Some(p) => p.clone(),
+            None => return false,
+        };
+        let target_id = match template_map.get(&prop.target_node_id) {
+            Some(&id) => id,
+            None => return false,
+        };
+
+        let mut overrides = match self.scene.get_node(instance_id).map(|n| &n.kind) {
+            Some(NodeKind::Instance(data)) => data.overrides.get(&target_id).cloned().unwrap_or_default(),
+            _ => return false,
+        };
+        match prop.kind {
+            ExposedPropKind::Text => {
+                let text = match value.as_str() {
+                    Some(s) => s.to_string(),
+                    None => return false,
+                };
+                if let Some(node) = self.scene.get_node_mut(target_id) {
+                    if let NodeKind::Text { content, .. } = &mut node.kind {
+                        *content = text.clone();
+                    }
+                }
+                overrides.text = Some(text);
+            }
+            ExposedPropKind::Fill => {
+                let hex = match value.as_str() {
+                    Some(s) => s.to_string(),
+                    None => return false,
+                };
+                let color = match Color::from_hex(&hex) {
+                    Some(c) => c,
+                    None => return false,
+                };
+                if let Some(node) = self.scene.get_node_mut(target_id) {
+                    node.set_top_fill_color(color);
+                    node.fill_style = None;
+                }
+                overrides.fill_hex = Some(hex);
+            }
+            ExposedPropKind::Visible => {
+                let visible = match value.as_bool() {
+                    Some(b) => b,
+                    None => return false,
+                };
+                if let Some(node) = self.scene.get_node_mut(target_id) {
+                    node.visible = visible;
+                }
+                overrides.visible = Some(visible);
             }
         }
 
-        // Store override in instance data
         if let Some(node) = self.scene.get_node_mut(instance_id) {
             if let NodeKind::Instance(data) = &mut node.kind {
-                data.overrides.insert(target_node_id, overrides);
+                data.overrides.insert(target_id, overrides);
             }
         }
-
         true
     }
 
@@ -875,6 +3127,47 @@ impl Engine {
         }
     }
 
+    /// Turn an instance into a plain frame: children stay as they are, but the link to its
+    /// component (variant, overrides, slots) is dropped so component edits no longer reach it.
+    /// One undo step. Returns the same id, or 0 if it isn't an instance.
+    pub fn detach_instance(&mut self, instance_id: u64) -> u64 {
+        if !matches!(self.scene.get_node(instance_id).map(|n| &n.kind), Some(NodeKind::Instance(_))) {
+            return 0;
+        }
+        self.push_undo();
+        self.detach_instance_node(instance_id);
+        instance_id
+    }
+
+    /// Turn a node into a plain frame, dropping its instance link and the "[I] " name prefix
+    fn detach_instance_node(&mut self, id: u64) {
+        if let Some(node) = self.scene.get_node_mut(id) {
+            node.kind = NodeKind::Frame;
+            node.name = node.name.trim_start_matches("[I] ").to_string();
+        }
+    }
+
+    /// Ids of every instance of a component in the scene, ascending
+    fn instances_of(&self, comp_id: u64) -> Vec<u64> {
+        let mut ids: Vec<u64> = self.scene.all_node_ids().into_iter()
+            .filter(|&id| matches!(
+                self.scene.get_node(id).map(|n| &n.kind),
+                Some(NodeKind::Instance(data)) if data.component_id == comp_id
+            ))
+            .collect();
+        ids.sort();
+        ids
+    }
+
+    /// Instances of a component ("where is this used") as JSON: [{ id, name }]
+    pub fn get_component_instances(&self, comp_id: u64) -> String {
+        let list: Vec<_> = self.instances_of(comp_id).into_iter()
+            .filter_map(|id| self.scene.get_node(id))
+            .map(|n| serde_json::json!({ "id": n.id, "name": n.name }))
+            .collect();
+        serde_json::to_string(&list).unwrap_or_else(|_| "[]".to_string())
+    }
+
     // =============================================
     // Text Properties (Stage 2 & 3)
     // =============================================
@@ -882,7 +3175,7 @@ impl Engine {
     pub fn set_line_height(&mut self, id: u64, value: f64) {
         if let Some(node) = self.scene.get_node_mut(id) {
             if let NodeKind::Text { ref mut line_height, .. } = node.kind {
-                *line_height = value.max(0.5).min(5.0);
+                *line_height = value.clamp(0.5, 5.0);
             }
         }
     }
@@ -918,6 +3211,99 @@ impl Engine {
         }
     }
 
+    /// Set extra spacing between paragraphs (hard line breaks) of a text node
+    pub fn set_paragraph_gap(&mut self, id: u64, px: f64) {
+        if let Some(node) = self.scene.get_node_mut(id) {
+            if let NodeKind::Text { ref mut paragraph_gap, .. } = node.kind {
+                *paragraph_gap = px.max(0.0);
+            }
+        }
+    }
+
+    /// Add a drop shadow effect (offset and blur in scene px)
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_drop_shadow(&mut self, id: u64, r: u8, g: u8, b: u8, a: f64, offset_x: f64, offset_y: f64, blur: f64) -> bool {
+        match self.scene.get_node_mut(id) {
+            Some(node) => {
+                node.effects.push(Effect::DropShadow { color: Color { r, g, b, a }, offset_x, offset_y, blur: blur.max(0.0) });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Set the node's drop shadow, replacing its first one (or adding one). Offset and blur
+    /// are in scene px, so the shadow scales with zoom like the shape does.
+    #[allow(clippy::too_many_arguments)]
+    pub fn set_shadow(&mut self, id: u64, r: u8, g: u8, b: u8, a: f64, offset_x: f64, offset_y: f64, blur: f64) -> bool {
+        let Some(node) = self.scene.get_node_mut(id) else { return false };
+        let shadow = Effect::DropShadow { color: Color { r, g, b, a }, offset_x, offset_y, blur: blur.max(0.0) };
+        match node.effects.iter_mut().find(|e| matches!(e, Effect::DropShadow { .. })) {
+            Some(existing) => *existing = shadow,
+            None => node.effects.insert(0, shadow),
+        }
+        true
+    }
+
+    /// Remove every drop shadow from a node
+    pub fn clear_shadow(&mut self, id: u64) -> bool {
+        let Some(node) = self.scene.get_node_mut(id) else { return false };
+        node.effects.retain(|e| !matches!(e, Effect::DropShadow { .. }));
+        true
+    }
+
+    /// Remove the effect at `index`
+    pub fn remove_effect(&mut self, id: u64, index: usize) -> bool {
+        match self.scene.get_node_mut(id) {
+            Some(node) if index < node.effects.len() => {
+                node.effects.remove(index);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Get a node's effects as JSON
+    pub fn get_effects(&self, id: u64) -> String {
+        self.scene.get_node(id)
+            .map(|n| serde_json::to_string(&n.effects).unwrap_or_default())
+            .unwrap_or_else(|| "[]".to_string())
+    }
+
+    /// Set text direction: "ltr" or "rtl"
+    pub fn set_text_direction(&mut self, id: u64, dir: &str) {
+        if let Some(node) = self.scene.get_node_mut(id) {
+            if let NodeKind::Text { ref mut direction, .. } = node.kind {
+                *direction = match dir {
+                    "rtl" => TextDirection::Rtl,
+                    _ => TextDirection::Ltr,
+                };
+            }
+        }
+    }
+
+    /// Draw a text node's outline beneath its fill (true) or on top of it (false)
+    pub fn set_text_stroke_under(&mut self, id: u64, under: bool) {
+        if let Some(node) = self.scene.get_node_mut(id) {
+            if let NodeKind::Text { ref mut stroke_under, .. } = node.kind {
+                *stroke_under = under;
+            }
+        }
+    }
+
+    /// Set list formatting: "none", "bullet", "numbered"
+    pub fn set_list_style(&mut self, id: u64, style: &str) {
+        if let Some(node) = self.scene.get_node_mut(id) {
+            if let NodeKind::Text { ref mut list_style, .. } = node.kind {
+                *list_style = match style {
+                    "bullet" => ListStyle::Bullet,
+                    "numbered" => ListStyle::Numbered,
+                    _ => ListStyle::None,
+                };
+            }
+        }
+    }
+
     // =============================================
     // Text Sizing
     // =============================================
@@ -959,6 +3345,53 @@ impl Engine {
         }
     }
 
+    /// Set resize constraints: each axis is "start", "end", "stretch", "center", or "scale"
+    pub fn set_constraints(&mut self, id: u64, horizontal: &str, vertical: &str) -> bool {
+        let parse = |s: &str| match s {
+            "start" => Some(Constraint::Start),
+            "end" => Some(Constraint::End),
+            "stretch" => Some(Constraint::Stretch),
+            "center" => Some(Constraint::Center),
+            "scale" => Some(Constraint::Scale),
+            _ => None,
+        };
+        let (Some(horizontal), Some(vertical)) = (parse(horizontal), parse(vertical)) else {
+            return false;
+        };
+        match self.scene.get_node_mut(id) {
+            Some(node) => {
+                node.constraints = Constraints { horizontal, vertical };
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Take a child out of its auto-layout parent's flow so it keeps its own position
+    pub fn set_layout_absolute(&mut self, id: u64, absolute: bool) {
+        if let Some(node) = self.scene.get_node_mut(id) {
+            node.layout_absolute = absolute;
+        }
+    }
+
+    /// Set min/max width and height; NaN leaves that bound unset
+    pub fn set_size_constraints(&mut self, id: u64, min_w: f64, max_w: f64, min_h: f64, max_h: f64) {
+        let bound = |v: f64| if v.is_nan() { None } else { Some(v.max(0.0)) };
+        if let Some(node) = self.scene.get_node_mut(id) {
+            node.min_width = bound(min_w);
+            node.max_width = bound(max_w);
+            node.min_height = bound(min_h);
+            node.max_height = bound(max_h);
+        }
+    }
+
+    /// Set how much of a flex parent's free space a child takes (0 = keep its own size)
+    pub fn set_flex_grow(&mut self, id: u64, value: f64) {
+        if let Some(node) = self.scene.get_node_mut(id) {
+            node.flex_grow = value.max(0.0);
+        }
+    }
+
     /// Set flex direction: "row" or "column"
     pub fn set_flex_direction(&mut self, id: u64, dir: &str) {
         if let Some(node) = self.scene.get_node_mut(id) {
@@ -1000,6 +3433,29 @@ impl Engine {
         }
     }
 
+    /// Same padding on all four sides (CSS `padding: all`)
+    pub fn set_padding_uniform(&mut self, id: u64, all: f64) {
+        self.set_layout_padding(id, all, all, all, all);
+    }
+
+    /// Vertical (top/bottom) and horizontal (left/right) padding (CSS `padding: v h`)
+    pub fn set_padding_axes(&mut self, id: u64, vertical: f64, horizontal: f64) {
+        self.set_layout_padding(id, vertical, horizontal, vertical, horizontal);
+    }
+
+    /// Padding as JSON { top, right, bottom, left }, or "null" if not found
+    pub fn get_padding(&self, id: u64) -> String {
+        match self.scene.get_node(id) {
+            Some(node) => serde_json::json!({
+                "top": node.layout.padding_top,
+                "right": node.layout.padding_right,
+                "bottom": node.layout.padding_bottom,
+                "left": node.layout.padding_left,
+            }).to_string(),
+            None => "null".to_string(),
+        }
+    }
+
     /// Set grid columns
     pub fn set_grid_columns(&mut self, id: u64, cols: u32) {
         if let Some(node) = self.scene.get_node_mut(id) {
@@ -1007,6 +3463,29 @@ impl Engine {
         }
     }
 
+    /// Set how wrapped flex lines are spread along the cross axis:
+    /// "start", "center", "end", "space-between", "space-around", "space-evenly"
+    pub fn set_align_content(&mut self, id: u64, align: &str) {
+        if let Some(node) = self.scene.get_node_mut(id) {
+            node.layout.align_content = parse_justify(align);
+        }
+    }
+
+    /// Set a fixed grid row count (0 = rows fit their content)
+    pub fn set_grid_rows(&mut self, id: u64, rows: u32) {
+        if let Some(node) = self.scene.get_node_mut(id) {
+            node.layout.grid_rows = rows;
+        }
+    }
+
+    /// Set separate grid gaps between rows and between columns
+    pub fn set_grid_gaps(&mut self, id: u64, row_gap: f64, col_gap: f64) {
+        if let Some(node) = self.scene.get_node_mut(id) {
+            node.layout.row_gap = Some(row_gap.max(0.0));
+            node.layout.column_gap = Some(col_gap.max(0.0));
+        }
+    }
+
     /// Set flex wrap: "nowrap" or "wrap"
     pub fn set_flex_wrap(&mut self, id: u64, wrap: &str) {
         if let Some(node) = self.scene.get_node_mut(id) {
@@ -1017,6 +3496,21 @@ impl Engine {
         }
     }
 
+    /// Detect a clean row/column of children in a free-form frame and convert it to
+    /// flex auto-layout (direction, gap, padding, alignment). Returns false if not linear.
+    pub fn infer_layout(&mut self, frame_id: u64) -> bool {
+        let (layout, order) = match layout::infer_flex(&self.scene, frame_id) {
+            Some(r) => r,
+            None => return false,
+        };
+        self.push_undo();
+        if let Some(node) = self.scene.get_node_mut(frame_id) {
+            node.layout = layout;
+            node.children = order;
+        }
+        true
+    }
+
     /// Get layout as JSON
     pub fn get_layout(&self, id: u64) -> String {
         if let Some(node) = self.scene.get_node(id) {
@@ -1038,6 +3532,8 @@ impl Engine {
                 content: content.to_string(),
                 tags,
                 updated_at: js_sys::Date::now() as u64,
+                replies: vec![],
+                resolved: false,
             });
             true
         } else {
@@ -1045,6 +3541,35 @@ impl Engine {
         }
     }
 
+    /// Append a reply to a note's thread
+    pub fn reply_to_note(&mut self, node_id: u64, index: usize, content: &str, tags_json: &str) -> bool {
+        let tags: Vec<String> = serde_json::from_str(tags_json).unwrap_or_default();
+        match self.scene.get_node_mut(node_id).and_then(|n| n.notes.get_mut(index)) {
+            Some(note) => {
+                note.replies.push(Note {
+                    content: content.to_string(),
+                    tags,
+                    updated_at: js_sys::Date::now() as u64,
+                    replies: vec![],
+                    resolved: false,
+                });
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Mark a note thread resolved or reopen it
+    pub fn resolve_note(&mut self, node_id: u64, index: usize, resolved: bool) -> bool {
+        match self.scene.get_node_mut(node_id).and_then(|n| n.notes.get_mut(index)) {
+            Some(note) => {
+                note.resolved = resolved;
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Update a note by index
     pub fn update_note(&mut self, node_id: u64, index: usize, content: &str) -> bool {
         if let Some(node) = self.scene.get_node_mut(node_id) {
@@ -1077,6 +3602,25 @@ impl Engine {
         }
     }
 
+    /// Show/hide every node carrying a note with the given tag. Returns the number of nodes affected.
+    pub fn set_visibility_by_tag(&mut self, tag: &str, visible: bool) -> u32 {
+        let ids: Vec<u64> = self.scene.all_node_ids().into_iter()
+            .filter(|&id| {
+                self.scene.get_node(id)
+                    .map(|n| n.notes.iter().any(|note| note.tags.iter().any(|t| t == tag)))
+                    .unwrap_or(false)
+            })
+            .collect();
+        if ids.is_empty() {
+            return 0;
+        }
+        self.push_undo();
+        for &id in &ids {
+            self.set_visible(id, visible);
+        }
+        ids.len() as u32
+    }
+
     /// Get node JSON enriched with notes (for agent consumption)
     pub fn get_node_with_notes(&self, node_id: u64) -> String {
         if let Some(node) = self.scene.get_node(node_id) {
@@ -1099,3 +3643,1114 @@ impl Engine {
         }
     }
 }
+
+/// Why `import_scene` rejected a document
+enum ImportError {
+    Parse(serde_json::Error),
+    /// Saved by a newer build with this format version
+    Version(u32),
+}
+
+/// Buffer produced by `copy_selection`: each subtree root first, ids as in the source document
+#[derive(serde::Serialize, serde::Deserialize)]
+struct Clipboard {
+    subtrees: Vec<Vec<Node>>,
+    /// Name of each component the copied instances use, so a paste can tell whether this
+    /// document has the same component
+    #[serde(default)]
+    components: std::collections::HashMap<u64, String>,
+    /// Shared styles the copied nodes link to
+    #[serde(default)]
+    styles: StyleStore,
+}
+
+/// One entry of `add_nodes_batch`
+#[derive(serde::Deserialize)]
+struct NodeSpec {
+    kind: String,
+    #[serde(default)]
+    x: f64,
+    #[serde(default)]
+    y: f64,
+    #[serde(default)]
+    width: f64,
+    #[serde(default)]
+    height: f64,
+    fill: Option<String>,
+    parent: Option<u64>,
+    name: Option<String>,
+    content: Option<String>,
+    font_size: Option<f64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn import_history_validates_and_caps_both_stacks() {
+        let mut engine = Engine::new(800.0, 600.0);
+        engine.add_rect(0.0, 0.0, 10.0, 10.0);
+        let scene = serde_json::to_string(&engine.scene.export()).unwrap();
+        let many = vec![scene.clone(); 150];
+        let json = serde_json::json!({ "undo": many, "redo": many }).to_string();
+        assert!(engine.import_history(&json));
+        assert_eq!((engine.undo_stack.len(), engine.redo_stack.len()), (100, 100));
+        let bad = serde_json::json!({ "undo": [scene.clone()], "redo": [scene, "not a scene"] }).to_string();
+        assert!(!engine.import_history(&bad));
+        assert_eq!((engine.undo_stack.len(), engine.redo_stack.len()), (100, 100));
+        assert!(!engine.import_history("{\"undo\": [\"{}\"], \"redo\": []}"));
+    }
+
+    #[test]
+    fn visible_when_follows_boolean_variant() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 200.0, 100.0);
+        let icon = engine.add_rect(10.0, 10.0, 20.0, 20.0);
+        engine.reparent_node(icon, Some(frame), false);
+
+        let comp = engine.create_component(frame, "Button");
+        assert!(engine.add_variant_prop(comp, "hasIcon", r#"{"type":"boolean","default":false}"#));
+        assert!(engine.set_visible_when(comp, icon, r#"{"hasIcon":{"Boolean":true}}"#));
+
+        let instance = engine.create_instance(comp, 300.0, 0.0);
+        let child = engine.scene.get_children_of(instance)[0];
+        assert!(!engine.scene.get_node(child).unwrap().visible);
+
+        assert!(engine.set_instance_variant(instance, r#"{"hasIcon":{"Boolean":true}}"#));
+        let child = engine.scene.get_children_of(instance)[0];
+        assert!(engine.scene.get_node(child).unwrap().visible);
+
+        assert!(engine.set_instance_variant(instance, r#"{"hasIcon":{"Boolean":false}}"#));
+        let child = engine.scene.get_children_of(instance)[0];
+        assert!(!engine.scene.get_node(child).unwrap().visible);
+    }
+
+    #[test]
+    fn connector_follows_endpoints_and_dies_with_them() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let a = engine.add_rect(0.0, 0.0, 100.0, 100.0);
+        let b = engine.add_rect(300.0, 0.0, 100.0, 100.0);
+        let c = engine.add_connector(a, b, "straight");
+        let end = |e: &Engine| {
+            let n = e.scene.get_node(c).unwrap();
+            let NodeKind::Connector { points, .. } = &n.kind else { unreachable!() };
+            let (first, last) = (points[0], *points.last().unwrap());
+            ((n.x + first.x, n.y + first.y), (n.x + last.x, n.y + last.y))
+        };
+        assert_eq!(end(&engine), ((100.0, 50.0), (300.0, 50.0)));
+
+        engine.move_node(b, 0.0, 200.0, false);
+        layout::compute_layouts(&mut engine.scene);
+        let (start, _) = end(&engine);
+        assert!(start.1 > 50.0, "route should re-aim toward the moved endpoint");
+
+        engine.remove_node(b);
+        assert!(engine.scene.get_node(c).is_none());
+    }
+
+    #[test]
+    fn batch_add_skips_invalid_specs() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 200.0, 200.0);
+        let ids: Vec<u64> = serde_json::from_str(&engine.add_nodes_batch(&format!(
+            r##"[{{"kind":"rect","x":10,"y":10,"width":20,"height":20,"fill":"#ff0000","parent":{}}},
+                {{"kind":"star","width":5,"height":5}},
+                {{"kind":"ellipse","width":0,"height":5}},
+                {{"kind":"text","content":"Hi","name":"Label"}}]"##,
+            frame,
+        ))).unwrap();
+        assert_eq!(ids.len(), 2);
+        assert_eq!(engine.scene.get_node(ids[0]).unwrap().parent, Some(frame));
+        assert_eq!(engine.scene.get_node(ids[1]).unwrap().name, "Label");
+        assert!(engine.last_error().contains("spec 1") && engine.last_error().contains("spec 2"));
+
+        engine.undo();
+        assert!(engine.scene.get_node(ids[0]).is_none());
+
+        // A batch that creates nothing leaves no undo step behind
+        let undo_len = engine.undo_stack.len();
+        assert_eq!(engine.add_nodes_batch(r#"[{"kind":"star","width":5,"height":5}]"#), "[]");
+        assert_eq!(engine.undo_stack.len(), undo_len);
+    }
+
+    #[test]
+    fn text_height_tracks_line_count() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let text = engine.add_text(0.0, 0.0, "one", 10.0);
+        engine.set_text_content(text, "one\ntwo\nthree");
+        assert_eq!(engine.scene.get_node(text).unwrap().height, 36.0);
+        engine.set_font_size(text, 20.0);
+        assert_eq!(engine.scene.get_node(text).unwrap().height, 72.0);
+    }
+
+    #[test]
+    fn z_order_commands_reorder_layers() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let a = engine.add_rect(0.0, 0.0, 10.0, 10.0);
+        let b = engine.add_rect(0.0, 0.0, 10.0, 10.0);
+        let c = engine.add_rect(0.0, 0.0, 10.0, 10.0);
+        let order = |e: &Engine| -> Vec<u64> {
+            let layers: Vec<serde_json::Value> = serde_json::from_str(&e.get_layer_list()).unwrap();
+            layers.iter().map(|l| l["id"].as_u64().unwrap()).collect()
+        };
+        assert!(engine.bring_to_front(a));
+        assert_eq!(order(&engine), vec![b, c, a]);
+        assert!(engine.send_to_back(c));
+        assert_eq!(order(&engine), vec![c, b, a]);
+        assert!(engine.bring_forward(c));
+        assert_eq!(order(&engine), vec![b, c, a]);
+        assert!(engine.send_backward(a));
+        assert_eq!(order(&engine), vec![b, a, c]);
+        assert!(!engine.send_to_back(b));
+    }
+
+    #[test]
+    fn single_node_aligns_within_parent_padding() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 200.0, 100.0);
+        let child = engine.add_rect(50.0, 30.0, 20.0, 20.0);
+        engine.reparent_node(child, Some(frame), false);
+        engine.set_padding_uniform(frame, 10.0);
+        engine.select(child);
+
+        assert!(engine.align_selection("right"));
+        assert_eq!(engine.scene.get_node(child).unwrap().x, 170.0);
+        assert!(engine.align_selection("center-vertical"));
+        assert_eq!(engine.scene.get_node(child).unwrap().y, 40.0);
+        engine.undo();
+        assert_eq!(engine.scene.get_node(child).unwrap().y, 30.0);
+    }
+
+    #[test]
+    fn distribute_equalizes_edge_gaps_for_mixed_sizes() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let a = engine.add_rect(0.0, 0.0, 10.0, 10.0);
+        let wide = engine.add_rect(20.0, 0.0, 60.0, 10.0);
+        let c = engine.add_rect(190.0, 0.0, 10.0, 10.0);
+        engine.select(c);
+        engine.add_to_selection(a);
+        engine.add_to_selection(wide);
+        assert!(!engine.distribute_selection("diagonal"));
+
+        assert!(engine.distribute_selection("horizontal"));
+        // 200 span - 80 of widths leaves two 60px gaps
+        assert_eq!(engine.scene.get_node(wide).unwrap().x, 70.0);
+        assert_eq!(engine.scene.get_node(c).unwrap().x, 190.0);
+    }
+
+    #[test]
+    fn flex_grow_splits_free_space_by_weight() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 390.0, 100.0);
+        let fixed = engine.add_rect(0.0, 0.0, 100.0, 20.0);
+        let one = engine.add_rect(0.0, 0.0, 10.0, 20.0);
+        let two = engine.add_rect(0.0, 0.0, 10.0, 20.0);
+        for id in [fixed, one, two] {
+            engine.reparent_node(id, Some(frame), false);
+        }
+        engine.set_layout_mode(frame, "flex");
+        engine.set_layout_gap(frame, 10.0);
+        engine.set_flex_grow(one, 1.0);
+        engine.set_flex_grow(two, 2.0);
+
+        layout::compute_layouts(&mut engine.scene);
+        let width = |e: &Engine, id| e.scene.get_node(id).unwrap().width;
+        assert_eq!((width(&engine, fixed), width(&engine, one), width(&engine, two)), (100.0, 90.0, 180.0));
+        assert_eq!(engine.scene.get_node(two).unwrap().x, 210.0);
+
+        // Grown children shrink back with their parent
+        engine.resize_node(frame, 240.0, 100.0, false);
+        layout::compute_layouts(&mut engine.scene);
+        assert_eq!((width(&engine, one), width(&engine, two)), (40.0, 80.0));
+    }
+
+    #[test]
+    fn size_limits_bound_grow_and_stretch() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 300.0, 100.0);
+        let capped = engine.add_rect(0.0, 0.0, 10.0, 20.0);
+        let open = engine.add_rect(0.0, 0.0, 10.0, 20.0);
+        engine.reparent_node(capped, Some(frame), false);
+        engine.reparent_node(open, Some(frame), false);
+        engine.set_layout_mode(frame, "flex");
+        engine.set_align_items(frame, "stretch");
+        engine.set_flex_grow(capped, 1.0);
+        engine.set_flex_grow(open, 1.0);
+        engine.set_size_constraints(capped, f64::NAN, 100.0, f64::NAN, 60.0);
+
+        layout::compute_layouts(&mut engine.scene);
+        let c = engine.scene.get_node(capped).unwrap();
+        assert_eq!((c.width, c.height), (100.0, 60.0));
+        // The capped child's unused share goes to its sibling
+        assert_eq!(engine.scene.get_node(open).unwrap().width, 200.0);
+
+        engine.set_size_constraints(open, 50.0, f64::NAN, f64::NAN, f64::NAN);
+        engine.resize_node(open, 10.0, 10.0, true);
+        assert_eq!(engine.scene.get_node(open).unwrap().width, 50.0);
+    }
+
+    #[test]
+    fn absolute_children_skip_flex_flow() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 300.0, 100.0);
+        let badge = engine.add_rect(280.0, -5.0, 10.0, 10.0);
+        let item = engine.add_rect(50.0, 50.0, 40.0, 20.0);
+        engine.reparent_node(badge, Some(frame), false);
+        engine.reparent_node(item, Some(frame), false);
+        engine.set_layout_mode(frame, "flex");
+        engine.set_layout_absolute(badge, true);
+
+        layout::compute_layouts(&mut engine.scene);
+        let b = engine.scene.get_node(badge).unwrap();
+        assert_eq!((b.x, b.y), (280.0, -5.0));
+        // The flowing child starts the row as if the badge weren't there
+        assert_eq!(engine.scene.get_node(item).unwrap().x, 0.0);
+        assert_eq!(engine.scene.hit_test(Point { x: 285.0, y: 0.0 }), Some(badge));
+    }
+
+    #[test]
+    fn fixed_grid_rows_fill_cells_with_separate_gaps() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 210.0, 110.0);
+        let cells: Vec<u64> = (0..4).map(|_| engine.add_rect(0.0, 0.0, 10.0, 10.0)).collect();
+        for &c in &cells {
+            engine.reparent_node(c, Some(frame), false);
+        }
+        engine.set_layout_mode(frame, "grid");
+        engine.set_grid_columns(frame, 2);
+        engine.set_grid_rows(frame, 2);
+        engine.set_grid_gaps(frame, 10.0, 30.0);
+
+        layout::compute_layouts(&mut engine.scene);
+        let last = engine.scene.get_node(cells[3]).unwrap();
+        assert_eq!((last.x, last.y, last.width, last.height), (120.0, 60.0, 90.0, 50.0));
+    }
+
+    #[test]
+    fn aspect_locked_resize_keeps_ratio() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let id = engine.add_rect(0.0, 0.0, 200.0, 100.0);
+        let size = |e: &Engine| {
+            let n = e.scene.get_node(id).unwrap();
+            (n.width, n.height)
+        };
+        assert!(engine.resize_node_keep_ratio(id, 300.0, 120.0));
+        assert_eq!(size(&engine), (300.0, 120.0));
+
+        engine.set_aspect_locked(id, true);
+        // Width changed more (2x), so height follows it
+        engine.resize_node_keep_ratio(id, 600.0, 130.0);
+        assert_eq!(size(&engine), (600.0, 240.0));
+        // Height changed more, so width follows it
+        engine.resize_node_keep_ratio(id, 610.0, 60.0);
+        assert_eq!(size(&engine), (150.0, 60.0));
+    }
+
+    #[test]
+    fn flex_wrap_breaks_into_lines() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 100.0, 200.0);
+        let chips: Vec<u64> = [(40.0, 20.0), (40.0, 30.0), (40.0, 20.0), (90.0, 20.0)].iter()
+            .map(|&(w, h)| engine.add_rect(0.0, 0.0, w, h))
+            .collect();
+        for &c in &chips {
+            engine.reparent_node(c, Some(frame), false);
+        }
+        engine.set_layout_mode(frame, "flex");
+        engine.set_flex_wrap(frame, "wrap");
+        engine.set_layout_gap(frame, 10.0);
+        engine.set_align_items(frame, "center");
+
+        layout::compute_layouts(&mut engine.scene);
+        let pos = |e: &Engine, id| {
+            let n = e.scene.get_node(id).unwrap();
+            (n.x, n.y)
+        };
+        // First line holds two chips and is 30 tall; the shorter chip centers within it
+        assert_eq!(pos(&engine, chips[0]), (0.0, 5.0));
+        assert_eq!(pos(&engine, chips[1]), (50.0, 0.0));
+        assert_eq!(pos(&engine, chips[2]), (0.0, 40.0));
+        assert_eq!(pos(&engine, chips[3]), (0.0, 70.0));
+
+        engine.set_align_content(frame, "end");
+        layout::compute_layouts(&mut engine.scene);
+        assert_eq!(pos(&engine, chips[3]), (0.0, 180.0));
+    }
+
+    #[test]
+    fn frame_resize_applies_child_constraints() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 200.0, 100.0);
+        let pinned = engine.add_rect(150.0, 10.0, 40.0, 20.0);
+        let stretched = engine.add_rect(10.0, 50.0, 180.0, 20.0);
+        engine.reparent_node(pinned, Some(frame), false);
+        engine.reparent_node(stretched, Some(frame), false);
+        assert!(engine.set_constraints(pinned, "end", "start"));
+        assert!(engine.set_constraints(stretched, "stretch", "start"));
+
+        assert!(engine.resize_node(frame, 300.0, 100.0, false));
+        let pinned = engine.scene.get_node(pinned).unwrap();
+        assert_eq!((pinned.x, pinned.width), (250.0, 40.0));
+        let stretched = engine.scene.get_node(stretched).unwrap();
+        assert_eq!((stretched.x, stretched.width), (10.0, 280.0));
+        assert_eq!(stretched.y, 50.0);
+    }
+
+    #[test]
+    fn scale_constraint_zooms_child_with_frame() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 200.0, 100.0);
+        let child = engine.add_rect(20.0, 10.0, 40.0, 30.0);
+        let inner = engine.add_rect(25.0, 15.0, 10.0, 10.0);
+        engine.reparent_node(child, Some(frame), false);
+        engine.reparent_node(inner, Some(child), false);
+        assert!(engine.set_constraints(child, "scale", "scale"));
+
+        assert!(engine.resize_node(frame, 400.0, 200.0, false));
+        let c = engine.scene.get_node(child).unwrap();
+        assert_eq!((c.x, c.y, c.width, c.height), (40.0, 20.0, 80.0, 60.0));
+        let i = engine.scene.get_node(inner).unwrap();
+        assert_eq!((i.x, i.y, i.width, i.height), (50.0, 30.0, 20.0, 20.0));
+    }
+
+    #[test]
+    fn hit_test_skips_invisible_nodes_unless_enabled() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let below = engine.add_rect(0.0, 0.0, 100.0, 100.0);
+        let ghost = engine.add_rect(0.0, 0.0, 100.0, 100.0);
+        engine.set_opacity(ghost, 0.0);
+        assert_eq!(engine.hit_test(50.0, 50.0), Some(below));
+
+        engine.set_hit_invisible(true);
+        assert_eq!(engine.hit_test(50.0, 50.0), Some(ghost));
+
+        // A visible stroke keeps a transparent-fill rect clickable
+        engine.set_hit_invisible(false);
+        engine.set_opacity(ghost, 1.0);
+        engine.set_fill_color(ghost, 0, 0, 0, 0.0);
+        assert_eq!(engine.hit_test(50.0, 50.0), Some(below));
+        engine.set_stroke(ghost, 0, 0, 0, 1.0, 1.0);
+        assert_eq!(engine.hit_test(50.0, 50.0), Some(ghost));
+    }
+
+    #[test]
+    fn resize_selection_scales_nodes_within_bounds() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let a = engine.add_rect(0.0, 0.0, 50.0, 50.0);
+        let b = engine.add_rect(100.0, 50.0, 50.0, 50.0);
+        engine.scene.selection = vec![a, b];
+
+        assert!(engine.resize_selection(300.0, 200.0, 0));
+        let a = engine.scene.get_node(a).unwrap();
+        assert_eq!((a.x, a.y, a.width, a.height), (0.0, 0.0, 100.0, 100.0));
+        let b = engine.scene.get_node(b).unwrap();
+        assert_eq!((b.x, b.y, b.width, b.height), (200.0, 100.0, 100.0, 100.0));
+    }
+
+    #[test]
+    fn rotate_selection_orbits_shared_center() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let a = engine.add_rect(0.0, 0.0, 50.0, 50.0);
+        let b = engine.add_rect(150.0, 0.0, 50.0, 50.0);
+        engine.scene.selection = vec![a, b];
+
+        assert!(engine.rotate_selection(std::f64::consts::FRAC_PI_2, None, None));
+        let close = |v: f64, e: f64| (v - e).abs() < 1e-9;
+        let a = engine.scene.get_node(a).unwrap();
+        assert!(close(a.x, 75.0) && close(a.y, -75.0) && close(a.rotation, std::f64::consts::FRAC_PI_2));
+        let b = engine.scene.get_node(b).unwrap();
+        assert!(close(b.x, 75.0) && close(b.y, 75.0) && close(b.rotation, std::f64::consts::FRAC_PI_2));
+    }
+
+    #[test]
+    fn locked_nodes_reject_direct_mutation() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 200.0, 100.0);
+        let rect = engine.add_rect(10.0, 10.0, 20.0, 20.0);
+        engine.set_locked(rect, true);
+
+        assert!(!engine.move_node(rect, 5.0, 5.0, false));
+        assert!(!engine.set_node_position(rect, 50.0, 50.0, false));
+        assert!(!engine.resize_node(rect, 80.0, 80.0, false));
+        assert!(!engine.reparent_node(rect, Some(frame), false));
+        let node = engine.scene.get_node(rect).unwrap();
+        assert_eq!((node.x, node.y, node.width, node.height), (10.0, 10.0, 20.0, 20.0));
+        assert_eq!(node.parent, None);
+
+        assert!(engine.move_node(rect, 5.0, 5.0, true));
+        assert!(engine.resize_node(rect, 80.0, 80.0, true));
+        assert!(engine.reparent_node(rect, Some(frame), true));
+        let node = engine.scene.get_node(rect).unwrap();
+        assert_eq!((node.x, node.y, node.width, node.height), (15.0, 15.0, 80.0, 80.0));
+        assert_eq!(node.parent, Some(frame));
+    }
+
+    #[test]
+    fn marquee_skips_locked_nodes() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let free = engine.add_rect(10.0, 10.0, 20.0, 20.0);
+        let locked = engine.add_rect(40.0, 10.0, 20.0, 20.0);
+        let outside = engine.add_rect(300.0, 300.0, 20.0, 20.0);
+        engine.set_locked(locked, true);
+
+        assert_eq!(engine.select_in_rect(0.0, 0.0, 100.0, 100.0, true), vec![free]);
+        assert!(!engine.get_selection().contains(&outside));
+        // Touching is enough without `contain`; fully-inside is required with it
+        assert_eq!(engine.select_in_rect(320.0, 320.0, -10.0, -10.0, false), vec![outside]);
+        assert!(engine.select_in_rect(320.0, 320.0, -10.0, -10.0, true).is_empty());
+
+        engine.select(free);
+        engine.add_to_selection(locked);
+        engine.move_selection(5.0, 0.0);
+        assert_eq!(engine.scene.get_node(free).unwrap().x, 15.0);
+        assert_eq!(engine.scene.get_node(locked).unwrap().x, 40.0);
+
+        assert_eq!(engine.delete_selection(), 1);
+        assert!(engine.scene.get_node(locked).is_some());
+        assert_eq!(engine.get_selection(), vec![locked]);
+    }
+
+    #[test]
+    fn duplicated_component_syncs_its_own_instances() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 100.0, 50.0);
+        let label = engine.add_rect(10.0, 10.0, 20.0, 20.0);
+        assert!(engine.reparent_node(label, Some(frame), false));
+        let comp = engine.create_component(frame, "Button");
+        let original = engine.create_instance(comp, 0.0, 200.0);
+        let copy = engine.duplicate_component(comp, "Button 2");
+        let source = engine.components.get(copy).unwrap().variants[""].root_node_id;
+        let node = engine.scene.get_node(source).unwrap();
+        assert_eq!((node.x, node.parent, node.name.as_str()), (140.0, None, "[C] Button 2"));
+        let copy_label = node.children[0];
+        assert_ne!(copy_label, label);
+        let instance = engine.create_instance(copy, 0.0, 300.0);
+        // Editing the copy's source reaches its instances but not the original's
+        engine.scene.get_node_mut(copy_label).unwrap().width = 60.0;
+        assert_eq!(engine.sync_instances(copy), 1);
+        let width = |engine: &Engine, id: u64| {
+            let child = engine.scene.get_node(id).unwrap().children[0];
+            engine.scene.get_node(child).unwrap().width
+        };
+        assert_eq!(width(&engine, instance), 60.0);
+        assert_eq!(width(&engine, original), 20.0);
+        assert_eq!(engine.scene.get_node(label).unwrap().width, 20.0);
+    }
+
+    #[test]
+    fn computed_bounds_report_the_last_layout_pass() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 300.0, 100.0);
+        let rect = engine.add_rect(50.0, 50.0, 20.0, 20.0);
+        assert!(engine.reparent_node(rect, Some(frame), false));
+        engine.scene.get_node_mut(frame).unwrap().layout.mode = LayoutMode::Flex;
+        layout::compute_layouts(&mut engine.scene);
+
+        let bounds: serde_json::Value = serde_json::from_str(&engine.get_computed_bounds(rect)).unwrap();
+        let node = engine.scene.get_node(rect).unwrap();
+        assert_ne!((node.x, node.y), (50.0, 50.0));
+        assert_eq!((bounds["x"].as_f64(), bounds["y"].as_f64()), (Some(node.x), Some(node.y)));
+        assert_eq!(engine.get_computed_bounds(999), "null");
+    }
+
+    #[test]
+    fn shared_styles_apply_list_and_export() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let rect = engine.add_rect(0.0, 0.0, 50.0, 50.0);
+        let text = engine.add_text(0.0, 60.0, "Hi", 12.0);
+        engine.set_color_style("Brand / Primary", 255, 0, 0, 1.0);
+        assert!(engine.set_text_style("Body", r#"{"font_family":"Inter","font_size":16}"#));
+        assert!(engine.apply_color_style(rect, "Brand / Primary"));
+        assert!(engine.apply_text_style(text, "Body"));
+        assert!(!engine.apply_text_style(rect, "Body"));
+        assert!(!engine.apply_color_style(rect, "Missing"));
+
+        // Restyling repaints linked nodes
+        engine.set_color_style("Brand / Primary", 0, 0, 255, 1.0);
+        assert_eq!(engine.scene.get_node(rect).unwrap().top_fill().unwrap().color, Color { r: 0, g: 0, b: 255, a: 1.0 });
+        assert!(engine.set_text_style("Body", r#"{"font_family":"Inter","font_size":20}"#));
+        assert!(matches!(engine.scene.get_node(text).unwrap().kind, NodeKind::Text { font_size, .. } if font_size == 20.0));
+
+        let styles: serde_json::Value = serde_json::from_str(&engine.list_styles()).unwrap();
+        assert_eq!(styles["colors"]["Brand / Primary"]["b"], 255);
+        assert_eq!(styles["texts"]["Body"]["font_weight"], 400);
+        let vars = engine.export_css_vars();
+        assert!(vars.contains("--brand-primary: rgba(0,0,255,1);"));
+        assert!(vars.contains("--body-font-size: 20px;"));
+        assert!(engine.export_css(rect).contains("background: var(--brand-primary);"));
+        assert!(engine.export_css(text).contains("font-size: var(--body-font-size);"));
+
+        // A hand edit to the fill unlinks it from the style
+        assert!(engine.set_fill_opacity(rect, 0, 0.5));
+        assert_eq!(engine.scene.get_node(rect).unwrap().fill_style, None);
+        engine.set_color_style("Brand / Primary", 0, 255, 0, 1.0);
+        assert_eq!(engine.scene.get_node(rect).unwrap().top_fill().unwrap().color, Color { r: 0, g: 0, b: 255, a: 1.0 });
+        // Relinking a translucent layer keeps its opacity in the exported CSS
+        assert!(engine.apply_color_style(rect, "Brand / Primary"));
+        assert!(engine.export_css(rect).contains("background: color-mix(in srgb, var(--brand-primary) 50%, transparent);"));
+    }
+
+    #[test]
+    fn handle_resize_skips_children_of_locked_frames() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 200.0, 100.0);
+        let rect = engine.add_rect(10.0, 10.0, 20.0, 20.0);
+        assert!(engine.reparent_node(rect, Some(frame), false));
+        engine.set_locked(frame, true);
+
+        assert!(!engine.resize_from_handle(rect, 3, 80.0, 80.0, false, false, false));
+        let node = engine.scene.get_node(rect).unwrap();
+        assert_eq!((node.width, node.height), (20.0, 20.0));
+
+        assert!(engine.resize_from_handle(rect, 3, 80.0, 80.0, false, false, true));
+        let node = engine.scene.get_node(rect).unwrap();
+        assert_eq!((node.width, node.height), (70.0, 70.0));
+    }
+
+    #[test]
+    fn rotated_handle_resize_keeps_anchor_on_screen() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let id = engine.add_rect(100.0, 100.0, 100.0, 50.0);
+        engine.set_rotation(id, std::f64::consts::FRAC_PI_2);
+        let corners = |engine: &Engine| {
+            let node = engine.scene.get_node(id).unwrap();
+            let (px, py) = node.pivot();
+            let (sin, cos) = node.rotation.sin_cos();
+            let rotate = |x: f64, y: f64| (px + (x - px) * cos - (y - py) * sin, py + (x - px) * sin + (y - py) * cos);
+            (rotate(node.x, node.y), rotate(node.x + node.width, node.y + node.height))
+        };
+        let (top_left, _) = corners(&engine);
+
+        // Drag the bottom-right handle: the top-left corner must not move on screen
+        let node = engine.scene.get_node(id).unwrap();
+        let (px, py) = node.pivot();
+        let target = (px - (node.y + 80.0 - py), py + (node.x + 200.0 - px));
+        assert!(engine.resize_from_handle(id, 3, target.0, target.1, false, false, false));
+        let node = engine.scene.get_node(id).unwrap();
+        assert!((node.width - 200.0).abs() < 1e-9 && (node.height - 80.0).abs() < 1e-9);
+        let (new_top_left, bottom_right) = corners(&engine);
+        assert!((new_top_left.0 - top_left.0).abs() < 1e-9 && (new_top_left.1 - top_left.1).abs() < 1e-9);
+        assert!((bottom_right.0 - target.0).abs() < 1e-9 && (bottom_right.1 - target.1).abs() < 1e-9);
+    }
+
+    #[test]
+    fn radial_gradient_reaches_render_geometry_and_code_export() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let glow = engine.add_rect(10.0, 20.0, 200.0, 100.0);
+        engine.scene.get_node_mut(glow).unwrap().fills[0].opacity = 0.5;
+        assert!(engine.set_fill_radial_gradient(glow, r##"[{"offset":0,"color":"#ffffff"},{"offset":1,"color":"#000000"}]"##, 0.25, 0.5, 0.5));
+
+        // The renderer maps the normalized center/radius onto the node's box
+        let node = engine.scene.get_node(glow).unwrap();
+        let gradient = node.fills[0].gradient.as_ref().unwrap();
+        assert_eq!(gradient.radial_circle(node.x, node.y, node.width, node.height), (60.0, 70.0, 100.0));
+
+        let expected = "radial-gradient(circle 100px at 50px 50px, rgba(255,255,255,0.5) 0%, rgba(0,0,0,0.5) 100%)";
+        assert!(engine.export_css(glow).contains(&format!("background: {};", expected)));
+        assert!(engine.export_jsx(glow).contains(expected));
+
+        // Below another layer it is exported as one background layer among several
+        engine.scene.get_node_mut(glow).unwrap().fills.push(Fill::solid(Color::from_hex("#ff0000").unwrap()));
+        assert!(engine.export_css(glow).contains(&format!(", {};", expected)));
+    }
+
+    #[test]
+    fn shadow_set_clear_and_round_trip() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let card = engine.add_rect(0.0, 0.0, 100.0, 60.0);
+        assert!(engine.set_shadow(card, 0, 0, 0, 0.25, 2.0, 4.0, -3.0));
+        assert!(engine.set_shadow(card, 10, 20, 30, 0.5, 0.0, 8.0, 12.0));
+        let shadow = Effect::DropShadow { color: Color { r: 10, g: 20, b: 30, a: 0.5 }, offset_x: 0.0, offset_y: 8.0, blur: 12.0 };
+        // Setting again replaces the shadow instead of stacking a second one
+        assert_eq!(engine.scene.get_node(card).unwrap().effects, vec![shadow.clone()]);
+
+        let mut reloaded = Engine::new(800.0, 600.0);
+        assert!(reloaded.import_scene(&engine.export_scene()));
+        assert_eq!(reloaded.scene.get_node(card).unwrap().effects, vec![shadow]);
+
+        assert!(engine.clear_shadow(card));
+        assert!(engine.scene.get_node(card).unwrap().effects.is_empty());
+        assert!(!engine.set_shadow(999, 0, 0, 0, 1.0, 0.0, 0.0, 0.0));
+        assert!(!engine.clear_shadow(999));
+    }
+
+    #[test]
+    fn added_images_wait_for_their_natural_size() {
+        let mut engine = Engine::new(800.0, 600.0);
+        assert_eq!(engine.add_image(0.0, 0.0, "", 10.0, 10.0), 0);
+        assert_eq!(engine.add_image(0.0, 0.0, "data:image/png;base64,AA", 0.0, 10.0), 0);
+        let id = engine.add_image(10.0, 20.0, "data:image/png;base64,AA", 120.0, 80.0);
+        let node = engine.scene.get_node(id).unwrap();
+        assert!(matches!(node.kind, NodeKind::Image { natural_width, natural_height, .. } if natural_width == 0.0 && natural_height == 0.0));
+        assert_eq!((node.x, node.y, node.width, node.height), (10.0, 20.0, 120.0, 80.0));
+        assert!(node.aspect_locked && node.fills.is_empty());
+
+        let svg = engine.export_svg(false);
+        assert!(svg.contains(r#"<image href="data:image/png;base64,AA" x="10" y="20" width="120" height="80" preserveAspectRatio="none" />"#));
+    }
+
+    #[test]
+    fn handle_resize_respects_size_limits() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let id = engine.add_rect(100.0, 100.0, 100.0, 50.0);
+        engine.scene.get_node_mut(id).unwrap().max_width = Some(150.0);
+        engine.scene.get_node_mut(id).unwrap().min_height = Some(40.0);
+
+        // Dragging the top-left corner keeps the bottom-right corner anchored at the limits
+        assert!(engine.resize_from_handle(id, 0, 0.0, 190.0, false, false, false));
+        let node = engine.scene.get_node(id).unwrap();
+        assert_eq!((node.x, node.y, node.width, node.height), (50.0, 110.0, 150.0, 40.0));
+    }
+
+    #[test]
+    fn set_path_points_refits_bounds() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let id = engine.add_path(r#"[{"x":10,"y":10},{"x":50,"y":30}]"#, false);
+        assert!(engine.set_path_points(id, r#"[{"x":100,"y":20},{"x":140,"y":80},{"x":120,"y":60}]"#));
+        let n = engine.scene.get_node(id).unwrap();
+        assert_eq!((n.x, n.y, n.width, n.height), (100.0, 20.0, 40.0, 60.0));
+        assert!(!engine.set_path_points(id, "[]"));
+    }
+
+    #[test]
+    fn line_hits_near_its_segment_only() {
+        let mut engine = Engine::new(800.0, 600.0);
+        // Drawn right-to-left and downward: starts at the box's top-right corner
+        let id = engine.add_line(200.0, 100.0, 100.0, 200.0);
+        let (start, end) = engine.scene.get_node(id).unwrap().line_endpoints().unwrap();
+        assert_eq!((start.x, start.y, end.x, end.y), (200.0, 100.0, 100.0, 200.0));
+
+        assert_eq!(engine.scene.hit_test(Point { x: 151.0, y: 150.0 }), Some(id));
+        // Inside the bounding box but far from the diagonal
+        assert_eq!(engine.scene.hit_test(Point { x: 110.0, y: 110.0 }), None);
+
+        assert!(engine.set_line_arrows(id, false, true));
+        let heads = engine.scene.get_node(id).unwrap().line_arrowheads();
+        assert_eq!(heads.len(), 1);
+        assert_eq!((heads[0][0].x, heads[0][0].y), (100.0, 200.0));
+    }
+
+    #[test]
+    fn star_and_polygon_vertices_fit_the_box() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let hex = engine.add_polygon(0.0, 0.0, 100.0, 100.0, 6);
+        let pts = engine.scene.get_node(hex).unwrap().shape_vertices().unwrap();
+        assert_eq!(pts.len(), 6);
+        assert!((pts[0].x - 50.0).abs() < 1e-9 && pts[0].y.abs() < 1e-9);
+
+        let star = engine.add_star(0.0, 0.0, 100.0, 200.0, 5, 0.5);
+        let pts = engine.scene.get_node(star).unwrap().shape_vertices().unwrap();
+        assert_eq!(pts.len(), 10);
+        // Tips reach the bounding ellipse, inner vertices sit halfway in
+        for (i, p) in pts.iter().enumerate() {
+            let r = ((p.x - 50.0) / 50.0).hypot((p.y - 100.0) / 100.0);
+            assert!((r - if i % 2 == 0 { 1.0 } else { 0.5 }).abs() < 1e-9);
+        }
+
+        // Hits follow the outline, not the bounding box
+        let star = engine.scene.get_node(star).unwrap();
+        assert!(star.contains_point(Point { x: 50.0, y: 100.0 }));
+        assert!(star.contains_point(Point { x: 50.0, y: 5.0 }));
+        assert!(!star.contains_point(Point { x: 5.0, y: 5.0 }));
+        assert!(!star.contains_point(Point { x: 50.0, y: 195.0 }));
+        let hex = engine.scene.get_node(hex).unwrap();
+        assert!(hex.contains_point(Point { x: 50.0, y: 50.0 }));
+        assert!(!hex.contains_point(Point { x: 99.0, y: 2.0 }));
+    }
+
+    #[test]
+    fn blend_mode_ignores_unknown_names() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let id = engine.add_rect(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(engine.scene.get_node(id).unwrap().composite_operation(), None);
+        assert!(engine.set_blend_mode(id, "multiply"));
+        assert!(!engine.set_blend_mode(id, "sparkle"));
+        let node = engine.scene.get_node(id).unwrap();
+        assert_eq!(node.composite_operation(), Some("multiply"));
+
+        // Older documents without the field load as "normal"
+        let mut json = serde_json::to_value(node).unwrap();
+        json.as_object_mut().unwrap().remove("blend_mode");
+        let loaded: Node = serde_json::from_value(json).unwrap();
+        assert_eq!(loaded.blend_mode, "normal");
+    }
+
+    #[test]
+    fn detached_instance_becomes_plain_frame() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 200.0, 100.0);
+        let label = engine.add_rect(10.0, 10.0, 20.0, 20.0);
+        engine.reparent_node(label, Some(frame), false);
+        let comp = engine.create_component(frame, "Button");
+        let instance = engine.create_instance(comp, 300.0, 0.0);
+        let children = engine.scene.get_children_of(instance);
+
+        assert_eq!(engine.detach_instance(instance), instance);
+        let node = engine.scene.get_node(instance).unwrap();
+        assert!(matches!(node.kind, NodeKind::Frame));
+        assert_eq!(node.name, "Button");
+        assert_eq!(engine.scene.get_children_of(instance), children);
+        assert_eq!(engine.instances_of(comp), Vec::<u64>::new());
+        assert_eq!(engine.detach_instance(instance), 0);
+    }
+
+    #[test]
+    fn sync_instances_picks_up_edits_and_keeps_overrides() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 200.0, 100.0);
+        let label = engine.add_text(10.0, 10.0, "Label", 14.0);
+        engine.reparent_node(label, Some(frame), false);
+        let comp = engine.create_component(frame, "Button");
+        let instance = engine.create_instance(comp, 300.0, 50.0);
+        let text_of = |e: &Engine, id| match &e.scene.get_node(id).unwrap().kind {
+            NodeKind::Text { content, .. } => content.clone(),
+            _ => unreachable!(),
+        };
+        let child = engine.scene.get_children_of(instance)[0];
+        assert!(engine.set_instance_override(instance, child, r#"{"text":"Buy now"}"#));
+
+        // Edit the source frame: add an icon and rename the label
+        let icon = engine.add_rect(150.0, 10.0, 20.0, 20.0);
+        engine.reparent_node(icon, Some(frame), false);
+        engine.set_text_content(label, "Label v2");
+
+        assert_eq!(engine.sync_instances(comp), 1);
+        let children = engine.scene.get_children_of(instance);
+        assert_eq!(children.len(), 2);
+        assert_eq!(text_of(&engine, children[0]), "Buy now");
+        let icon_copy = engine.scene.get_node(children[1]).unwrap();
+        assert_eq!((icon_copy.x, icon_copy.y), (450.0, 60.0));
+        let root = engine.scene.get_node(instance).unwrap();
+        assert_eq!((root.x, root.y), (300.0, 50.0));
+
+        // The override follows the re-cloned node, so a second sync keeps it too
+        engine.sync_instances(comp);
+        let children = engine.scene.get_children_of(instance);
+        assert_eq!(text_of(&engine, children[0]), "Buy now");
+    }
+
+    #[test]
+    fn fill_override_survives_sync() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 200.0, 100.0);
+        let dot = engine.add_rect(10.0, 10.0, 20.0, 20.0);
+        engine.reparent_node(dot, Some(frame), false);
+        let comp = engine.create_component(frame, "Badge");
+        let instance = engine.create_instance(comp, 300.0, 0.0);
+        let child = engine.scene.get_children_of(instance)[0];
+        let fill_of = |e: &Engine| {
+            let child = e.scene.get_children_of(instance)[0];
+            e.scene.get_node(child).unwrap().top_fill().unwrap().color
+        };
+
+        assert!(!engine.set_instance_override(instance, child, r#"{"fill_hex":"nope"}"#));
+        assert!(engine.set_instance_override(instance, child, r##"{"fill_hex":"#ff0000"}"##));
+        assert!(fill_of(&engine).approx_eq(Color::from_hex("#ff0000").unwrap()));
+
+        assert_eq!(engine.sync_instances(comp), 1);
+        assert!(fill_of(&engine).approx_eq(Color::from_hex("#ff0000").unwrap()));
+    }
+
+    #[test]
+    fn nested_instances_link_to_their_component() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let button_frame = engine.add_frame(0.0, 0.0, 100.0, 40.0);
+        let bg = engine.add_rect(0.0, 0.0, 100.0, 40.0);
+        engine.reparent_node(bg, Some(button_frame), false);
+        let button = engine.create_component(button_frame, "Button");
+
+        let card_frame = engine.add_frame(0.0, 100.0, 200.0, 200.0);
+        let inner = engine.create_instance(button, 20.0, 120.0);
+        engine.reparent_node(inner, Some(card_frame), false);
+        let card = engine.create_component(card_frame, "Card");
+
+        let card_instance = engine.create_instance(card, 400.0, 100.0);
+        let nested = engine.scene.get_children_of(card_instance)[0];
+        assert_ne!(nested, inner);
+        let NodeKind::Instance(data) = &engine.scene.get_node(nested).unwrap().kind else { panic!("expected instance") };
+        assert_eq!(data.component_id, button);
+        let nested_bg = engine.scene.get_children_of(nested)[0];
+        assert_eq!(data.template_map.get(&bg), Some(&nested_bg));
+        assert_eq!(engine.scene.get_node(nested_bg).unwrap().x, 420.0);
+
+        // Editing the button reaches the copy nested inside the card instance
+        let icon = engine.add_rect(70.0, 10.0, 20.0, 20.0);
+        engine.reparent_node(icon, Some(button_frame), false);
+        engine.sync_instances(button);
+        let nested = engine.scene.get_children_of(card_instance)[0];
+        assert_eq!(engine.scene.get_children_of(nested).len(), 2);
+    }
+
+    #[test]
+    fn nested_overrides_survive_outer_sync() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let button_frame = engine.add_frame(0.0, 0.0, 100.0, 40.0);
+        let label = engine.add_text(10.0, 10.0, "Label", 14.0);
+        let bg = engine.add_rect(0.0, 0.0, 100.0, 40.0);
+        engine.reparent_node(label, Some(button_frame), false);
+        engine.reparent_node(bg, Some(button_frame), false);
+        let button = engine.create_component(button_frame, "Button");
+        let card_frame = engine.add_frame(0.0, 100.0, 200.0, 200.0);
+        let inner = engine.create_instance(button, 20.0, 120.0);
+        engine.reparent_node(inner, Some(card_frame), false);
+        let card = engine.create_component(card_frame, "Card");
+        let card_instance = engine.create_instance(card, 400.0, 100.0);
+
+        // One override stored on the nested instance, one on the outer instance
+        let nested = engine.scene.get_children_of(card_instance)[0];
+        let (nested_label, nested_bg) = match engine.scene.get_children_of(nested)[..] {
+            [l, b] => (l, b),
+            _ => panic!("expected two children"),
+        };
+        assert!(engine.set_instance_override(nested, nested_label, r#"{"text":"Buy"}"#));
+        assert!(engine.set_instance_override(card_instance, nested_bg, r##"{"fill_hex":"#00ff00"}"##));
+
+        let title = engine.add_text(10.0, 110.0, "Title", 14.0);
+        engine.reparent_node(title, Some(card_frame), false);
+        assert_eq!(engine.sync_instances(card), 1);
+
+        let nested = engine.scene.get_children_of(card_instance)[0];
+        let (nested_label, nested_bg) = match engine.scene.get_children_of(nested)[..] {
+            [l, b] => (l, b),
+            _ => panic!("expected two children"),
+        };
+        assert!(matches!(&engine.scene.get_node(nested_label).unwrap().kind, NodeKind::Text { content, .. } if content == "Buy"));
+        let green = Color { r: 0, g: 255, b: 0, a: 1.0 };
+        assert!(engine.scene.get_node(nested_bg).unwrap().top_fill().unwrap().color.approx_eq(green));
+
+        // Both keep applying on later syncs, keyed by the new scene ids
+        assert_eq!(engine.sync_instances(card), 1);
+        let nested = engine.scene.get_children_of(card_instance)[0];
+        let nested_label = engine.scene.get_children_of(nested)[0];
+        assert!(matches!(&engine.scene.get_node(nested_label).unwrap().kind, NodeKind::Text { content, .. } if content == "Buy"));
+    }
+
+    #[test]
+    fn paste_into_another_document_remaps_ids() {
+        let mut source = Engine::new(800.0, 600.0);
+        let frame = source.add_frame(0.0, 0.0, 200.0, 100.0);
+        let a = source.add_rect(10.0, 10.0, 20.0, 20.0);
+        let b = source.add_rect(100.0, 10.0, 20.0, 20.0);
+        source.reparent_node(a, Some(frame), false);
+        source.reparent_node(b, Some(frame), false);
+        let outside = source.add_rect(300.0, 0.0, 20.0, 20.0);
+        let link = source.add_connector(a, outside, "straight");
+        source.select(frame);
+        source.add_to_selection(a);
+        source.add_to_selection(link);
+        let buffer = source.copy_selection();
+
+        let mut target = Engine::new(800.0, 600.0);
+        target.add_rect(0.0, 0.0, 5.0, 5.0);
+        let pasted = target.paste(&buffer, 10.0, 20.0);
+        // The selected child is carried by its frame; the connector loses an endpoint and is dropped
+        assert_eq!(pasted.len(), 1);
+        assert_eq!(target.get_selection(), pasted);
+        let root = target.scene.get_node(pasted[0]).unwrap();
+        assert_eq!((root.x, root.y), (10.0, 20.0));
+        let children = root.children.clone();
+        assert_eq!(children.len(), 2);
+        for &c in &children {
+            assert_eq!(target.scene.get_node(c).unwrap().parent, Some(pasted[0]));
+        }
+        assert_eq!(target.scene.get_node(children[1]).unwrap().x, 110.0);
+        assert_eq!(target.scene.node_count(), 4);
+        assert!(target.paste("not json", 0.0, 0.0).is_empty());
+        let undo_len = target.undo_stack.len();
+        assert!(target.paste(r#"{"subtrees":[]}"#, 0.0, 0.0).is_empty());
+        assert_eq!(target.undo_stack.len(), undo_len);
+    }
+
+    #[test]
+    fn paste_detaches_foreign_instances_and_brings_styles() {
+        let mut source = Engine::new(800.0, 600.0);
+        let button_frame = source.add_frame(0.0, 0.0, 100.0, 40.0);
+        let button = source.create_component(button_frame, "Button");
+        let instance = source.create_instance(button, 200.0, 0.0);
+        let swatch = source.add_rect(0.0, 100.0, 20.0, 20.0);
+        source.set_color_style("Brand", 255, 0, 0, 1.0);
+        assert!(source.apply_color_style(swatch, "Brand"));
+        source.select(instance);
+        source.add_to_selection(swatch);
+        let buffer = source.copy_selection();
+
+        // Pasting back into the same document keeps the instance linked and renames the copies
+        let same = source.paste(&buffer, 0.0, 0.0);
+        assert!(matches!(source.scene.get_node(same[0]).unwrap().kind, NodeKind::Instance(_)));
+        assert_ne!(source.scene.get_node(same[1]).unwrap().name, source.scene.get_node(swatch).unwrap().name);
+
+        let mut target = Engine::new(800.0, 600.0);
+        let pasted = target.paste(&buffer, 0.0, 0.0);
+        let frame = target.scene.get_node(pasted[0]).unwrap();
+        assert!(matches!(frame.kind, NodeKind::Frame));
+        assert!(!frame.name.starts_with("[I] "));
+        assert_eq!(target.scene.get_node(pasted[1]).unwrap().fill_style.as_deref(), Some("Brand"));
+        assert!(target.list_styles().contains("Brand"));
+    }
+
+    #[test]
+    fn deep_duplicate_copies_children() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 200.0, 100.0);
+        let a = engine.add_rect(10.0, 10.0, 20.0, 20.0);
+        let b = engine.add_rect(100.0, 50.0, 20.0, 20.0);
+        engine.reparent_node(a, Some(frame), false);
+        engine.reparent_node(b, Some(frame), false);
+
+        let copy = engine.duplicate_node_deep(frame);
+        let node = engine.scene.get_node(copy).unwrap();
+        assert_eq!((node.x, node.y), (20.0, 20.0));
+        assert_ne!(node.name, engine.scene.get_node(frame).unwrap().name);
+        let children = engine.scene.get_children_of(copy);
+        assert_eq!(children.len(), 2);
+        assert!(!children.contains(&a) && !children.contains(&b));
+        let pos: Vec<(f64, f64)> = children.iter()
+            .map(|&c| engine.scene.get_node(c).unwrap())
+            .map(|n| (n.x, n.y))
+            .collect();
+        assert_eq!(pos, vec![(30.0, 30.0), (120.0, 70.0)]);
+        // The original is untouched
+        assert_eq!(engine.scene.get_children_of(frame), vec![a, b]);
+
+        engine.undo();
+        assert!(engine.scene.get_node(copy).is_none());
+    }
+
+    #[test]
+    fn svg_export_notes_gradient_fills() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(0.0, 0.0, 200.0, 100.0);
+        let glow = engine.add_rect(10.0, 10.0, 50.0, 50.0);
+        engine.reparent_node(glow, Some(frame), false);
+        let svg = engine.export_svg(false);
+        assert!(svg.contains("viewBox=\"0 0 200 100\""));
+        assert!(!svg.contains("<!--"));
+
+        assert!(engine.set_fill_radial_gradient(glow, r##"[{"offset":0,"color":"#ffffff"},{"offset":1,"color":"#000000"}]"##, 0.5, 0.5, 0.5));
+        engine.set_node_name(glow, "Glow--1");
+        let svg = engine.export_svg(false);
+        assert!(svg.contains("<!-- Glow- -1: gradient fill not supported"));
+    }
+
+    #[test]
+    fn png_export_of_nothing_is_empty() {
+        let mut engine = Engine::new(800.0, 600.0);
+        assert_eq!(engine.export_png(2.0, false, false), "");
+        engine.add_rect(0.0, 0.0, 10.0, 10.0);
+        assert_eq!(engine.export_png(2.0, true, false), "");
+        assert_eq!(engine.export_png(0.0, false, true), "");
+    }
+
+    #[test]
+    fn frame_svg_export_is_clipped_to_the_frame() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let frame = engine.add_frame(100.0, 50.0, 200.0, 100.0);
+        let inside = engine.add_rect(110.0, 60.0, 20.0, 20.0);
+        let overflow = engine.add_rect(250.0, 60.0, 200.0, 20.0);
+        engine.reparent_node(inside, Some(frame), false);
+        engine.reparent_node(overflow, Some(frame), false);
+        let other = engine.add_ellipse(0.0, 0.0, 10.0, 10.0);
+
+        let svg = engine.export_frame_svg(frame);
+        assert!(svg.contains("viewBox=\"100 50 200 100\""));
+        assert!(svg.contains(&format!("clip-path=\"url(#clip-{})\"", frame)));
+        assert!(!svg.contains("<ellipse"));
+        assert_eq!(engine.export_frame_svg(other), "");
+        assert_eq!(engine.export_frame_image(other, 2.0), "");
+    }
+
+    #[test]
+    fn import_migrates_old_scenes_and_reports_errors() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let id = engine.add_rect(0.0, 0.0, 10.0, 10.0);
+        let mut old: serde_json::Value = serde_json::from_str(&engine.export_scene()).unwrap();
+        assert_eq!(old["version"], crate::scene::SCENE_VERSION);
+        // A pre-versioning document with a single `fill`
+        old.as_object_mut().unwrap().remove("version");
+        let node = &mut old["nodes"][0];
+        node["fill"] = node["fills"][0].clone();
+        node.as_object_mut().unwrap().remove("fills");
+
+        let result = engine.import_scene_result(&old.to_string());
+        assert_eq!(result, r#"{"ok":true,"version":0}"#);
+        assert_eq!(engine.scene.get_node(id).unwrap().fills.len(), 1);
+
+        let result: serde_json::Value = serde_json::from_str(&engine.import_scene_result("{\"nodes\": 3}")).unwrap();
+        assert_eq!(result["ok"], false);
+        assert_eq!(result["line"], 1);
+        old["version"] = serde_json::json!(99);
+        let result: serde_json::Value = serde_json::from_str(&engine.import_scene_result(&old.to_string())).unwrap();
+        assert!(result["error"].as_str().unwrap().contains("version 99"));
+        assert!(engine.scene.get_node(id).is_some());
+    }
+
+    #[test]
+    fn zoom_to_selection_fits_rotated_bounds() {
+        let mut engine = Engine::new(800.0, 600.0);
+        assert!(!engine.zoom_to_selection(20.0));
+        let a = engine.add_rect(0.0, 0.0, 100.0, 100.0);
+        engine.set_rotation(a, std::f64::consts::FRAC_PI_4);
+        engine.select(a);
+        let bounds: serde_json::Value = serde_json::from_str(&engine.get_selection_bounds().unwrap()).unwrap();
+        let side = 100.0 * std::f64::consts::SQRT_2;
+        assert!((bounds["width"].as_f64().unwrap() - side).abs() < 1e-9);
+
+        assert!(engine.zoom_to_selection(20.0));
+        // Height is the limiting side: (600 - 40) / side
+        assert!((engine.get_zoom() - 560.0 / side).abs() < 1e-9);
+        let (cx, cy) = engine.renderer.screen_to_scene(400.0, 300.0);
+        assert!((cx - 50.0).abs() < 1e-9 && (cy - 50.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn zoom_to_fit_covers_visible_roots() {
+        let mut engine = Engine::new(800.0, 600.0);
+        engine.zoom_to_fit(10.0);
+        assert_eq!((engine.get_zoom(), engine.get_pan_x(), engine.get_pan_y()), (1.0, 400.0, 300.0));
+
+        engine.add_rect(0.0, 0.0, 100.0, 100.0);
+        engine.add_rect(300.0, 100.0, 100.0, 100.0);
+        let hidden = engine.add_rect(5000.0, 0.0, 10.0, 10.0);
+        engine.set_visible(hidden, false);
+        engine.zoom_to_fit(0.0);
+        // 400 × 200 of content in an 800 × 600 canvas
+        assert_eq!(engine.get_zoom(), 2.0);
+        assert_eq!(engine.renderer.screen_to_scene(400.0, 300.0), (200.0, 100.0));
+
+        // Tiny content is capped at the maximum zoom
+        let mut engine = Engine::new(800.0, 600.0);
+        engine.add_rect(0.0, 0.0, 1.0, 1.0);
+        engine.zoom_to_fit(0.0);
+        assert_eq!(engine.get_zoom(), 10.0);
+    }
+
+    #[test]
+    fn moves_snap_to_grid_unless_raw() {
+        let mut engine = Engine::new(800.0, 600.0);
+        let id = engine.add_rect(3.0, 4.0, 10.0, 10.0);
+        let pos = |e: &Engine| {
+            let n = e.scene.get_node(id).unwrap();
+            (n.x, n.y)
+        };
+        engine.set_snap_grid(8.0);
+        engine.move_node(id, 10.0, 1.0, false);
+        assert_eq!(pos(&engine), (16.0, 8.0));
+        engine.move_node_raw(id, 1.0, 1.0, false);
+        assert_eq!(pos(&engine), (17.0, 9.0));
+        engine.set_node_position(id, 21.0, -5.0, false);
+        assert_eq!(pos(&engine), (24.0, -8.0));
+
+        // Handle drags snap the dragged edge and leave the opposite one where it was
+        assert!(engine.resize_from_handle(id, 0, 5.0, -29.0, false, false, false));
+        let n = engine.scene.get_node(id).unwrap();
+        assert_eq!((n.x, n.y, n.x + n.width, n.y + n.height), (8.0, -32.0, 34.0, 2.0));
+
+        assert!(!engine.set_snap_grid(f64::INFINITY));
+        assert!(!engine.set_snap_grid(f64::NAN));
+        assert_eq!(engine.snap_grid, 8.0);
+        assert!(engine.set_snap_grid(0.0));
+        engine.move_node(id, 1.5, 0.0, false);
+        assert_eq!(pos(&engine), (9.5, -32.0));
+    }
+}
@@ -1,30 +1,49 @@
-use crate::types::{Color, Rect as BBox};
-use crate::component::InstanceData;
+use crate::types::{Color, Point, Rect as BBox};
+use crate::component::{InstanceData, VariantKey};
 use serde::{Deserialize, Serialize};
 
 pub type NodeId = u64;
 
 /// Text alignment
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
 pub enum TextAlign {
+    #[default]
     Left,
     Center,
     Right,
 }
 
-impl Default for TextAlign {
-    fn default() -> Self { TextAlign::Left }
-}
-
 /// Font style
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
 pub enum FontStyle {
+    #[default]
     Normal,
     Italic,
 }
 
-impl Default for FontStyle {
-    fn default() -> Self { FontStyle::Normal }
+/// List formatting applied per paragraph (hard line break) of a text node
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+pub enum ListStyle {
+    #[default]
+    None,
+    Bullet,
+    Numbered,
+}
+
+/// How a connector is routed between its endpoints
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+pub enum ConnectorKind {
+    #[default]
+    Straight,
+    /// Horizontal/vertical segments with a single elbow pair
+    Orthogonal,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+pub enum TextDirection {
+    #[default]
+    Ltr,
+    Rtl,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -43,6 +62,17 @@ pub enum NodeKind {
         font_weight: u16,
         #[serde(default)]
         font_style: FontStyle,
+        /// Extra space between paragraphs, in px
+        #[serde(default)]
+        paragraph_gap: f64,
+        #[serde(default)]
+        list_style: ListStyle,
+        /// Draw the outline beneath the fill instead of on top of it
+        #[serde(default)]
+        stroke_under: bool,
+        /// Right-to-left text starts at the right edge; `text_align` Left/Right mean start/end
+        #[serde(default)]
+        direction: TextDirection,
     },
     Frame,
     Group,
@@ -50,58 +80,212 @@ pub enum NodeKind {
     Slot { slot_name: String },
     /// An instance of a component
     Instance(Box<InstanceData>),
+    /// Polyline/polygon; points are relative to the node's top-left
+    Path { points: Vec<Point>, closed: bool },
+    /// Raster image (data URL or object URL) stretched to the node's box. The natural size is
+    /// the decoded bitmap's, 0 until the renderer has loaded it.
+    Image { src: String, natural_width: f64, natural_height: f64 },
+    /// Regular n-gon inscribed in the node's bounding ellipse, first vertex at the top
+    Polygon { sides: u32 },
+    /// Star with `points` tips on the bounding ellipse; inner vertices sit at
+    /// `inner_radius_ratio` of the outer radius
+    Star { points: u32, inner_radius_ratio: f64 },
+    /// Straight segment from (x, y) to (x + x2, y + y2). The box always spans the segment, so a
+    /// line pointing left or up starts at the box's right or bottom edge; after a resize the box
+    /// is authoritative and `x2`/`y2` only give the direction.
+    Line {
+        x2: f64,
+        y2: f64,
+        #[serde(default)]
+        arrow_start: bool,
+        #[serde(default)]
+        arrow_end: bool,
+    },
+    /// Line between the edges of two nodes, with an arrowhead at `to`. `points` is the
+    /// cached route relative to the node's top-left, recomputed by the layout pass.
+    Connector {
+        from: NodeId,
+        to: NodeId,
+        #[serde(default)]
+        kind: ConnectorKind,
+        #[serde(default)]
+        points: Vec<Point>,
+    },
+}
+
+/// Triangle (tip, left, right) pointing from `back` toward `tip`, `size` long
+fn arrowhead(back: Point, tip: Point, size: f64) -> Option<[Point; 3]> {
+    let (dx, dy) = (tip.x - back.x, tip.y - back.y);
+    let len = dx.hypot(dy);
+    if len <= 1e-9 {
+        return None;
+    }
+    let (ux, uy) = (dx / len, dy / len);
+    let (bx, by) = (tip.x - ux * size, tip.y - uy * size);
+    let (nx, ny) = (-uy * size / 2.0, ux * size / 2.0);
+    Some([tip, Point { x: bx + nx, y: by + ny }, Point { x: bx - nx, y: by - ny }])
+}
+
+fn distance_to_segment(p: Point, a: Point, b: Point) -> f64 {
+    let (dx, dy) = (b.x - a.x, b.y - a.y);
+    let len_sq = dx * dx + dy * dy;
+    let t = if len_sq > 0.0 { (((p.x - a.x) * dx + (p.y - a.y) * dy) / len_sq).clamp(0.0, 1.0) } else { 0.0 };
+    (p.x - a.x - t * dx).hypot(p.y - a.y - t * dy)
+}
+
+/// Even-odd point-in-polygon test
+fn polygon_contains(vertices: &[Point], p: Point) -> bool {
+    let mut inside = false;
+    let mut j = vertices.len().wrapping_sub(1);
+    for (i, a) in vertices.iter().enumerate() {
+        let b = vertices[j];
+        if (a.y > p.y) != (b.y > p.y) && p.x < (b.x - a.x) * (p.y - a.y) / (b.y - a.y) + a.x {
+            inside = !inside;
+        }
+        j = i;
+    }
+    inside
 }
 
 fn default_line_height() -> f64 { 1.2 }
+fn default_transform_origin() -> (f64, f64) { (0.5, 0.5) }
+fn default_blend_mode() -> String { "normal".to_string() }
+
+/// Blend modes a node can use; all but "normal" map straight to canvas composite operations
+pub const BLEND_MODES: &[&str] = &[
+    "normal", "multiply", "screen", "overlay", "darken", "lighten", "color-dodge", "color-burn",
+    "hard-light", "soft-light", "difference", "exclusion", "hue", "saturation", "color", "luminosity",
+];
 fn default_font_weight() -> u16 { 400 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Fill {
     pub color: Color,
+    /// Component color property this fill is bound to (template nodes only)
+    #[serde(default)]
+    pub bound_prop: Option<String>,
+    /// Image source (data URL or object URL) painted over the shape instead of `color`
+    #[serde(default)]
+    pub image: Option<String>,
+    /// Opacity of this fill layer, independent of the node's opacity
+    #[serde(default = "default_fill_opacity")]
+    pub opacity: f64,
+    /// Gradient painted instead of `color` (an image still takes precedence)
+    #[serde(default)]
+    pub gradient: Option<Gradient>,
+}
+
+fn default_fill_opacity() -> f64 { 1.0 }
+
+fn clamp_size(v: f64, min: Option<f64>, max: Option<f64>) -> f64 {
+    let v = max.map_or(v, |m| v.min(m));
+    min.map_or(v, |m| v.max(m))
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GradientStop {
+    /// Position along the gradient, 0..1
+    pub offset: f64,
+    pub color: Color,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum Gradient {
+    /// `center` is in 0..1 node space; `radius` is a fraction of the node's larger side
+    Radial { stops: Vec<GradientStop>, center: Point, radius: f64 },
+}
+
+impl Gradient {
+    /// Center and radius of a radial gradient painted over a `width` x `height` box at (x, y)
+    pub fn radial_circle(&self, x: f64, y: f64, width: f64, height: f64) -> (f64, f64, f64) {
+        match self {
+            Gradient::Radial { center, radius, .. } => {
+                (x + center.x * width, y + center.y * height, (radius * width.max(height)).max(0.0))
+            }
+        }
+    }
+}
+
+impl Fill {
+    pub fn solid(color: Color) -> Self {
+        Self { color, bound_prop: None, image: None, opacity: 1.0, gradient: None }
+    }
+
+    pub fn image(src: String) -> Self {
+        Self { color: Color::transparent(), bound_prop: None, image: Some(src), opacity: 1.0, gradient: None }
+    }
+
+    /// The color with the layer opacity folded into its alpha
+    pub fn layer_color(&self) -> Color {
+        Color { a: self.color.a * self.opacity, ..self.color }
+    }
+}
+
+/// Visual effect painted with a node
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum Effect {
+    DropShadow { color: Color, offset_x: f64, offset_y: f64, blur: f64 },
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Png,
+    Svg,
+}
+
+/// Export preset attached to a node (like a row in an export panel)
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ExportSetting {
+    #[serde(default)]
+    pub format: ExportFormat,
+    #[serde(default = "default_export_scale")]
+    pub scale: f64,
+    /// Appended to the node name for the output file, e.g. "@2x"
+    #[serde(default)]
+    pub suffix: String,
+}
+
+fn default_export_scale() -> f64 { 1.0 }
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Stroke {
     pub color: Color,
     pub width: f64,
+    /// Dash pattern (segment lengths in px); empty for a solid line
+    #[serde(default)]
+    pub dash: Vec<f64>,
 }
 
 /// Layout mode for container nodes
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
 pub enum LayoutMode {
+    #[default]
     None,
     Flex,
     Grid,
 }
 
-impl Default for LayoutMode {
-    fn default() -> Self { LayoutMode::None }
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
 pub enum FlexDirection {
+    #[default]
     Row,
     Column,
 }
 
-impl Default for FlexDirection {
-    fn default() -> Self { FlexDirection::Row }
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
 pub enum Align {
+    #[default]
     Start,
     Center,
     End,
     Stretch,
 }
 
-impl Default for Align {
-    fn default() -> Self { Align::Start }
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
 pub enum Justify {
+    #[default]
     Start,
     Center,
     End,
@@ -110,31 +294,43 @@ pub enum Justify {
     SpaceEvenly,
 }
 
-impl Default for Justify {
-    fn default() -> Self { Justify::Start }
-}
-
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
 pub enum FlexWrap {
+    #[default]
     NoWrap,
     Wrap,
 }
 
-impl Default for FlexWrap {
-    fn default() -> Self { FlexWrap::NoWrap }
+/// How a child follows one axis of its parent when the parent is resized
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+pub enum Constraint {
+    /// Keep the distance to the left/top edge
+    #[default]
+    Start,
+    /// Keep the distance to the right/bottom edge
+    End,
+    /// Keep both edge distances, resizing the child
+    Stretch,
+    /// Keep the offset from the parent's center
+    Center,
+    /// Scale position and size with the parent
+    Scale,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Default)]
+pub struct Constraints {
+    pub horizontal: Constraint,
+    pub vertical: Constraint,
 }
 
 /// Text sizing mode
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
 pub enum TextSizing {
+    #[default]
     Fit,
     Fixed,
 }
 
-impl Default for TextSizing {
-    fn default() -> Self { TextSizing::Fit }
-}
-
 /// Layout properties for container nodes (Frame, Instance, Group)
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct Layout {
@@ -148,9 +344,18 @@ pub struct Layout {
     pub padding_bottom: f64,
     pub padding_left: f64,
     pub wrap: FlexWrap,
+    /// Spacing of wrapped lines along the cross axis
+    #[serde(default)]
+    pub align_content: Justify,
     // Grid-specific
     pub grid_columns: u32,
+    /// Fixed row count dividing the content height; 0 sizes rows to their tallest child
     pub grid_rows: u32,
+    /// Grid spacing between rows / columns; `gap` is used where unset
+    #[serde(default)]
+    pub row_gap: Option<f64>,
+    #[serde(default)]
+    pub column_gap: Option<f64>,
 }
 
 /// Attached note (markdown)
@@ -159,6 +364,11 @@ pub struct Note {
     pub content: String,
     pub tags: Vec<String>,
     pub updated_at: u64,
+    /// Review thread replies (only top-level notes carry replies)
+    #[serde(default)]
+    pub replies: Vec<Note>,
+    #[serde(default)]
+    pub resolved: bool,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -171,12 +381,23 @@ pub struct Node {
     pub width: f64,
     pub height: f64,
     pub rotation: f64,
+    /// Rotation pivot in normalized node space (0..1, default center)
+    #[serde(default = "default_transform_origin")]
+    pub transform_origin: (f64, f64),
     pub opacity: f64,
     pub visible: bool,
     pub locked: bool,
-    pub fill: Option<Fill>,
+    /// Fill layers, painted bottom to top
+    #[serde(default)]
+    pub fills: Vec<Fill>,
+    /// Single fill from scenes saved before `fills` existed; moved into `fills` on import
+    #[serde(default, rename = "fill", skip_serializing)]
+    pub legacy_fill: Option<Fill>,
     pub stroke: Option<Stroke>,
     pub corner_radius: f64,
+    /// 0 = circular corners, up to 1 = fully smoothed (squircle) corners
+    #[serde(default)]
+    pub corner_smoothing: f64,
     pub children: Vec<NodeId>,
     pub parent: Option<NodeId>,
     /// Text sizing mode (Fit = auto-size to content, Fixed = manual)
@@ -188,6 +409,51 @@ pub struct Node {
     /// Attached notes (markdown documents)
     #[serde(default)]
     pub notes: Vec<Note>,
+    /// Component template children only: visible only when the instance's variant values match
+    #[serde(default)]
+    pub visible_when: Option<VariantKey>,
+    /// Clip children to this node's bounds
+    #[serde(default)]
+    pub clip_content: bool,
+    /// Content scroll offset of a clipped frame (children are drawn shifted by the negative offset)
+    #[serde(default)]
+    pub scroll_x: f64,
+    #[serde(default)]
+    pub scroll_y: f64,
+    #[serde(default)]
+    pub effects: Vec<Effect>,
+    #[serde(default)]
+    pub export_settings: Vec<ExportSetting>,
+    /// Resize behavior inside a free-form (LayoutMode::None) frame
+    #[serde(default)]
+    pub constraints: Constraints,
+    /// Shared color style the top fill was set from
+    #[serde(default)]
+    pub fill_style: Option<String>,
+    /// Shared text style applied to a text node
+    #[serde(default)]
+    pub text_style: Option<String>,
+    /// Share of a flex parent's free main-axis space; 0 keeps the node's own size
+    #[serde(default)]
+    pub flex_grow: f64,
+    /// Keep manual x/y inside an auto-layout parent instead of joining the flow
+    #[serde(default)]
+    pub layout_absolute: bool,
+    /// Size limits honored by resizing and auto-layout (None = unbounded)
+    #[serde(default)]
+    pub min_width: Option<f64>,
+    #[serde(default)]
+    pub max_width: Option<f64>,
+    #[serde(default)]
+    pub min_height: Option<f64>,
+    #[serde(default)]
+    pub max_height: Option<f64>,
+    /// Resize proportionally, keeping the current width:height ratio
+    #[serde(default)]
+    pub aspect_locked: bool,
+    /// How the node's paint mixes with what's beneath it (one of `BLEND_MODES`)
+    #[serde(default = "default_blend_mode")]
+    pub blend_mode: String,
 }
 
 impl Node {
@@ -199,21 +465,263 @@ impl Node {
             x: 0.0, y: 0.0,
             width: 100.0, height: 100.0,
             rotation: 0.0,
+            transform_origin: default_transform_origin(),
             opacity: 1.0,
             visible: true,
             locked: false,
-            fill: Some(Fill { color: Color { r: 200, g: 200, b: 200, a: 1.0 } }),
+            fills: vec![Fill::solid(Color { r: 200, g: 200, b: 200, a: 1.0 })],
+            legacy_fill: None,
             stroke: None,
             corner_radius: 0.0,
+            corner_smoothing: 0.0,
             children: vec![],
             parent: None,
             text_sizing: TextSizing::default(),
             layout: Layout::default(),
             notes: vec![],
+            visible_when: None,
+            clip_content: false,
+            scroll_x: 0.0,
+            scroll_y: 0.0,
+            effects: vec![],
+            export_settings: vec![],
+            constraints: Constraints::default(),
+            fill_style: None,
+            text_style: None,
+            flex_grow: 0.0,
+            layout_absolute: false,
+            min_width: None,
+            max_width: None,
+            min_height: None,
+            max_height: None,
+            aspect_locked: false,
+            blend_mode: default_blend_mode(),
+        }
+    }
+
+    /// `w` limited to the node's min/max width (min wins if they conflict)
+    pub fn clamp_width(&self, w: f64) -> f64 {
+        clamp_size(w, self.min_width, self.max_width)
+    }
+
+    /// `h` limited to the node's min/max height (min wins if they conflict)
+    pub fn clamp_height(&self, h: f64) -> f64 {
+        clamp_size(h, self.min_height, self.max_height)
+    }
+
+    /// Short kind label ("Rect", "Text", "Instance", ...) for display and naming
+    pub fn kind_label(&self) -> &'static str {
+        match self.kind {
+            NodeKind::Rect => "Rect",
+            NodeKind::Ellipse => "Ellipse",
+            NodeKind::Text { .. } => "Text",
+            NodeKind::Frame => "Frame",
+            NodeKind::Group => "Group",
+            NodeKind::Slot { .. } => "Slot",
+            NodeKind::Instance(_) => "Instance",
+            NodeKind::Path { .. } => "Path",
+            NodeKind::Image { .. } => "Image",
+            NodeKind::Connector { .. } => "Connector",
+            NodeKind::Line { .. } => "Line",
+            NodeKind::Polygon { .. } => "Polygon",
+            NodeKind::Star { .. } => "Star",
+        }
+    }
+
+    /// Canvas composite operation for the blend mode; None for "normal" or an unknown mode
+    pub fn composite_operation(&self) -> Option<&str> {
+        let mode = self.blend_mode.as_str();
+        (mode != "normal" && BLEND_MODES.contains(&mode)).then_some(mode)
+    }
+
+    /// Topmost fill layer; used where only a single paint can be expressed
+    pub fn top_fill(&self) -> Option<&Fill> {
+        self.fills.last()
+    }
+
+    /// Replace the topmost fill's paint with a solid color (adding a layer if there is none),
+    /// keeping the layer's opacity and binding
+    pub fn set_top_fill_color(&mut self, color: Color) {
+        match self.fills.last_mut() {
+            Some(fill) => {
+                fill.color = color;
+                fill.image = None;
+                fill.gradient = None;
+            }
+            None => self.fills.push(Fill::solid(color)),
+        }
+    }
+
+    /// Nothing of the node itself gets painted: zero opacity, or (for leaf shapes) only
+    /// fully transparent color fills and no visible stroke
+    pub fn is_invisible(&self) -> bool {
+        if self.opacity <= 0.0 {
+            return true;
+        }
+        if matches!(self.kind, NodeKind::Frame | NodeKind::Group | NodeKind::Instance(_) | NodeKind::Slot { .. } | NodeKind::Image { .. }) {
+            return false;
+        }
+        let fills_clear = self.fills.iter().all(|f| f.image.is_none() && f.gradient.is_none() && f.layer_color().a <= 0.0);
+        let stroke_clear = self.stroke.as_ref().map(|s| s.width <= 0.0 || s.color.a <= 0.0).unwrap_or(true);
+        fills_clear && stroke_clear
+    }
+
+    /// Set a text node's height from its hard line count, so geometry is right before the next
+    /// render re-measures with real font metrics (which also accounts for wrapping)
+    pub fn estimate_text_height(&mut self) {
+        if let NodeKind::Text { content, font_size, line_height, paragraph_gap, .. } = &self.kind {
+            let lines = content.split('\n').count() as f64;
+            self.height = (font_size * line_height * lines + paragraph_gap * (lines - 1.0)).max(1.0);
         }
     }
 
     pub fn bounds(&self) -> BBox {
         BBox { x: self.x, y: self.y, width: self.width, height: self.height }
     }
+
+    /// Arrowhead triangle (tip, left, right) in scene space at the end of a connector's route
+    pub fn connector_arrowhead(&self) -> Option<[Point; 3]> {
+        let NodeKind::Connector { points, .. } = &self.kind else { return None };
+        let tip = *points.last()?;
+        // Direction of the last segment with any length
+        let back = points.iter().rev().skip(1).find(|p| (p.x - tip.x).hypot(p.y - tip.y) > 1e-9)?;
+        let abs = |p: &Point| Point { x: self.x + p.x, y: self.y + p.y };
+        arrowhead(abs(back), abs(&tip), self.arrow_size())
+    }
+
+    /// Arrowhead length, scaled with the stroke width
+    fn arrow_size(&self) -> f64 {
+        self.stroke.as_ref().map(|s| s.width * 4.0).unwrap_or(0.0).max(8.0)
+    }
+
+    /// Vertices of a polygon or star relative to the node's top-left
+    pub fn shape_vertices(&self) -> Option<Vec<Point>> {
+        let (count, inner) = match self.kind {
+            NodeKind::Polygon { sides } => (sides.max(3) as usize, None),
+            NodeKind::Star { points, inner_radius_ratio } => (points.max(3) as usize * 2, Some(inner_radius_ratio.clamp(0.0, 1.0))),
+            _ => return None,
+        };
+        let (rx, ry) = (self.width / 2.0, self.height / 2.0);
+        Some((0..count).map(|i| {
+            let angle = -std::f64::consts::FRAC_PI_2 + std::f64::consts::TAU * i as f64 / count as f64;
+            let scale = match inner {
+                Some(ratio) if i % 2 == 1 => ratio,
+                _ => 1.0,
+            };
+            Point { x: rx + rx * scale * angle.cos(), y: ry + ry * scale * angle.sin() }
+        }).collect())
+    }
+
+    /// Start and end of a line node in scene space (unrotated)
+    pub fn line_endpoints(&self) -> Option<(Point, Point)> {
+        let NodeKind::Line { x2, y2, .. } = self.kind else { return None };
+        let dx = if x2 == 0.0 { 0.0 } else { self.width.copysign(x2) };
+        let dy = if y2 == 0.0 { 0.0 } else { self.height.copysign(y2) };
+        let start = Point {
+            x: if dx < 0.0 { self.x + self.width } else { self.x },
+            y: if dy < 0.0 { self.y + self.height } else { self.y },
+        };
+        Some((start, Point { x: start.x + dx, y: start.y + dy }))
+    }
+
+    /// Arrowhead triangles for a line's enabled ends, in scene space (unrotated)
+    pub fn line_arrowheads(&self) -> Vec<[Point; 3]> {
+        let (NodeKind::Line { arrow_start, arrow_end, .. }, Some((start, end))) = (&self.kind, self.line_endpoints()) else {
+            return vec![];
+        };
+        let size = self.arrow_size();
+        [(*arrow_start, end, start), (*arrow_end, start, end)].into_iter()
+            .filter(|(on, _, _)| *on)
+            .filter_map(|(_, back, tip)| arrowhead(back, tip, size))
+            .collect()
+    }
+
+    /// Refit x/y/width/height to a path's points, shifting points so the min corner is (0, 0)
+    pub fn normalize_path(&mut self) {
+        if let NodeKind::Path { points, .. } | NodeKind::Connector { points, .. } = &mut self.kind {
+            if points.is_empty() { return; }
+            let min_x = points.iter().map(|p| p.x).fold(f64::MAX, f64::min);
+            let min_y = points.iter().map(|p| p.y).fold(f64::MAX, f64::min);
+            let max_x = points.iter().map(|p| p.x).fold(f64::MIN, f64::max);
+            let max_y = points.iter().map(|p| p.y).fold(f64::MIN, f64::max);
+            for p in points.iter_mut() {
+                p.x -= min_x;
+                p.y -= min_y;
+            }
+            self.x += min_x;
+            self.y += min_y;
+            self.width = (max_x - min_x).max(1.0);
+            self.height = (max_y - min_y).max(1.0);
+        }
+    }
+
+    /// Scene-space rotation pivot derived from `transform_origin`
+    pub fn pivot(&self) -> (f64, f64) {
+        (self.x + self.width * self.transform_origin.0, self.y + self.height * self.transform_origin.1)
+    }
+
+    /// Map a scene-space point into the node's unrotated frame (inverse rotation about the pivot)
+    pub fn unrotate_point(&self, p: Point) -> Point {
+        if self.rotation == 0.0 {
+            return p;
+        }
+        let (px, py) = self.pivot();
+        let (sin, cos) = (-self.rotation).sin_cos();
+        let (dx, dy) = (p.x - px, p.y - py);
+        Point { x: px + dx * cos - dy * sin, y: py + dx * sin + dy * cos }
+    }
+
+    /// Whether a scene-space point lies on the node's shape, honoring rotation; ellipses
+    /// test against the ellipse rather than their box
+    pub fn contains_point(&self, p: Point) -> bool {
+        let local = self.unrotate_point(p);
+        // Lines hit within a few px of the segment, so thin ones stay clickable
+        if let Some((a, b)) = self.line_endpoints() {
+            let tolerance = self.stroke.as_ref().map(|s| s.width / 2.0).unwrap_or(0.0).max(4.0);
+            return distance_to_segment(local, a, b) <= tolerance;
+        }
+        if !self.bounds().contains(local) {
+            return false;
+        }
+        match self.kind {
+            NodeKind::Ellipse => {
+                let (rx, ry) = (self.width / 2.0, self.height / 2.0);
+                let nx = (local.x - self.x - rx) / rx;
+                let ny = (local.y - self.y - ry) / ry;
+                nx * nx + ny * ny <= 1.0
+            }
+            NodeKind::Polygon { .. } | NodeKind::Star { .. } => {
+                let vertices = self.shape_vertices().unwrap_or_default();
+                polygon_contains(&vertices, Point { x: local.x - self.x, y: local.y - self.y })
+            }
+            // Every other kind (rects, frames, text, images, paths, connectors...) hits on its
+            // bounding box; paths and connectors don't test the stroke itself
+            _ => true,
+        }
+    }
+
+    /// Axis-aligned box enclosing the node after rotation about its pivot
+    pub fn rotated_bounds(&self) -> BBox {
+        if self.rotation == 0.0 {
+            return self.bounds();
+        }
+        let (px, py) = self.pivot();
+        let (sin, cos) = self.rotation.sin_cos();
+        let corners = [
+            (self.x, self.y),
+            (self.x + self.width, self.y),
+            (self.x, self.y + self.height),
+            (self.x + self.width, self.y + self.height),
+        ];
+        let (mut min_x, mut min_y) = (f64::MAX, f64::MAX);
+        let (mut max_x, mut max_y) = (f64::MIN, f64::MIN);
+        for (cx, cy) in corners {
+            let (dx, dy) = (cx - px, cy - py);
+            let rx = px + dx * cos - dy * sin;
+            let ry = py + dx * sin + dy * cos;
+            min_x = min_x.min(rx); min_y = min_y.min(ry);
+            max_x = max_x.max(rx); max_y = max_y.max(ry);
+        }
+        BBox { x: min_x, y: min_y, width: max_x - min_x, height: max_y - min_y }
+    }
 }
@@ -1,14 +1,217 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use wasm_bindgen::JsValue;
-use web_sys::CanvasRenderingContext2d;
-use crate::node::{Node, NodeKind, TextSizing, TextAlign, FontStyle};
-use crate::scene::Scene;
+use web_sys::{CanvasRenderingContext2d, HtmlImageElement};
+use crate::node::{Effect, Gradient, Node, NodeKind, Stroke, TextSizing, TextAlign, TextDirection, FontStyle, ListStyle};
+use crate::hit_test::ROTATION_HANDLE_GAP;
+use crate::scene::{Axis, Scene};
 use crate::transform::Transform;
-use crate::types::Color;
+use crate::types::{Color, Point, Rect};
 
 pub struct Renderer {
     pub viewport: Transform,
     pub canvas_width: f64,
     pub canvas_height: f64,
+    /// Offscreen export: skip editor chrome (frame labels, note badges)
+    pub export_mode: bool,
+    /// When set, pan/zoom keep at least this many screen px of the content on screen
+    pan_limit: Option<f64>,
+    /// Container highlighted as the drop target while dragging nodes
+    pub drop_target: Option<u64>,
+    /// Snap grid size in scene units; None draws the default adaptive grid
+    pub grid_size: Option<f64>,
+    /// Interpolate image fills when scaling; off keeps bitmaps pixel-crisp
+    image_smoothing: bool,
+    /// Image registry: decoded images keyed by src
+    images: RefCell<HashMap<String, HtmlImageElement>>,
+    /// Measured text widths keyed by "<font>\0<text>"; stale once web fonts finish loading
+    text_widths: RefCell<HashMap<String, f64>>,
+}
+
+/// Drop the whole width cache past this many entries rather than tracking recency
+const TEXT_WIDTH_CACHE_LIMIT: usize = 4096;
+/// Bezier handle length (as a fraction of the radius) that approximates a quarter circle
+const CIRCLE_KAPPA: f64 = 0.5523;
+
+/// Split text into user-perceived characters: a base char plus any combining marks,
+/// variation selectors, skin-tone modifiers, tag chars, or ZWJ-joined followers, and
+/// regional-indicator pairs (flags). Never splits inside a UTF-8 sequence.
+fn grapheme_clusters(text: &str) -> Vec<&str> {
+    let extends = |c: char| matches!(c as u32,
+        0x0300..=0x036F | 0x1AB0..=0x1AFF | 0x20D0..=0x20FF | 0xFE20..=0xFE2F
+        | 0xFE00..=0xFE0F | 0xE0100..=0xE01EF | 0x1F3FB..=0x1F3FF | 0xE0020..=0xE007F | 0x200D);
+    let regional = |c: char| matches!(c as u32, 0x1F1E6..=0x1F1FF);
+    let mut clusters = vec![];
+    let mut chars = text.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        let mut prev = c;
+        let mut flag_pair = regional(c);
+        while let Some(&(_, next)) = chars.peek() {
+            if extends(next) || prev == '\u{200D}' || (flag_pair && regional(next)) {
+                flag_pair = false;
+                prev = next;
+                chars.next();
+            } else {
+                break;
+            }
+        }
+        let end = chars.peek().map(|&(i, _)| i).unwrap_or(text.len());
+        clusters.push(&text[start..end]);
+    }
+    clusters
+}
+
+/// Scripts written without spaces, where a line may break between any two characters
+fn is_cjk(cluster: &str) -> bool {
+    cluster.chars().next().is_some_and(|c| matches!(c as u32,
+        0x1100..=0x11FF | 0x2E80..=0x9FFF | 0xAC00..=0xD7AF | 0xF900..=0xFAFF | 0xFF00..=0xFFEF | 0x20000..=0x2FFFF))
+}
+
+/// Greedy word wrap of one paragraph. Breaks at spaces and between CJK characters; a word
+/// wider than `max_width` on its own is broken between grapheme clusters.
+fn wrap_paragraph(paragraph: &str, max_width: f64, measure: &mut dyn FnMut(&str) -> f64) -> Vec<String> {
+    // Break units, each flagged with whether a space separates it from the previous unit
+    let mut units: Vec<(String, bool)> = vec![];
+    for (i, word) in paragraph.split(' ').enumerate() {
+        let mut spaced = i > 0;
+        let mut pending = String::new();
+        for cluster in grapheme_clusters(word) {
+            if is_cjk(cluster) {
+                if !pending.is_empty() {
+                    units.push((std::mem::take(&mut pending), spaced));
+                    spaced = false;
+                }
+                units.push((cluster.to_string(), spaced));
+                spaced = false;
+            } else {
+                pending.push_str(cluster);
+            }
+        }
+        if !pending.is_empty() || word.is_empty() {
+            units.push((pending, spaced));
+        }
+    }
+
+    let mut lines = vec![];
+    let mut current = String::new();
+    for (unit, spaced) in units {
+        let test = if current.is_empty() {
+            unit.clone()
+        } else if spaced {
+            format!("{} {}", current, unit)
+        } else {
+            format!("{}{}", current, unit)
+        };
+        if measure(&test) <= max_width || (current.is_empty() && grapheme_clusters(&unit).len() <= 1) {
+            current = test;
+            continue;
+        }
+        if !current.is_empty() {
+            lines.push(std::mem::take(&mut current));
+        }
+        if measure(&unit) <= max_width {
+            current = unit;
+            continue;
+        }
+        // Over-long word: fill lines cluster by cluster
+        for cluster in grapheme_clusters(&unit) {
+            let test = format!("{}{}", current, cluster);
+            if measure(&test) > max_width && !current.is_empty() {
+                lines.push(std::mem::take(&mut current));
+                current = cluster.to_string();
+            } else {
+                current = test;
+            }
+        }
+    }
+    if !current.is_empty() {
+        lines.push(current);
+    }
+    lines
+}
+
+/// Left edge of a line of width `line_w` inside the area [area_x, area_x + area_w]. For RTL
+/// text the alignment is logical: Left (start) hugs the right edge and Right (end) the left.
+fn line_x(align: &TextAlign, direction: &TextDirection, area_x: f64, area_w: f64, line_w: f64) -> f64 {
+    let at_end = match (align, direction) {
+        (TextAlign::Center, _) => return area_x + (area_w - line_w) / 2.0,
+        (TextAlign::Left, TextDirection::Ltr) | (TextAlign::Right, TextDirection::Rtl) => false,
+        (TextAlign::Right, TextDirection::Ltr) | (TextAlign::Left, TextDirection::Rtl) => true,
+    };
+    if at_end { area_x + area_w - line_w } else { area_x }
+}
+
+/// One wrapped line of a text node
+struct TextLine {
+    text: String,
+    /// List marker drawn in the indent, on a paragraph's first line only
+    marker: Option<String>,
+    /// Index of the hard-break paragraph this line belongs to
+    paragraph: usize,
+}
+
+/// Vertical placement shared by every line of a text node
+struct LineMetrics {
+    line_h: f64,
+    /// Distance from the top of a line box to its baseline
+    baseline: f64,
+    paragraph_gap: f64,
+    /// Viewport zoom, for snapping runs to device pixels
+    zoom: f64,
+}
+
+/// Position every run of wrapped text: (text, left x, baseline y), with a line's list marker
+/// as its own run just before the line
+fn place_text_runs(node: &Node, lines: &[TextLine], indent: f64, align: &TextAlign, direction: &TextDirection, metrics: &LineMetrics, measure: &mut dyn FnMut(&str) -> f64) -> Vec<(String, f64, f64)> {
+    let pad = Renderer::text_stroke_pad(node);
+    let zoom = metrics.zoom;
+    // Text is aligned within the area beside the list indent (left for LTR, right for RTL)
+    let rtl = *direction == TextDirection::Rtl;
+    let text_x = if rtl { node.x + pad } else { node.x + pad + indent };
+    let text_w = node.width - pad * 2.0 - indent;
+    let mut runs = vec![];
+    for (i, line) in lines.iter().enumerate() {
+        let raw_y = node.y + pad + metrics.baseline + metrics.line_h * i as f64 + metrics.paragraph_gap * line.paragraph as f64;
+        let snapped_y = (raw_y * zoom).round() / zoom;
+        if let Some(marker) = &line.marker {
+            let marker_x = if rtl { node.x + node.width - pad - measure(marker) } else { node.x + pad };
+            runs.push((marker.clone(), (marker_x * zoom).round() / zoom, snapped_y));
+        }
+        let x = line_x(align, direction, text_x, text_w, measure(&line.text));
+        runs.push((line.text.clone(), (x * zoom).round() / zoom, snapped_y));
+    }
+    runs
+}
+
+/// One paint of a text run
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum TextPass {
+    Fill,
+    Stroke,
+}
+
+/// Paint order for a run of text, each pass flagged with whether it casts the drop shadow.
+/// Only the first pass casts it, so outlined text doesn't get a doubled shadow.
+fn text_paint_passes(fill: bool, stroke: bool, stroke_under: bool) -> Vec<(TextPass, bool)> {
+    let mut passes = vec![];
+    if stroke && stroke_under {
+        passes.push(TextPass::Stroke);
+    }
+    if fill {
+        passes.push(TextPass::Fill);
+    }
+    if stroke && !stroke_under {
+        passes.push(TextPass::Stroke);
+    }
+    passes.into_iter().enumerate().map(|(i, p)| (p, i == 0)).collect()
+}
+
+fn list_marker(style: &ListStyle, number: usize) -> Option<String> {
+    match style {
+        ListStyle::None => None,
+        ListStyle::Bullet => Some("•".to_string()),
+        ListStyle::Numbered => Some(format!("{}.", number)),
+    }
 }
 
 impl Renderer {
@@ -17,6 +220,58 @@ impl Renderer {
             viewport: Transform::identity(),
             canvas_width: width,
             canvas_height: height,
+            export_mode: false,
+            image_smoothing: true,
+            drop_target: None,
+            grid_size: None,
+            pan_limit: None,
+            images: RefCell::new(HashMap::new()),
+            text_widths: RefCell::new(HashMap::new()),
+        }
+    }
+
+    pub fn set_image_smoothing(&mut self, enabled: bool) {
+        self.image_smoothing = enabled;
+    }
+
+    /// Forget cached text widths so every text node is re-measured with the current fonts
+    pub fn invalidate_text_metrics(&self) {
+        self.text_widths.borrow_mut().clear();
+    }
+
+    /// Width of `text` in the context's current font, cached per font
+    fn text_width(&self, ctx: &CanvasRenderingContext2d, text: &str) -> f64 {
+        let key = format!("{}\0{}", ctx.font(), text);
+        if let Some(&w) = self.text_widths.borrow().get(&key) {
+            return w;
+        }
+        let w = ctx.measure_text(text).map(|m| m.width()).unwrap_or(0.0);
+        let mut cache = self.text_widths.borrow_mut();
+        if cache.len() >= TEXT_WIDTH_CACHE_LIMIT {
+            cache.clear();
+        }
+        cache.insert(key, w);
+        w
+    }
+
+    /// Register an image source so it starts decoding before it's first drawn
+    pub fn register_image(&self, src: &str) {
+        self.image(src);
+    }
+
+    /// Look up (or start loading) an image. Returns None until it has finished decoding.
+    fn image(&self, src: &str) -> Option<HtmlImageElement> {
+        let mut images = self.images.borrow_mut();
+        if !images.contains_key(src) {
+            let img = HtmlImageElement::new().ok()?;
+            img.set_src(src);
+            images.insert(src.to_string(), img);
+        }
+        let img = images.get(src)?;
+        if img.complete() && img.natural_width() > 0 {
+            Some(img.clone())
+        } else {
+            None
         }
     }
 
@@ -30,7 +285,7 @@ impl Renderer {
     }
 
     /// Word-wrap text into lines fitting within max_width. If max_width is None, no wrapping.
-    fn wrap_text(ctx: &CanvasRenderingContext2d, text: &str, max_width: Option<f64>) -> Vec<String> {
+    fn wrap_text(&self, ctx: &CanvasRenderingContext2d, text: &str, max_width: Option<f64>) -> Vec<String> {
         let mut lines = Vec::new();
         for paragraph in text.split('\n') {
             if paragraph.is_empty() {
@@ -39,28 +294,7 @@ impl Renderer {
             }
             match max_width {
                 Some(mw) if mw > 0.0 => {
-                    let words: Vec<&str> = paragraph.split(' ').collect();
-                    let mut current_line = String::new();
-                    for word in words {
-                        let test = if current_line.is_empty() {
-                            word.to_string()
-                        } else {
-                            format!("{} {}", current_line, word)
-                        };
-                        if let Ok(m) = ctx.measure_text(&test) {
-                            if m.width() > mw && !current_line.is_empty() {
-                                lines.push(current_line);
-                                current_line = word.to_string();
-                            } else {
-                                current_line = test;
-                            }
-                        } else {
-                            current_line = test;
-                        }
-                    }
-                    if !current_line.is_empty() {
-                        lines.push(current_line);
-                    }
+                    lines.extend(wrap_paragraph(paragraph, mw, &mut |s| self.text_width(ctx, s)));
                 }
                 _ => {
                     lines.push(paragraph.to_string());
@@ -73,19 +307,73 @@ impl Renderer {
         lines
     }
 
+    /// Split content into paragraphs, prefix list markers, and wrap each paragraph to the
+    /// width left after the marker indent. Returns the lines and the indent width.
+    fn layout_text_lines(&self, ctx: &CanvasRenderingContext2d, content: &str, max_width: Option<f64>, list_style: &ListStyle) -> (Vec<TextLine>, f64) {
+        let paragraphs: Vec<&str> = content.split('\n').collect();
+        let mut markers = vec![];
+        let mut number = 0;
+        for paragraph in &paragraphs {
+            if paragraph.is_empty() {
+                markers.push(None);
+            } else {
+                number += 1;
+                markers.push(list_marker(list_style, number));
+            }
+        }
+        // Indent fits the widest marker plus a trailing space
+        let indent = markers.iter()
+            .flatten()
+            .map(|m| self.text_width(ctx, &format!("{} ", m)))
+            .fold(0.0, f64::max);
+
+        let wrap_width = max_width.map(|w| (w - indent).max(1.0));
+        let mut lines = vec![];
+        for (i, paragraph) in paragraphs.iter().enumerate() {
+            for (j, text) in self.wrap_text(ctx, paragraph, wrap_width).into_iter().enumerate() {
+                let marker = if j == 0 { markers[i].clone() } else { None };
+                lines.push(TextLine { text, marker, paragraph: i });
+            }
+        }
+        (lines, indent)
+    }
+
+    /// Half the text outline width: the outline extends this far outside the glyphs,
+    /// so text is inset by it and the box grows by it on every side
+    fn text_stroke_pad(node: &Node) -> f64 {
+        node.stroke.as_ref().map(|s| s.width / 2.0).unwrap_or(0.0)
+    }
+
+    /// Record the bitmap size of image nodes whose source has finished decoding (starting the
+    /// load for ones not seen yet)
+    pub fn measure_images(&self, scene: &mut Scene) {
+        for id in scene.all_node_ids() {
+            let src = match scene.get_node(id).map(|n| &n.kind) {
+                Some(NodeKind::Image { src, natural_width, .. }) if *natural_width <= 0.0 => src.clone(),
+                _ => continue,
+            };
+            let Some(img) = self.image(&src) else { continue };
+            if let Some(NodeKind::Image { natural_width, natural_height, .. }) = scene.get_node_mut(id).map(|n| &mut n.kind) {
+                *natural_width = img.natural_width() as f64;
+                *natural_height = img.natural_height() as f64;
+            }
+        }
+    }
+
     /// Measure all Fit-mode text nodes and update their dimensions
     pub fn measure_text_nodes(&self, ctx: &CanvasRenderingContext2d, scene: &mut Scene) {
         let ids: Vec<u64> = scene.all_node_ids();
         for id in ids {
-            let (content, font_size, font_family, line_height, font_weight, font_style, is_fit, node_width) = {
+            let (content, font_size, font_family, line_height, font_weight, font_style, paragraph_gap, list_style, is_fit, node_width, stroke_pad) = {
                 let node = match scene.get_node(id) {
                     Some(n) => n,
                     None => continue,
                 };
                 match &node.kind {
-                    NodeKind::Text { content, font_size, font_family, line_height, font_weight, font_style, .. } => {
+                    NodeKind::Text { content, font_size, font_family, line_height, font_weight, font_style, paragraph_gap, list_style, .. } => {
                         (content.clone(), *font_size, font_family.clone(), *line_height, *font_weight, font_style.clone(),
-                         node.text_sizing == TextSizing::Fit, node.width)
+                         *paragraph_gap, list_style.clone(), node.text_sizing == TextSizing::Fit, node.width,
+                         Self::text_stroke_pad(node))
                     }
                     _ => continue,
                 }
@@ -94,8 +382,8 @@ impl Renderer {
             let font_str = Self::build_font_string(font_size, &font_family, font_weight, &font_style);
             ctx.set_font(&font_str);
 
-            let max_width = if !is_fit { Some(node_width) } else { None };
-            let lines = Self::wrap_text(ctx, &content, max_width);
+            let max_width = if !is_fit { Some(node_width - stroke_pad * 2.0) } else { None };
+            let (lines, indent) = self.layout_text_lines(ctx, &content, max_width, &list_style);
             let line_h = font_size * line_height;
 
             // Use font metrics for accurate bounding box height
@@ -108,22 +396,21 @@ impl Renderer {
             };
             // Line height is at least the font's natural height
             let effective_line_h = line_h.max(font_height);
+            let paragraph_count = lines.last().map(|l| l.paragraph + 1).unwrap_or(1);
+            let total_h = effective_line_h * lines.len() as f64 + paragraph_gap * (paragraph_count - 1) as f64
+                + stroke_pad * 2.0;
 
             if is_fit {
                 let mut max_w: f64 = 1.0;
                 for line in &lines {
-                    if let Ok(m) = ctx.measure_text(line) {
-                        max_w = max_w.max(m.width());
-                    }
+                    max_w = max_w.max(indent + self.text_width(ctx, &line.text) + stroke_pad * 2.0);
                 }
-                let total_h = effective_line_h * lines.len() as f64;
                 if let Some(node) = scene.get_node_mut(id) {
                     node.width = max_w.max(1.0);
                     node.height = total_h.max(1.0);
                 }
             } else {
                 // Fixed mode: update height to fit content
-                let total_h = effective_line_h * lines.len() as f64;
                 if let Some(node) = scene.get_node_mut(id) {
                     node.height = total_h.max(1.0);
                 }
@@ -131,7 +418,7 @@ impl Renderer {
         }
     }
 
-    pub fn render(&self, ctx: &CanvasRenderingContext2d, scene: &Scene, _editing_node: Option<u64>) {
+    pub fn render(&self, ctx: &CanvasRenderingContext2d, scene: &Scene, _editing_node: Option<u64>, path_edit_mode: bool) {
         ctx.set_fill_style_str("#1a1a1a");
         ctx.fill_rect(0.0, 0.0, self.canvas_width, self.canvas_height);
         self.draw_grid(ctx);
@@ -143,19 +430,49 @@ impl Renderer {
             self.viewport.tx, self.viewport.ty,
         ).ok();
 
-        for id in scene.render_order() {
-            if let Some(node) = scene.get_node(id) {
-                if !node.visible { continue; }
-                self.render_node(ctx, node, scene);
-            }
+        let mut visited = HashSet::new();
+        for &id in scene.root_children() {
+            self.render_subtree(ctx, id, scene, &mut visited);
         }
 
+        let multi = scene.selection.len() > 1;
         for &id in &scene.selection {
             if let Some(node) = scene.get_node(id) {
-                self.render_selection(ctx, node);
+                let (ox, oy) = scene.scroll_offset(id);
+                ctx.save();
+                ctx.translate(-ox, -oy).ok();
+                self.render_selection(ctx, node, !multi);
+                ctx.restore();
+            }
+        }
+        if multi {
+            if let Some(bounds) = scene.selection_bounds() {
+                self.render_selection_bounds(ctx, bounds);
+            }
+        }
+
+        if let Some(node) = self.drop_target.and_then(|id| scene.get_node(id)) {
+            let (ox, oy) = scene.scroll_offset(node.id);
+            ctx.save();
+            ctx.translate(-ox, -oy).ok();
+            ctx.set_fill_style_str("rgba(59,130,246,0.08)");
+            ctx.fill_rect(node.x, node.y, node.width, node.height);
+            ctx.set_stroke_style_str(&Color::blue().to_css());
+            ctx.set_line_width(2.0 / self.viewport.a);
+            ctx.stroke_rect(node.x, node.y, node.width, node.height);
+            ctx.restore();
+        }
+
+        if path_edit_mode {
+            for &id in &scene.selection {
+                if let Some(node) = scene.get_node(id) {
+                    self.render_path_vertices(ctx, node);
+                }
             }
         }
 
+        self.draw_guides(ctx, scene);
+
         // Editing text cursor indicator
         if let Some(eid) = _editing_node {
             if let Some(node) = scene.get_node(eid) {
@@ -171,18 +488,81 @@ impl Renderer {
         ctx.restore();
     }
 
+    /// Render visible nodes only (no background, grid, or selection) through the viewport
+    pub fn render_nodes(&self, ctx: &CanvasRenderingContext2d, scene: &Scene) {
+        ctx.save();
+        ctx.transform(
+            self.viewport.a, self.viewport.b,
+            self.viewport.c, self.viewport.d,
+            self.viewport.tx, self.viewport.ty,
+        ).ok();
+        let mut visited = HashSet::new();
+        for &id in scene.root_children() {
+            self.render_subtree(ctx, id, scene, &mut visited);
+        }
+        ctx.restore();
+    }
+
+    /// Render a node and its descendants, clipping and scrolling children of clipped frames.
+    /// Nodes already in `visited` are skipped so a child cycle can't recurse forever.
+    fn render_subtree(&self, ctx: &CanvasRenderingContext2d, id: u64, scene: &Scene, visited: &mut HashSet<u64>) {
+        let node = match scene.get_node(id) {
+            Some(n) if n.visible && visited.insert(id) => n,
+            _ => return,
+        };
+        self.render_node(ctx, node, scene);
+        if node.children.is_empty() {
+            return;
+        }
+        if node.clip_content {
+            ctx.save();
+            self.draw_rounded_rect(ctx, node.x, node.y, node.width, node.height, node.corner_radius, node.corner_smoothing);
+            ctx.clip();
+            ctx.translate(-node.scroll_x, -node.scroll_y).ok();
+        }
+        for &child in &node.children {
+            self.render_subtree(ctx, child, scene, visited);
+        }
+        if node.clip_content {
+            ctx.restore();
+        }
+    }
+
     fn render_node(&self, ctx: &CanvasRenderingContext2d, node: &Node, scene: &Scene) {
         ctx.save();
         ctx.set_global_alpha(node.opacity);
+        // Composite mode is part of the saved state, so the restore below resets it for later nodes
+        if let Some(op) = node.composite_operation() {
+            ctx.set_global_composite_operation(op).ok();
+        }
+        self.apply_shadow(ctx, node);
+
+        // Rects, ellipses, paths and images rotate in their own draw calls; these kinds are
+        // drawn in scene coordinates, so rotate the whole context about the pivot
+        if node.rotation != 0.0 && matches!(node.kind, NodeKind::Frame | NodeKind::Text { .. } | NodeKind::Instance(_)) {
+            let (px, py) = node.pivot();
+            ctx.translate(px, py).ok();
+            ctx.rotate(node.rotation).ok();
+            ctx.translate(-px, -py).ok();
+        }
 
         match &node.kind {
             NodeKind::Rect => self.render_rect(ctx, node),
             NodeKind::Ellipse => self.render_ellipse(ctx, node),
-            NodeKind::Text { content, font_size, font_family, line_height, text_align, font_weight, font_style } => self.render_text(ctx, node, content, *font_size, font_family, *line_height, text_align, *font_weight, font_style),
+            NodeKind::Text { content, font_size, font_family, line_height, text_align, font_weight, font_style, paragraph_gap, list_style, stroke_under, direction } => self.render_text(ctx, node, content, *font_size, font_family, *line_height, text_align, *font_weight, font_style, *paragraph_gap, list_style, *stroke_under, direction),
             NodeKind::Frame => self.render_frame(ctx, node, scene),
             NodeKind::Group => {}
             NodeKind::Slot { .. } => self.render_slot(ctx, node),
             NodeKind::Instance(_) => self.render_instance(ctx, node, scene),
+            NodeKind::Path { points, closed } => self.render_path(ctx, node, points, *closed),
+            NodeKind::Connector { points, .. } => self.render_connector(ctx, node, points),
+            NodeKind::Line { .. } => self.render_line(ctx, node),
+            NodeKind::Polygon { .. } | NodeKind::Star { .. } => {
+                if let Some(points) = node.shape_vertices() {
+                    self.render_path(ctx, node, &points, true);
+                }
+            }
+            NodeKind::Image { src, .. } => self.render_image(ctx, node, src),
         }
 
         ctx.restore();
@@ -190,40 +570,175 @@ impl Renderer {
 
     fn render_rect(&self, ctx: &CanvasRenderingContext2d, node: &Node) {
         if node.rotation != 0.0 {
+            let (px, py) = node.pivot();
             ctx.save();
-            ctx.translate(node.x + node.width / 2.0, node.y + node.height / 2.0).ok();
+            ctx.translate(px, py).ok();
             ctx.rotate(node.rotation).ok();
-            let x = -node.width / 2.0;
-            let y = -node.height / 2.0;
-            self.draw_rounded_rect(ctx, x, y, node.width, node.height, node.corner_radius);
-            self.apply_fill_stroke(ctx, node);
+            let x = node.x - px;
+            let y = node.y - py;
+            self.draw_rounded_rect(ctx, x, y, node.width, node.height, node.corner_radius, node.corner_smoothing);
+            self.apply_fill_stroke(ctx, node, x, y);
             ctx.restore();
         } else {
-            self.draw_rounded_rect(ctx, node.x, node.y, node.width, node.height, node.corner_radius);
-            self.apply_fill_stroke(ctx, node);
+            self.draw_rounded_rect(ctx, node.x, node.y, node.width, node.height, node.corner_radius, node.corner_smoothing);
+            self.apply_fill_stroke(ctx, node, node.x, node.y);
         }
     }
 
     fn render_ellipse(&self, ctx: &CanvasRenderingContext2d, node: &Node) {
+        // Rotate about the transform origin, which may be off-center
+        let (px, py) = node.pivot();
+        ctx.save();
+        ctx.translate(px, py).ok();
+        ctx.rotate(node.rotation).ok();
         ctx.begin_path();
         ctx.ellipse(
-            node.x + node.width / 2.0,
-            node.y + node.height / 2.0,
+            node.x + node.width / 2.0 - px,
+            node.y + node.height / 2.0 - py,
             node.width / 2.0,
             node.height / 2.0,
-            node.rotation,
+            0.0,
             0.0,
             std::f64::consts::TAU,
         ).ok();
-        self.apply_fill_stroke(ctx, node);
+        self.apply_fill_stroke(ctx, node, node.x - px, node.y - py);
+        ctx.restore();
     }
 
-    fn render_text(&self, ctx: &CanvasRenderingContext2d, node: &Node, content: &str, font_size: f64, font_family: &str, line_height: f64, text_align: &TextAlign, font_weight: u16, font_style: &FontStyle) {
-        if let Some(fill) = &node.fill {
-            ctx.set_fill_style_str(&fill.color.to_css());
+    fn render_path(&self, ctx: &CanvasRenderingContext2d, node: &Node, points: &[Point], closed: bool) {
+        if points.is_empty() { return; }
+        let (px, py) = node.pivot();
+        ctx.save();
+        ctx.translate(px, py).ok();
+        ctx.rotate(node.rotation).ok();
+        let (ox, oy) = (node.x - px, node.y - py);
+        ctx.begin_path();
+        ctx.move_to(ox + points[0].x, oy + points[0].y);
+        for p in &points[1..] {
+            ctx.line_to(ox + p.x, oy + p.y);
+        }
+        if closed {
+            ctx.close_path();
+        }
+        self.apply_fill_stroke(ctx, node, ox, oy);
+        ctx.restore();
+    }
+
+    /// Draw an image node stretched to its box, or a gray placeholder until it has decoded
+    /// (the image cache keeps loading it, so a later render picks it up)
+    fn render_image(&self, ctx: &CanvasRenderingContext2d, node: &Node, src: &str) {
+        let (px, py) = node.pivot();
+        ctx.save();
+        ctx.translate(px, py).ok();
+        ctx.rotate(node.rotation).ok();
+        let (x, y) = (node.x - px, node.y - py);
+        match self.image(src) {
+            Some(img) => {
+                ctx.set_image_smoothing_enabled(self.image_smoothing);
+                ctx.draw_image_with_html_image_element_and_dw_and_dh(&img, x, y, node.width, node.height).ok();
+            }
+            None => {
+                ctx.set_fill_style_str("rgba(128,128,128,0.3)");
+                ctx.fill_rect(x, y, node.width, node.height);
+            }
+        }
+        Self::clear_shadow(ctx);
+        if let Some(stroke) = &node.stroke {
+            self.apply_stroke_style(ctx, stroke);
+            ctx.stroke_rect(x, y, node.width, node.height);
+        }
+        ctx.restore();
+    }
+
+    /// Routed line plus a filled arrowhead in the stroke color. Connectors ignore rotation.
+    fn render_connector(&self, ctx: &CanvasRenderingContext2d, node: &Node, points: &[Point]) {
+        let Some(stroke) = &node.stroke else { return };
+        if points.len() < 2 { return; }
+        ctx.begin_path();
+        ctx.move_to(node.x + points[0].x, node.y + points[0].y);
+        for p in &points[1..] {
+            ctx.line_to(node.x + p.x, node.y + p.y);
+        }
+        self.apply_stroke_style(ctx, stroke);
+        ctx.stroke();
+        if let Some([tip, left, right]) = node.connector_arrowhead() {
+            ctx.set_line_dash(&js_sys::Array::new()).ok();
+            ctx.begin_path();
+            ctx.move_to(tip.x, tip.y);
+            ctx.line_to(left.x, left.y);
+            ctx.line_to(right.x, right.y);
+            ctx.close_path();
+            ctx.set_fill_style_str(&stroke.color.to_css());
+            ctx.fill();
+        }
+    }
+
+    /// Stroked segment with filled arrowheads in the stroke color, rotated about the pivot
+    fn render_line(&self, ctx: &CanvasRenderingContext2d, node: &Node) {
+        let (Some(stroke), Some((start, end))) = (&node.stroke, node.line_endpoints()) else { return };
+        ctx.save();
+        if node.rotation != 0.0 {
+            let (px, py) = node.pivot();
+            ctx.translate(px, py).ok();
+            ctx.rotate(node.rotation).ok();
+            ctx.translate(-px, -py).ok();
+        }
+        ctx.begin_path();
+        ctx.move_to(start.x, start.y);
+        ctx.line_to(end.x, end.y);
+        self.apply_stroke_style(ctx, stroke);
+        ctx.stroke();
+        ctx.set_line_dash(&js_sys::Array::new()).ok();
+        ctx.set_fill_style_str(&stroke.color.to_css());
+        for [tip, left, right] in node.line_arrowheads() {
+            ctx.begin_path();
+            ctx.move_to(tip.x, tip.y);
+            ctx.line_to(left.x, left.y);
+            ctx.line_to(right.x, right.y);
+            ctx.close_path();
+            ctx.fill();
+        }
+        ctx.restore();
+    }
+
+    /// Vertex handles for a selected path in edit-path mode
+    fn render_path_vertices(&self, ctx: &CanvasRenderingContext2d, node: &Node) {
+        let points = match &node.kind {
+            NodeKind::Path { points, .. } => points,
+            _ => return,
+        };
+        let (px, py) = node.pivot();
+        ctx.save();
+        ctx.translate(px, py).ok();
+        ctx.rotate(node.rotation).ok();
+        let r = 4.0 / self.viewport.a;
+        ctx.set_fill_style_str("white");
+        ctx.set_stroke_style_str(&Color::blue().to_css());
+        ctx.set_line_width(1.5 / self.viewport.a);
+        for p in points {
+            ctx.begin_path();
+            ctx.arc(node.x - px + p.x, node.y - py + p.y, r, 0.0, std::f64::consts::TAU).ok();
+            ctx.fill();
+            ctx.stroke();
+        }
+        ctx.restore();
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render_text(&self, ctx: &CanvasRenderingContext2d, node: &Node, content: &str, font_size: f64, font_family: &str, line_height: f64, text_align: &TextAlign, font_weight: u16, font_style: &FontStyle, paragraph_gap: f64, list_style: &ListStyle, stroke_under: bool, direction: &TextDirection) {
+        if !node.fills.is_empty() || node.stroke.is_some() {
+            if let Some(stroke) = &node.stroke {
+                self.apply_stroke_style(ctx, stroke);
+                ctx.set_line_join("round");
+            }
             let font_str = Self::build_font_string(font_size, font_family, font_weight, font_style);
             ctx.set_font(&font_str);
             ctx.set_text_baseline("alphabetic");
+            // x is always the run's left edge; direction only drives bidi glyph ordering
+            ctx.set_text_align("left");
+            // web-sys has no binding for the canvas `direction` property
+            let dir = if *direction == TextDirection::Rtl { "rtl" } else { "ltr" };
+            js_sys::Reflect::set(ctx.as_ref(), &JsValue::from_str("direction"), &JsValue::from_str(dir)).ok();
 
             // Get font metrics for baseline positioning
             let (font_ascent, font_descent) = if let Ok(m) = ctx.measure_text("Mg") {
@@ -235,67 +750,71 @@ impl Renderer {
             };
             let font_height = font_ascent + font_descent;
 
-            let max_width = if node.text_sizing == TextSizing::Fixed { Some(node.width) } else { None };
-            let lines = Self::wrap_text(ctx, content, max_width);
+            let pad = Self::text_stroke_pad(node);
+            let max_width = if node.text_sizing == TextSizing::Fixed { Some(node.width - pad * 2.0) } else { None };
+            let (lines, indent) = self.layout_text_lines(ctx, content, max_width, list_style);
             let line_h = (font_size * line_height).max(font_height);
-            let zoom = self.viewport.a;
-            // Center font within line height
-            let half_leading = (line_h - font_height) / 2.0;
-
-            for (i, line) in lines.iter().enumerate() {
-                // Baseline = top of line + half_leading + font_ascent
-                let raw_y = node.y + half_leading + font_ascent + line_h * i as f64;
-                let snapped_y = (raw_y * zoom).round() / zoom;
-
-                // text_align x calculation
-                let x = match text_align {
-                    TextAlign::Left => {
-                        let raw_x = node.x;
-                        (raw_x * zoom).round() / zoom
-                    }
-                    TextAlign::Center => {
-                        let lw = ctx.measure_text(line).map(|m| m.width()).unwrap_or(0.0);
-                        let raw_x = node.x + (node.width - lw) / 2.0;
-                        (raw_x * zoom).round() / zoom
-                    }
-                    TextAlign::Right => {
-                        let lw = ctx.measure_text(line).map(|m| m.width()).unwrap_or(0.0);
-                        let raw_x = node.x + node.width - lw;
-                        (raw_x * zoom).round() / zoom
-                    }
-                };
-
-                ctx.fill_text(line, x, snapped_y).ok();
+            // Center font within line height: baseline = top of line + half_leading + font_ascent
+            let metrics = LineMetrics {
+                line_h,
+                baseline: (line_h - font_height) / 2.0 + font_ascent,
+                paragraph_gap,
+                zoom: self.viewport.a,
+            };
+            let runs = place_text_runs(node, &lines, indent, text_align, direction, &metrics, &mut |s| self.text_width(ctx, s));
+            for (text, x, y) in runs {
+                self.draw_text_run(ctx, node, &text, x, y, stroke_under);
             }
         }
     }
 
-    fn render_frame(&self, ctx: &CanvasRenderingContext2d, node: &Node, scene: &Scene) {
-        if let Some(fill) = &node.fill {
-            ctx.set_fill_style_str(&fill.color.to_css());
-            if node.corner_radius > 0.0 {
-                self.draw_rounded_rect(ctx, node.x, node.y, node.width, node.height, node.corner_radius);
-                ctx.fill();
+    /// Fill and/or outline one run of text, outline first when `stroke_under` is set
+    fn draw_text_run(&self, ctx: &CanvasRenderingContext2d, node: &Node, text: &str, x: f64, y: f64, stroke_under: bool) {
+        for (pass, casts_shadow) in text_paint_passes(!node.fills.is_empty(), node.stroke.is_some(), stroke_under) {
+            if casts_shadow {
+                self.apply_shadow(ctx, node);
             } else {
-                ctx.fill_rect(node.x, node.y, node.width, node.height);
+                Self::clear_shadow(ctx);
+            }
+            match pass {
+                TextPass::Fill => {
+                    // Image layers have no glyph equivalent; text paints only color layers
+                    for fill in node.fills.iter().filter(|f| f.image.is_none()) {
+                        ctx.set_global_alpha(node.opacity * fill.opacity);
+                        ctx.set_fill_style_str(&fill.color.to_css());
+                        ctx.fill_text(text, x, y).ok();
+                        Self::clear_shadow(ctx);
+                    }
+                    ctx.set_global_alpha(node.opacity);
+                }
+                TextPass::Stroke => {
+                    ctx.stroke_text(text, x, y).ok();
+                }
             }
         }
+    }
+
+    fn render_frame(&self, ctx: &CanvasRenderingContext2d, node: &Node, scene: &Scene) {
+        if !node.fills.is_empty() {
+            self.draw_rounded_rect(ctx, node.x, node.y, node.width, node.height, node.corner_radius, node.corner_smoothing);
+            self.paint_fills(ctx, node, node.x, node.y);
+        }
         if let Some(stroke) = &node.stroke {
-            ctx.set_stroke_style_str(&stroke.color.to_css());
-            ctx.set_line_width(stroke.width);
+            self.apply_stroke_style(ctx, stroke);
             if node.corner_radius > 0.0 {
-                self.draw_rounded_rect(ctx, node.x, node.y, node.width, node.height, node.corner_radius);
+                self.draw_rounded_rect(ctx, node.x, node.y, node.width, node.height, node.corner_radius, node.corner_smoothing);
                 ctx.stroke();
             } else {
                 ctx.stroke_rect(node.x, node.y, node.width, node.height);
             }
         }
+        Self::clear_shadow(ctx);
         // Only show label if parent doesn't have layout (avoids clutter in nested layouts)
         let parent_has_layout = node.parent
             .and_then(|pid| scene.get_node(pid))
             .map(|p| p.layout.mode != crate::node::LayoutMode::None)
             .unwrap_or(false);
-        if !parent_has_layout {
+        if !parent_has_layout && !self.export_mode {
             let font_size = (11.0 / self.viewport.a).min(11.0);
             let gap = (4.0 / self.viewport.a).min(4.0);
             ctx.set_fill_style_str("rgba(255,255,255,0.5)");
@@ -304,22 +823,28 @@ impl Renderer {
             ctx.fill_text(&node.name, node.x, node.y - gap).ok();
         }
 
-        // Note indicator (small yellow dot + count)
-        if !node.notes.is_empty() {
-            let r = (5.0 / self.viewport.a).min(5.0);
-            let cx = node.x + node.width - r * 2.0;
-            let cy = node.y + r * 2.0;
-            ctx.begin_path();
-            ctx.arc(cx, cy, r, 0.0, std::f64::consts::PI * 2.0).ok();
-            ctx.set_fill_style_str("rgba(251, 191, 36, 0.9)");
-            ctx.fill();
-            if node.notes.len() > 1 {
-                let fs = (8.0 / self.viewport.a).min(8.0);
-                ctx.set_font(&format!("600 {}px Inter, system-ui, sans-serif", fs));
-                ctx.set_text_baseline("middle");
-                ctx.set_fill_style_str("#1a1a1a");
-                ctx.fill_text(&node.notes.len().to_string(), cx - fs * 0.25, cy).ok();
-            }
+        self.render_note_badge(ctx, node);
+    }
+
+    /// Note indicator: yellow dot with the unresolved thread count, gray once all are resolved
+    fn render_note_badge(&self, ctx: &CanvasRenderingContext2d, node: &Node) {
+        if node.notes.is_empty() || self.export_mode {
+            return;
+        }
+        let unresolved = node.notes.iter().filter(|n| !n.resolved).count();
+        let r = (5.0 / self.viewport.a).min(5.0);
+        let cx = node.x + node.width - r * 2.0;
+        let cy = node.y + r * 2.0;
+        ctx.begin_path();
+        ctx.arc(cx, cy, r, 0.0, std::f64::consts::PI * 2.0).ok();
+        ctx.set_fill_style_str(if unresolved > 0 { "rgba(251, 191, 36, 0.9)" } else { "rgba(156, 163, 175, 0.6)" });
+        ctx.fill();
+        if unresolved > 1 {
+            let fs = (8.0 / self.viewport.a).min(8.0);
+            ctx.set_font(&format!("600 {}px Inter, system-ui, sans-serif", fs));
+            ctx.set_text_baseline("middle");
+            ctx.set_fill_style_str("#1a1a1a");
+            ctx.fill_text(&unresolved.to_string(), cx - fs * 0.25, cy).ok();
         }
     }
 
@@ -344,28 +869,21 @@ impl Renderer {
 
     fn render_instance(&self, ctx: &CanvasRenderingContext2d, node: &Node, scene: &Scene) {
         // Render like a frame but with diamond badge
-        if let Some(fill) = &node.fill {
-            ctx.set_fill_style_str(&fill.color.to_css());
-            if node.corner_radius > 0.0 {
-                ctx.begin_path();
-                let r = node.corner_radius.min(node.width / 2.0).min(node.height / 2.0);
-                ctx.round_rect_with_f64(node.x, node.y, node.width, node.height, r).ok();
-                ctx.fill();
-            } else {
-                ctx.fill_rect(node.x, node.y, node.width, node.height);
-            }
+        if !node.fills.is_empty() {
+            self.draw_rounded_rect(ctx, node.x, node.y, node.width, node.height, node.corner_radius, node.corner_smoothing);
+            self.paint_fills(ctx, node, node.x, node.y);
         }
         if let Some(stroke) = &node.stroke {
-            ctx.set_stroke_style_str(&stroke.color.to_css());
-            ctx.set_line_width(stroke.width);
+            self.apply_stroke_style(ctx, stroke);
             ctx.stroke_rect(node.x, node.y, node.width, node.height);
         }
+        Self::clear_shadow(ctx);
         // Instance label (skip if parent has layout)
         let parent_has_layout = node.parent
             .and_then(|pid| scene.get_node(pid))
             .map(|p| p.layout.mode != crate::node::LayoutMode::None)
             .unwrap_or(false);
-        if !parent_has_layout {
+        if !parent_has_layout && !self.export_mode {
             let font_size = (11.0 / self.viewport.a).min(11.0);
             let gap = (4.0 / self.viewport.a).min(4.0);
             ctx.set_fill_style_str("rgba(16, 185, 129, 0.7)");
@@ -374,36 +892,67 @@ impl Renderer {
             ctx.fill_text(&node.name, node.x, node.y - gap).ok();
         }
 
-        if !node.notes.is_empty() {
-            let r = (5.0 / self.viewport.a).min(5.0);
-            let cx = node.x + node.width - r * 2.0;
-            let cy = node.y + r * 2.0;
-            ctx.begin_path();
-            ctx.arc(cx, cy, r, 0.0, std::f64::consts::PI * 2.0).ok();
-            ctx.set_fill_style_str("rgba(251, 191, 36, 0.9)");
-            ctx.fill();
-            if node.notes.len() > 1 {
-                let fs = (8.0 / self.viewport.a).min(8.0);
-                ctx.set_font(&format!("600 {}px Inter, system-ui, sans-serif", fs));
-                ctx.set_text_baseline("middle");
-                ctx.set_fill_style_str("#1a1a1a");
-                ctx.fill_text(&node.notes.len().to_string(), cx - fs * 0.25, cy).ok();
-            }
+        self.render_note_badge(ctx, node);
+    }
+
+    /// Outline a selected node. Handles are omitted (and the outline kept subtle) when
+    /// the node is part of a multi-selection, which gets shared handles instead.
+    /// Outline (and handles) drawn in the node's rotated frame, matching `node_handle_positions`
+    fn render_selection(&self, ctx: &CanvasRenderingContext2d, node: &Node, show_handles: bool) {
+        ctx.save();
+        if node.rotation != 0.0 {
+            let (px, py) = node.pivot();
+            ctx.translate(px, py).ok();
+            ctx.rotate(node.rotation).ok();
+            ctx.translate(-px, -py).ok();
         }
+        if show_handles {
+            ctx.set_stroke_style_str(&Color::blue().to_css());
+            ctx.set_line_width(1.5 / self.viewport.a);
+            ctx.stroke_rect(node.x, node.y, node.width, node.height);
+            self.render_handles(ctx, node.x, node.y, node.width, node.height);
+            self.render_rotation_handle(ctx, node);
+        } else {
+            ctx.set_stroke_style_str("rgba(59,130,246,0.5)");
+            ctx.set_line_width(1.0 / self.viewport.a);
+            ctx.stroke_rect(node.x, node.y, node.width, node.height);
+        }
+        ctx.restore();
     }
 
-    fn render_selection(&self, ctx: &CanvasRenderingContext2d, node: &Node) {
-        let sel_color = Color::blue().to_css();
-        ctx.set_stroke_style_str(&sel_color);
+    /// Dashed box around the whole multi-selection with shared resize handles
+    fn render_selection_bounds(&self, ctx: &CanvasRenderingContext2d, b: Rect) {
+        ctx.set_stroke_style_str(&Color::blue().to_css());
         ctx.set_line_width(1.5 / self.viewport.a);
-        ctx.stroke_rect(node.x, node.y, node.width, node.height);
+        let dash = 4.0 / self.viewport.a;
+        ctx.set_line_dash(&js_sys::Array::of2(&JsValue::from(dash), &JsValue::from(dash))).ok();
+        ctx.stroke_rect(b.x, b.y, b.width, b.height);
+        ctx.set_line_dash(&js_sys::Array::new()).ok();
+        self.render_handles(ctx, b.x, b.y, b.width, b.height);
+    }
 
+    /// Knob on a stem above the top-center edge; the context is already in the node's rotated frame
+    fn render_rotation_handle(&self, ctx: &CanvasRenderingContext2d, node: &Node) {
+        let cx = node.x + node.width / 2.0;
+        let knob_y = node.y - ROTATION_HANDLE_GAP / self.viewport.a;
+        ctx.begin_path();
+        ctx.move_to(cx, node.y);
+        ctx.line_to(cx, knob_y);
+        ctx.stroke();
+        ctx.begin_path();
+        ctx.arc(cx, knob_y, 4.0 / self.viewport.a, 0.0, std::f64::consts::TAU).ok();
+        ctx.set_fill_style_str("white");
+        ctx.fill();
+        ctx.stroke();
+    }
+
+    fn render_handles(&self, ctx: &CanvasRenderingContext2d, x: f64, y: f64, w: f64, h: f64) {
         let hs = 6.0 / self.viewport.a;
         let handles = [
-            (node.x, node.y),
-            (node.x + node.width, node.y),
-            (node.x, node.y + node.height),
-            (node.x + node.width, node.y + node.height),
+            (x, y),
+            (x + w, y),
+            (x, y + h),
+            (x + w, y + h),
         ];
         ctx.set_fill_style_str("white");
         for (hx, hy) in handles {
@@ -412,8 +961,15 @@ impl Renderer {
         }
     }
 
-    fn draw_rounded_rect(&self, ctx: &CanvasRenderingContext2d, x: f64, y: f64, w: f64, h: f64, r: f64) {
+    /// Rounded rectangle path. With `smoothing` > 0 each corner becomes a continuous-curvature
+    /// (squircle) bezier that starts further along the edge; at 0 it is a circular arc.
+    #[allow(clippy::too_many_arguments)]
+    fn draw_rounded_rect(&self, ctx: &CanvasRenderingContext2d, x: f64, y: f64, w: f64, h: f64, r: f64, smoothing: f64) {
         let r = r.min(w / 2.0).min(h / 2.0);
+        if smoothing > 0.0 && r > 0.0 {
+            Self::draw_smooth_rect(ctx, x, y, w, h, r, smoothing.min(1.0));
+            return;
+        }
         ctx.begin_path();
         ctx.move_to(x + r, y);
         ctx.line_to(x + w - r, y);
@@ -427,23 +983,134 @@ impl Renderer {
         ctx.close_path();
     }
 
-    fn apply_fill_stroke(&self, ctx: &CanvasRenderingContext2d, node: &Node) {
-        if let Some(fill) = &node.fill {
-            ctx.set_fill_style_str(&fill.color.to_css());
-            ctx.fill();
-        }
+    fn draw_smooth_rect(ctx: &CanvasRenderingContext2d, x: f64, y: f64, w: f64, h: f64, r: f64, smoothing: f64) {
+        // The curve extends up to (1 + smoothing) * r from the corner, limited by the half side
+        let d = (r * (1.0 + smoothing)).min(w / 2.0).min(h / 2.0);
+        // Circle-approximating handles sit (1 - KAPPA) * r from the corner; smoothing pulls them in
+        let c = d * (1.0 - CIRCLE_KAPPA) * (1.0 - 0.6 * smoothing);
+        let (right, bottom) = (x + w, y + h);
+        ctx.begin_path();
+        ctx.move_to(x + d, y);
+        ctx.line_to(right - d, y);
+        ctx.bezier_curve_to(right - c, y, right, y + c, right, y + d);
+        ctx.line_to(right, bottom - d);
+        ctx.bezier_curve_to(right, bottom - c, right - c, bottom, right - d, bottom);
+        ctx.line_to(x + d, bottom);
+        ctx.bezier_curve_to(x + c, bottom, x, bottom - c, x, bottom - d);
+        ctx.line_to(x, y + d);
+        ctx.bezier_curve_to(x, y + c, x + c, y, x + d, y);
+        ctx.close_path();
+    }
+
+    /// Fill and stroke the current path. (x, y) is the node's top-left in the current
+    /// coordinate space, used to place image fills.
+    fn apply_fill_stroke(&self, ctx: &CanvasRenderingContext2d, node: &Node, x: f64, y: f64) {
+        self.paint_fills(ctx, node, x, y);
         if let Some(stroke) = &node.stroke {
-            ctx.set_stroke_style_str(&stroke.color.to_css());
-            ctx.set_line_width(stroke.width);
+            self.apply_stroke_style(ctx, stroke);
             ctx.stroke();
         }
     }
 
+    /// Use a gradient as the fill style, mapping its normalized geometry onto the node's box
+    /// drawn at (x, y) — the local origin inside any rotation already applied to `ctx`
+    fn set_gradient_fill(&self, ctx: &CanvasRenderingContext2d, node: &Node, gradient: &Gradient, x: f64, y: f64) {
+        match gradient {
+            Gradient::Radial { stops, .. } => {
+                let (cx, cy, r) = gradient.radial_circle(x, y, node.width, node.height);
+                let Ok(g) = ctx.create_radial_gradient(cx, cy, 0.0, cx, cy, r) else { return };
+                for stop in stops {
+                    g.add_color_stop(stop.offset.clamp(0.0, 1.0) as f32, &stop.color.to_css()).ok();
+                }
+                ctx.set_fill_style_canvas_gradient(&g);
+            }
+        }
+    }
+
+    /// Paint each fill layer into the current path, bottom to top, with the layer's own
+    /// opacity on top of the node's. Only the bottom layer casts the node's shadow.
+    fn paint_fills(&self, ctx: &CanvasRenderingContext2d, node: &Node, x: f64, y: f64) {
+        for fill in &node.fills {
+            ctx.set_global_alpha(node.opacity * fill.opacity);
+            match &fill.image {
+                Some(src) => {
+                    ctx.save();
+                    ctx.clip();
+                    ctx.set_image_smoothing_enabled(self.image_smoothing);
+                    match self.image(src) {
+                        Some(img) => {
+                            ctx.draw_image_with_html_image_element_and_dw_and_dh(&img, x, y, node.width, node.height).ok();
+                        }
+                        None => {
+                            // Not decoded yet: gray placeholder until a later render
+                            ctx.set_fill_style_str("rgba(128,128,128,0.3)");
+                            ctx.fill_rect(x, y, node.width, node.height);
+                        }
+                    }
+                    ctx.restore();
+                }
+                None => {
+                    match &fill.gradient {
+                        Some(gradient) => self.set_gradient_fill(ctx, node, gradient, x, y),
+                        None => ctx.set_fill_style_str(&fill.color.to_css()),
+                    }
+                    ctx.fill();
+                }
+            }
+            // Upper layers and the stroke sit on the fill, so only the first paint casts the shadow
+            Self::clear_shadow(ctx);
+        }
+        ctx.set_global_alpha(node.opacity);
+    }
+
+    /// Configure the canvas shadow from the node's first drop shadow (canvas supports one).
+    /// Shadow offsets and blur are in device pixels, so they're scaled by the zoom.
+    fn apply_shadow(&self, ctx: &CanvasRenderingContext2d, node: &Node) {
+        let zoom = self.viewport.a;
+        match node.effects.first() {
+            Some(Effect::DropShadow { color, offset_x, offset_y, blur }) => {
+                ctx.set_shadow_color(&color.to_css());
+                ctx.set_shadow_offset_x(offset_x * zoom);
+                ctx.set_shadow_offset_y(offset_y * zoom);
+                ctx.set_shadow_blur(blur * zoom);
+            }
+            None => Self::clear_shadow(ctx),
+        }
+    }
+
+    fn clear_shadow(ctx: &CanvasRenderingContext2d) {
+        ctx.set_shadow_color("transparent");
+    }
+
+    /// Set stroke color, width, and dash pattern on the context
+    fn apply_stroke_style(&self, ctx: &CanvasRenderingContext2d, stroke: &Stroke) {
+        ctx.set_stroke_style_str(&stroke.color.to_css());
+        ctx.set_line_width(stroke.width);
+        if !stroke.dash.is_empty() {
+            let dash = js_sys::Array::new();
+            for &d in &stroke.dash {
+                dash.push(&JsValue::from(d));
+            }
+            ctx.set_line_dash(&dash).ok();
+        }
+    }
+
     fn draw_grid(&self, ctx: &CanvasRenderingContext2d) {
         let zoom = self.viewport.a;
         if zoom < 0.3 { return; }
 
-        let step = if zoom > 2.0 { 10.0 } else { 50.0 };
+        let step = match self.grid_size {
+            // Skip to a multiple of the snap size while its lines would be too dense to see
+            Some(size) => {
+                let mut step = size;
+                while step * zoom < 8.0 {
+                    step *= 2.0;
+                }
+                step
+            }
+            None if zoom > 2.0 => 10.0,
+            None => 50.0,
+        };
         let offset_x = self.viewport.tx % (step * zoom);
         let offset_y = self.viewport.ty % (step * zoom);
 
@@ -466,6 +1133,35 @@ impl Renderer {
         ctx.stroke();
     }
 
+    /// Guides span the whole canvas; drawn in screen space so they stay 1px at any zoom
+    fn draw_guides(&self, ctx: &CanvasRenderingContext2d, scene: &Scene) {
+        if scene.guides.is_empty() || self.export_mode {
+            return;
+        }
+        ctx.save();
+        ctx.reset_transform().ok();
+        ctx.set_stroke_style_str("rgba(255,64,129,0.8)");
+        ctx.set_line_width(1.0);
+        ctx.begin_path();
+        for guide in &scene.guides {
+            let p = self.viewport.apply(Point { x: guide.position, y: guide.position });
+            match guide.axis {
+                Axis::X => {
+                    let x = p.x.round() + 0.5;
+                    ctx.move_to(x, 0.0);
+                    ctx.line_to(x, self.canvas_height);
+                }
+                Axis::Y => {
+                    let y = p.y.round() + 0.5;
+                    ctx.move_to(0.0, y);
+                    ctx.line_to(self.canvas_width, y);
+                }
+            }
+        }
+        ctx.stroke();
+        ctx.restore();
+    }
+
     pub fn screen_to_scene(&self, x: f64, y: f64) -> (f64, f64) {
         if let Some(inv) = self.viewport.inverse() {
             let p = inv.apply(crate::types::Point { x, y });
@@ -486,8 +1182,125 @@ impl Renderer {
         self.viewport.d = new_zoom;
     }
 
+    /// Zoom (within 0.1–10) and pan so the scene-space `rect` fits the canvas, centered,
+    /// leaving `padding` screen px on every side
+    pub fn fit_rect(&mut self, rect: Rect, padding: f64) {
+        let avail_w = (self.canvas_width - padding * 2.0).max(1.0);
+        let avail_h = (self.canvas_height - padding * 2.0).max(1.0);
+        let zoom = (avail_w / rect.width.max(1e-6)).min(avail_h / rect.height.max(1e-6)).clamp(0.1, 10.0);
+        self.viewport.a = zoom;
+        self.viewport.d = zoom;
+        self.viewport.tx = self.canvas_width / 2.0 - (rect.x + rect.width / 2.0) * zoom;
+        self.viewport.ty = self.canvas_height / 2.0 - (rect.y + rect.height / 2.0) * zoom;
+    }
+
     pub fn pan(&mut self, dx: f64, dy: f64) {
         self.viewport.tx += dx;
         self.viewport.ty += dy;
     }
+
+    pub fn set_pan_limit(&mut self, enabled: bool, margin: f64) {
+        self.pan_limit = if enabled { Some(margin.max(0.0)) } else { None };
+    }
+
+    /// Clamp the pan so part of `content` (scene space) stays within the canvas, inset by the
+    /// pan-limit margin. No-op when the limit is off or there is no content.
+    pub fn clamp_pan(&mut self, content: Option<Rect>) {
+        let (Some(margin), Some(c)) = (self.pan_limit, content) else {
+            return;
+        };
+        let zoom = self.viewport.a;
+        let clamp = |t: f64, start: f64, size: f64, canvas: f64| {
+            let min = margin - (start + size) * zoom;
+            let max = canvas - margin - start * zoom;
+            if min > max { (min + max) / 2.0 } else { t.clamp(min, max) }
+        };
+        self.viewport.tx = clamp(self.viewport.tx, c.x, c.width, self.canvas_width);
+        self.viewport.ty = clamp(self.viewport.ty, c.y, c.height, self.canvas_height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Fake monospace measure: 10px per grapheme cluster
+    fn measure(s: &str) -> f64 {
+        grapheme_clusters(s).len() as f64 * 10.0
+    }
+
+    #[test]
+    fn emoji_clusters_measure_and_wrap_whole() {
+        let text = "👍🏽👨‍👩‍👧🇰🇷e\u{301}";
+        assert_eq!(grapheme_clusters(text), vec!["👍🏽", "👨‍👩‍👧", "🇰🇷", "e\u{301}"]);
+        assert_eq!(measure(text), 40.0);
+        let lines = wrap_paragraph(text, 25.0, &mut measure);
+        assert_eq!(lines, vec!["👍🏽👨‍👩‍👧", "🇰🇷e\u{301}"]);
+        assert_eq!(wrap_paragraph("hi 👋 there", 50.0, &mut measure), vec!["hi 👋", "there"]);
+    }
+
+    #[test]
+    fn long_words_hard_break_to_width() {
+        assert_eq!(wrap_paragraph("ab abcdefgh cd", 40.0, &mut measure), vec!["ab", "abcd", "efgh", "cd"]);
+        assert_eq!(wrap_paragraph("one two", 70.0, &mut measure), vec!["one two"]);
+    }
+
+    #[test]
+    fn cjk_wraps_between_characters() {
+        assert_eq!(wrap_paragraph("你好世界再见", 30.0, &mut measure), vec!["你好世", "界再见"]);
+        assert_eq!(wrap_paragraph("日本語 text", 60.0, &mut measure), vec!["日本語", "text"]);
+    }
+
+    #[test]
+    fn rtl_line_starts_at_right_edge() {
+        let (node_x, node_w, line_w) = (100.0, 200.0, 60.0);
+        let x = line_x(&TextAlign::Left, &TextDirection::Rtl, node_x, node_w, line_w);
+        // The first (rightmost) glyph ends at the node's right edge
+        assert_eq!(x + line_w, node_x + node_w);
+        assert_eq!(line_x(&TextAlign::Right, &TextDirection::Rtl, node_x, node_w, line_w), node_x);
+        assert_eq!(line_x(&TextAlign::Left, &TextDirection::Ltr, node_x, node_w, line_w), node_x);
+        assert_eq!(line_x(&TextAlign::Center, &TextDirection::Rtl, node_x, node_w, line_w), 170.0);
+    }
+
+    #[test]
+    fn outlined_text_casts_one_shadow_per_run() {
+        for stroke_under in [false, true] {
+            let passes = text_paint_passes(true, true, stroke_under);
+            assert_eq!(passes.len(), 2);
+            assert_eq!(passes.iter().filter(|(_, shadow)| *shadow).count(), 1);
+            assert!(passes[0].1);
+        }
+        assert_eq!(text_paint_passes(true, true, true)[0].0, TextPass::Stroke);
+        assert_eq!(text_paint_passes(true, true, false)[0].0, TextPass::Fill);
+        assert_eq!(text_paint_passes(true, false, false), vec![(TextPass::Fill, true)]);
+    }
+
+    #[test]
+    fn wrapped_text_casts_one_shadow_per_line() {
+        let mut node = Node::new(1, NodeKind::Rect);
+        node.width = 100.0;
+        node.stroke = Some(Stroke { color: Color::from_hex("#000").unwrap(), width: 2.0, dash: vec![] });
+        let lines: Vec<TextLine> = wrap_paragraph("one two three four five six", node.width - 2.0, &mut measure)
+            .into_iter()
+            .map(|text| TextLine { text, marker: None, paragraph: 0 })
+            .collect();
+        assert!(lines.len() >= 3);
+        let metrics = LineMetrics { line_h: 20.0, baseline: 15.0, paragraph_gap: 0.0, zoom: 1.0 };
+        let runs = place_text_runs(&node, &lines, 0.0, &TextAlign::Left, &TextDirection::Ltr, &metrics, &mut measure);
+        assert_eq!(runs.len(), lines.len());
+        for (i, (text, _, y)) in runs.iter().enumerate() {
+            assert_eq!(text, &lines[i].text);
+            assert_eq!(*y, 1.0 + 15.0 + 20.0 * i as f64);
+        }
+        // Each line is painted by a fill and an outline pass, and only one of them casts the
+        // shadow, so the block gets exactly one shadow per line
+        for stroke_under in [false, true] {
+            let shadows: usize = runs.iter()
+                .map(|_| text_paint_passes(true, true, stroke_under))
+                .inspect(|passes| assert_eq!(passes.len(), 2))
+                .map(|passes| passes.iter().filter(|(_, shadow)| *shadow).count())
+                .sum();
+            assert_eq!(shadows, lines.len());
+        }
+    }
 }
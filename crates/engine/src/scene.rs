@@ -1,13 +1,43 @@
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use serde::{Serialize, Deserialize};
-use crate::node::{Node, NodeId};
-use crate::types::Point;
+use crate::node::{Node, NodeId, NodeKind};
+use crate::styles::StyleStore;
+use crate::types::{Point, Rect};
+
+/// Document format version written by `export`; older documents are migrated on import
+pub const SCENE_VERSION: u32 = 1;
 
 #[derive(Serialize, Deserialize)]
 pub struct SceneData {
+    /// Format version (0 for documents saved before versioning)
+    #[serde(default)]
+    pub version: u32,
     pub nodes: Vec<Node>,
     pub root_children: Vec<NodeId>,
     pub next_id: NodeId,
+    /// Named selection sets (owned by Engine, carried here for export/import)
+    #[serde(default)]
+    pub selection_sets: HashMap<String, Vec<NodeId>>,
+    #[serde(default)]
+    pub guides: Vec<Guide>,
+    /// Shared styles (owned by Engine, carried here for export/import)
+    #[serde(default)]
+    pub styles: StyleStore,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Axis {
+    X,
+    Y,
+}
+
+/// Ruler guide: a vertical line at x = position (`Axis::X`) or a horizontal one at y = position
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Guide {
+    pub axis: Axis,
+    pub position: f64,
 }
 
 pub struct Scene {
@@ -15,6 +45,135 @@ pub struct Scene {
     root_children: Vec<NodeId>,
     next_id: NodeId,
     pub selection: Vec<NodeId>,
+    pub guides: Vec<Guide>,
+    /// Let `hit_test` pick nodes that paint nothing (see `Node::is_invisible`)
+    pub hit_invisible: bool,
+    /// Hit boxes bucketed by area, built on the first query and patched as nodes change
+    index: RefCell<SpatialIndex>,
+}
+
+/// Side of a spatial index cell, in scene units
+const INDEX_CELL: f64 = 256.0;
+/// Nodes spanning more cells than this are kept in one list that every query checks
+const INDEX_MAX_CELLS: i64 = 256;
+
+/// What the index last saw of a node: enough to tell whether a write moved it, shifted its
+/// children's scroll offset, or changed stacking order
+#[derive(PartialEq)]
+struct IndexEntry {
+    hit_box: Rect,
+    /// Scroll offset applied to the node's children
+    child_offset: (f64, f64),
+    children: Vec<NodeId>,
+}
+
+/// Uniform grid over every node's hit box (rotation-aware bounds shifted by ancestor
+/// scrolling), with each node's render-order rank so results keep stacking order.
+/// Writes only mark nodes dirty; the next query re-checks those and moves their cells.
+#[derive(Default)]
+struct SpatialIndex {
+    built: bool,
+    cells: HashMap<(i64, i64), Vec<NodeId>>,
+    oversized: Vec<NodeId>,
+    entries: HashMap<NodeId, IndexEntry>,
+    /// Render-order rank, None after the stacking order changed
+    rank: Option<HashMap<NodeId, usize>>,
+    dirty: HashSet<NodeId>,
+    /// Full builds so far (tests check that edits don't trigger one)
+    #[cfg(test)]
+    builds: usize,
+}
+
+impl SpatialIndex {
+    fn cell_range(r: &Rect) -> Option<(i64, i64, i64, i64)> {
+        let cell = |v: f64| (v / INDEX_CELL).floor() as i64;
+        let range = (cell(r.x), cell(r.y), cell(r.x + r.width), cell(r.y + r.height));
+        let finite = r.x.is_finite() && r.y.is_finite() && r.width.is_finite() && r.height.is_finite();
+        let small = (range.2 - range.0 + 1) * (range.3 - range.1 + 1) <= INDEX_MAX_CELLS;
+        (finite && small).then_some(range)
+    }
+
+    fn add_cells(&mut self, id: NodeId, hit_box: &Rect) {
+        match Self::cell_range(hit_box) {
+            Some((x0, y0, x1, y1)) => {
+                for cx in x0..=x1 {
+                    for cy in y0..=y1 {
+                        self.cells.entry((cx, cy)).or_default().push(id);
+                    }
+                }
+            }
+            None => self.oversized.push(id),
+        }
+    }
+
+    fn remove_cells(&mut self, id: NodeId, hit_box: &Rect) {
+        match Self::cell_range(hit_box) {
+            Some((x0, y0, x1, y1)) => {
+                for cx in x0..=x1 {
+                    for cy in y0..=y1 {
+                        if let Some(cell) = self.cells.get_mut(&(cx, cy)) {
+                            cell.retain(|&c| c != id);
+                            if cell.is_empty() {
+                                self.cells.remove(&(cx, cy));
+                            }
+                        }
+                    }
+                }
+            }
+            None => self.oversized.retain(|&c| c != id),
+        }
+    }
+
+    /// Store `entry` for `id` (or drop it if None), moving cells only if the hit box changed.
+    /// Returns the children whose offset may have changed and need re-checking.
+    fn update(&mut self, id: NodeId, entry: Option<IndexEntry>) -> Vec<NodeId> {
+        let old = self.entries.remove(&id);
+        let old_box = old.as_ref().map(|e| e.hit_box);
+        let new_box = entry.as_ref().map(|e| e.hit_box);
+        if old_box != new_box {
+            if let Some(b) = old_box {
+                self.remove_cells(id, &b);
+            }
+            if let Some(b) = new_box {
+                self.add_cells(id, &b);
+            }
+        }
+        let old_children = old.as_ref().map(|e| (e.child_offset, &e.children));
+        let new_children = entry.as_ref().map(|e| (e.child_offset, &e.children));
+        let mut stale = vec![];
+        if old_children != new_children {
+            if old_children.map(|c| c.1) != new_children.map(|c| c.1) {
+                self.rank = None;
+            }
+            stale.extend(old.iter().chain(&entry).flat_map(|e| e.children.iter().copied()));
+        }
+        if let Some(entry) = entry {
+            self.entries.insert(id, entry);
+        }
+        stale
+    }
+
+    /// Ids whose hit box may overlap `area`, topmost (last rendered) first
+    fn query(&self, area: &Rect) -> Vec<NodeId> {
+        let Some(rank) = &self.rank else { return vec![] };
+        let mut ids: Vec<NodeId> = match Self::cell_range(area) {
+            Some((x0, y0, x1, y1)) => (x0..=x1)
+                .flat_map(|cx| (y0..=y1).map(move |cy| (cx, cy)))
+                .filter_map(|c| self.cells.get(&c))
+                .flatten()
+                .chain(&self.oversized)
+                .copied()
+                .collect(),
+            // The area itself is huge: every node is a candidate
+            None => self.entries.keys().copied().collect(),
+        };
+        // Nodes detached from the tree aren't rendered, so they have no rank and can't be hit
+        ids.retain(|id| rank.contains_key(id));
+        ids.sort_unstable_by_key(|id| std::cmp::Reverse(rank[id]));
+        ids.dedup();
+        ids.retain(|id| self.entries[id].hit_box.intersects(area));
+        ids
+    }
 }
 
 impl Scene {
@@ -24,14 +183,128 @@ impl Scene {
             root_children: vec![],
             next_id: 1,
             selection: vec![],
+            guides: vec![],
+            hit_invisible: false,
+            index: RefCell::default(),
+        }
+    }
+
+    /// Queue a node for re-checking by the next hit test or marquee query
+    fn touch_index(&mut self, id: NodeId) {
+        let index = self.index.get_mut();
+        if index.built {
+            index.dirty.insert(id);
         }
     }
 
+    /// Note that sibling order changed somewhere, so render-order ranks must be recomputed
+    fn restack_index(&mut self) {
+        self.index.get_mut().rank = None;
+    }
+
+    /// What the spatial index should hold for a node, or None if it's gone
+    fn index_entry(&self, id: NodeId) -> Option<IndexEntry> {
+        let node = self.nodes.get(&id)?;
+        let (ox, oy) = self.scroll_offset(id);
+        let b = node.rotated_bounds();
+        // Lines are hit a few px off their segment (see `Node::contains_point`)
+        let slop = if matches!(node.kind, NodeKind::Line { .. }) {
+            node.stroke.as_ref().map(|s| s.width / 2.0).unwrap_or(0.0).max(4.0)
+        } else {
+            0.0
+        };
+        let child_offset = if node.clip_content { (ox + node.scroll_x, oy + node.scroll_y) } else { (ox, oy) };
+        Some(IndexEntry {
+            hit_box: Rect {
+                x: b.x - ox - slop,
+                y: b.y - oy - slop,
+                width: b.width + slop * 2.0,
+                height: b.height + slop * 2.0,
+            },
+            child_offset,
+            children: node.children.clone(),
+        })
+    }
+
+    /// Run `f` against the spatial index, building it on first use and otherwise re-checking
+    /// only the nodes written since the last query
+    fn with_index<T>(&self, f: impl FnOnce(&SpatialIndex) -> T) -> T {
+        let mut index = self.index.borrow_mut();
+        if !index.built {
+            index.built = true;
+            #[cfg(test)]
+            {
+                index.builds += 1;
+            }
+            for id in self.render_order() {
+                let entry = self.index_entry(id);
+                index.update(id, entry);
+            }
+        } else if !index.dirty.is_empty() {
+            let mut queue: Vec<NodeId> = index.dirty.drain().collect();
+            let mut seen = HashSet::new();
+            while let Some(id) = queue.pop() {
+                if seen.insert(id) {
+                    let entry = self.index_entry(id);
+                    queue.extend(index.update(id, entry));
+                }
+            }
+        }
+        if index.rank.is_none() {
+            index.rank = Some(self.render_order().into_iter().enumerate().map(|(rank, id)| (id, rank)).collect());
+        }
+        f(&index)
+    }
+
+    /// Nodes whose hit box contains `point`, topmost first
+    fn hit_candidates(&self, point: Point) -> Vec<NodeId> {
+        self.with_index(|index| index.query(&Rect { x: point.x, y: point.y, width: 0.0, height: 0.0 }))
+    }
+
+    /// Take a fresh id from the scene's counter without adding a node (for template copies)
+    pub fn reserve_id(&mut self) -> NodeId {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+
+    /// True if a node other than `except` is called `name`
+    pub fn name_taken(&self, name: &str, except: NodeId) -> bool {
+        self.nodes.values().any(|n| n.id != except && n.name == name)
+    }
+
+    /// Name for a copy of `name` that no node uses yet (see `increment_name`)
+    pub fn copy_name(&self, name: &str) -> String {
+        let taken: HashSet<&str> = self.nodes.values().map(|n| n.name.as_str()).collect();
+        let mut candidate = increment_name(name);
+        while taken.contains(candidate.as_str()) {
+            candidate = increment_name(&candidate);
+        }
+        candidate
+    }
+
+    /// Add nodes whose ids were taken with `reserve_id`, keeping their parent/child links.
+    /// Parentless nodes go to the top level; ids already in the scene are skipped.
+    pub fn add_reserved_nodes(&mut self, nodes: &[Node]) {
+        for node in nodes {
+            if self.nodes.contains_key(&node.id) {
+                continue;
+            }
+            if node.parent.is_none() {
+                self.root_children.push(node.id);
+            }
+            self.touch_index(node.id);
+            self.nodes.insert(node.id, node.clone());
+        }
+        self.restack_index();
+    }
+
     pub fn add_node(&mut self, mut node: Node) -> NodeId {
         let id = self.next_id;
         self.next_id += 1;
         node.id = id;
         if let Some(parent_id) = node.parent {
+            self.touch_index(parent_id);
             if let Some(parent) = self.nodes.get_mut(&parent_id) {
                 parent.children.push(id);
             }
@@ -39,6 +312,8 @@ impl Scene {
             self.root_children.push(id);
         }
         self.nodes.insert(id, node);
+        self.touch_index(id);
+        self.restack_index();
         id
     }
 
@@ -47,13 +322,16 @@ impl Scene {
     }
 
     pub fn get_node_mut(&mut self, id: NodeId) -> Option<&mut Node> {
+        self.touch_index(id);
         self.nodes.get_mut(&id)
     }
 
     pub fn remove_node(&mut self, id: NodeId) {
+        self.touch_index(id);
         if let Some(node) = self.nodes.remove(&id) {
             self.root_children.retain(|&c| c != id);
             if let Some(parent_id) = node.parent {
+                self.touch_index(parent_id);
                 if let Some(parent) = self.nodes.get_mut(&parent_id) {
                     parent.children.retain(|&c| c != id);
                 }
@@ -61,10 +339,48 @@ impl Scene {
             for child_id in node.children {
                 self.remove_node(child_id);
             }
+            // A connector can't outlive either of its endpoints
+            let dangling: Vec<NodeId> = self.nodes.iter()
+                .filter(|(_, n)| matches!(n.kind, NodeKind::Connector { from, to, .. } if from == id || to == id))
+                .map(|(&cid, _)| cid)
+                .collect();
+            for cid in dangling {
+                self.remove_node(cid);
+            }
         }
         self.selection.retain(|&s| s != id);
     }
 
+    /// Position of a node among its siblings (parent's children or root_children)
+    pub fn sibling_index(&self, id: NodeId) -> Option<usize> {
+        let parent = self.nodes.get(&id)?.parent;
+        let siblings = match parent {
+            Some(pid) => &self.nodes.get(&pid)?.children,
+            None => &self.root_children,
+        };
+        siblings.iter().position(|&c| c == id)
+    }
+
+    /// Move a node to `index` among its siblings (clamped), changing its stacking order
+    pub fn move_to_index(&mut self, id: NodeId, index: usize) {
+        self.restack_index();
+        let parent = match self.nodes.get(&id) {
+            Some(n) => n.parent,
+            None => return,
+        };
+        let siblings = match parent {
+            Some(pid) => match self.nodes.get_mut(&pid) {
+                Some(p) => &mut p.children,
+                None => return,
+            },
+            None => &mut self.root_children,
+        };
+        if let Some(pos) = siblings.iter().position(|&c| c == id) {
+            siblings.remove(pos);
+            siblings.insert(index.min(siblings.len()), id);
+        }
+    }
+
     pub fn render_order(&self) -> Vec<NodeId> {
         let mut result = vec![];
         self.collect_render_order(&self.root_children, &mut result);
@@ -72,54 +388,365 @@ impl Scene {
     }
 
     fn collect_render_order(&self, ids: &[NodeId], result: &mut Vec<NodeId>) {
+        let mut visited = HashSet::new();
         for &id in ids {
-            result.push(id);
-            if let Some(node) = self.nodes.get(&id) {
-                self.collect_render_order(&node.children, result);
+            self.walk_subtree(id, &mut visited, &mut |node| result.push(node.id));
+        }
+    }
+
+    /// Pre-order walk of a node and its descendants. Ids already in `visited` are skipped,
+    /// so a corrupted tree with a child cycle terminates instead of recursing forever.
+    pub fn walk_subtree<F: FnMut(&Node)>(&self, id: NodeId, visited: &mut HashSet<NodeId>, f: &mut F) {
+        if !visited.insert(id) {
+            return;
+        }
+        if let Some(node) = self.nodes.get(&id) {
+            f(node);
+            for &child in &node.children {
+                self.walk_subtree(child, visited, f);
             }
         }
     }
 
+    /// Ids of a node and all its descendants, root first
+    pub fn subtree_ids(&self, id: NodeId) -> Vec<NodeId> {
+        let mut ids = vec![];
+        self.walk_subtree(id, &mut HashSet::new(), &mut |n| ids.push(n.id));
+        ids
+    }
+
+    /// Clones of a node and all its descendants, root first
+    pub fn subtree_nodes(&self, id: NodeId) -> Vec<Node> {
+        let mut nodes = vec![];
+        self.walk_subtree(id, &mut HashSet::new(), &mut |n| nodes.push(n.clone()));
+        nodes
+    }
+
+    /// Insert copied subtrees (each root first, as from `subtree_nodes`, possibly from another
+    /// scene) under `parent` with fresh ids, offset by (dx, dy). Parent/child links, connector
+    /// endpoints and instance bookkeeping are remapped to the new ids; connectors whose endpoints
+    /// aren't among the copied nodes are dropped. Returns the new root ids.
+    pub fn insert_subtrees(&mut self, subtrees: &[Vec<Node>], parent: Option<NodeId>, dx: f64, dy: f64) -> Vec<NodeId> {
+        self.restack_index();
+        let copied: HashSet<NodeId> = subtrees.iter().flatten().map(|n| n.id).collect();
+        let kept: Vec<&Node> = subtrees.iter().flatten()
+            .filter(|n| match n.kind {
+                NodeKind::Connector { from, to, .. } => copied.contains(&from) && copied.contains(&to),
+                _ => true,
+            })
+            .collect();
+        let mut ids: HashMap<NodeId, NodeId> = HashMap::new();
+        for node in &kept {
+            ids.insert(node.id, self.next_id);
+            self.next_id += 1;
+        }
+        let roots: HashSet<NodeId> = subtrees.iter().filter_map(|t| t.first()).map(|n| n.id).collect();
+        let remap = |id: NodeId| ids.get(&id).copied();
+        for node in kept {
+            let mut copy = node.clone();
+            copy.id = ids[&node.id];
+            copy.x += dx;
+            copy.y += dy;
+            copy.parent = if roots.contains(&node.id) { parent } else { node.parent.and_then(remap) };
+            copy.children = node.children.iter().filter_map(|&c| remap(c)).collect();
+            match &mut copy.kind {
+                NodeKind::Connector { from, to, .. } => {
+                    *from = ids[from];
+                    *to = ids[to];
+                }
+                NodeKind::Instance(data) => {
+                    data.template_map = data.template_map.iter().filter_map(|(&t, &s)| Some((t, remap(s)?))).collect();
+                    data.overrides = data.overrides.drain().filter_map(|(s, o)| Some((remap(s)?, o))).collect();
+                    for fills in data.slot_fills.values_mut() {
+                        *fills = fills.iter().filter_map(|&s| remap(s)).collect();
+                    }
+                }
+                _ => {}
+            }
+            self.touch_index(copy.id);
+            self.nodes.insert(copy.id, copy);
+        }
+        let new_roots: Vec<NodeId> = subtrees.iter()
+            .filter_map(|t| t.first())
+            .filter_map(|n| remap(n.id))
+            .collect();
+        if let Some(p) = parent {
+            self.touch_index(p);
+        }
+        for &root in &new_roots {
+            match parent.and_then(|p| self.nodes.get_mut(&p)) {
+                Some(p) => p.children.push(root),
+                None => self.root_children.push(root),
+            }
+        }
+        new_roots
+    }
+
     pub fn hit_test(&self, point: Point) -> Option<NodeId> {
-        let order = self.render_order();
-        for &id in order.iter().rev() {
+        self.hit_test_excluding(point, &[], false)
+    }
+
+    /// `hit_test` that skips the `exclude` ids, and with `descendants` everything inside them
+    /// too, so a dragged node doesn't find itself under the cursor
+    pub fn hit_test_excluding(&self, point: Point, exclude: &[NodeId], descendants: bool) -> Option<NodeId> {
+        for id in self.hit_candidates(point) {
             if let Some(node) = self.nodes.get(&id) {
                 if !node.visible || node.locked { continue; }
-                if node.bounds().contains(point) {
-                    return Some(id);
+                if exclude.contains(&id) || (descendants && self.has_ancestor_in(id, exclude)) { continue; }
+                if !self.hit_invisible && node.is_invisible() { continue; }
+                match self.to_content_point(id, point) {
+                    Some(p) if node.contains_point(p) => return Some(id),
+                    _ => {}
                 }
             }
         }
         None
     }
 
+    /// Topmost visible container (frame, instance, or group) containing `point`, ignoring
+    /// `exclude` and their descendants. Used to find where dragged nodes would be dropped.
+    pub fn frame_at_point(&self, point: Point, exclude: &[NodeId]) -> Option<NodeId> {
+        for id in self.hit_candidates(point) {
+            let node = match self.nodes.get(&id) {
+                Some(n) => n,
+                None => continue,
+            };
+            if !matches!(node.kind, NodeKind::Frame | NodeKind::Instance(_) | NodeKind::Group) {
+                continue;
+            }
+            if !node.visible || node.opacity <= 0.0 || exclude.contains(&id) || self.has_ancestor_in(id, exclude) {
+                continue;
+            }
+            match self.to_content_point(id, point) {
+                Some(p) if node.contains_point(p) => return Some(id),
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Topmost visible, unlocked nodes lying entirely inside `rect` (`contain`) or touching it
+    /// (marquee selection). Children of a node that is already included are skipped.
+    pub fn nodes_in_rect(&self, rect: Rect, contain: bool) -> Vec<NodeId> {
+        let mut result: Vec<NodeId> = vec![];
+        let mut candidates = self.with_index(|index| index.query(&rect));
+        candidates.reverse();
+        for id in candidates {
+            let node = match self.nodes.get(&id) {
+                Some(n) => n,
+                None => continue,
+            };
+            if !node.visible || self.is_locked(id) {
+                continue;
+            }
+            if node.parent.map(|p| result.contains(&p)).unwrap_or(false) {
+                continue;
+            }
+            let (ox, oy) = self.scroll_offset(id);
+            let b = node.rotated_bounds();
+            let b = Rect { x: b.x - ox, y: b.y - oy, ..b };
+            if (contain && rect.contains_rect(&b)) || (!contain && rect.intersects(&b)) {
+                result.push(id);
+            }
+        }
+        result
+    }
+
+    /// True if any ancestor of `id` is in `ids`
+    pub fn has_ancestor_in(&self, id: NodeId, ids: &[NodeId]) -> bool {
+        let mut current = self.nodes.get(&id).and_then(|n| n.parent);
+        while let Some(pid) = current {
+            if ids.contains(&pid) {
+                return true;
+            }
+            current = self.nodes.get(&pid).and_then(|n| n.parent);
+        }
+        false
+    }
+
+    /// Total scroll offset applied to a node by its clipping ancestors
+    pub fn scroll_offset(&self, id: NodeId) -> (f64, f64) {
+        let mut offset = (0.0, 0.0);
+        let mut current = self.nodes.get(&id).and_then(|n| n.parent);
+        while let Some(pid) = current {
+            let parent = match self.nodes.get(&pid) {
+                Some(p) => p,
+                None => break,
+            };
+            if parent.clip_content {
+                offset.0 += parent.scroll_x;
+                offset.1 += parent.scroll_y;
+            }
+            current = parent.parent;
+        }
+        offset
+    }
+
+    /// Map a scene point into a node's (unscrolled) coordinate space, or None if a
+    /// clipping ancestor hides that point
+    pub fn to_content_point(&self, id: NodeId, point: Point) -> Option<Point> {
+        let mut ancestors = vec![];
+        let mut current = self.nodes.get(&id).and_then(|n| n.parent);
+        while let Some(pid) = current {
+            let parent = self.nodes.get(&pid)?;
+            ancestors.push(parent);
+            current = parent.parent;
+        }
+        let mut p = point;
+        for ancestor in ancestors.iter().rev() {
+            if ancestor.clip_content {
+                if !ancestor.bounds().contains(p) {
+                    return None;
+                }
+                p = Point { x: p.x + ancestor.scroll_x, y: p.y + ancestor.scroll_y };
+            }
+        }
+        Some(p)
+    }
+
+    /// Scroll a clipping frame, clamped so content can't scroll past its far edge.
+    /// Returns false if the node doesn't clip its content.
+    pub fn scroll_frame(&mut self, id: NodeId, dx: f64, dy: f64) -> bool {
+        self.touch_index(id);
+        let (max_x, max_y) = {
+            let node = match self.nodes.get(&id) {
+                Some(n) if n.clip_content => n,
+                _ => return false,
+            };
+            let (right, bottom) = node.children.iter()
+                .filter_map(|cid| self.nodes.get(cid))
+                .filter(|c| c.visible)
+                .fold((node.x + node.width, node.y + node.height), |(r, b), c| {
+                    (r.max(c.x + c.width + node.layout.padding_right), b.max(c.y + c.height + node.layout.padding_bottom))
+                });
+            (right - node.x - node.width, bottom - node.y - node.height)
+        };
+        if let Some(node) = self.nodes.get_mut(&id) {
+            node.scroll_x = (node.scroll_x + dx).clamp(0.0, max_x);
+            node.scroll_y = (node.scroll_y + dy).clamp(0.0, max_y);
+        }
+        true
+    }
+
+    /// True if the node or any of its ancestors is locked
+    pub fn is_locked(&self, id: NodeId) -> bool {
+        let mut visited = HashSet::new();
+        let mut current = Some(id);
+        while let Some(nid) = current.filter(|&n| visited.insert(n)) {
+            match self.nodes.get(&nid) {
+                Some(n) if n.locked => return true,
+                Some(n) => current = n.parent,
+                None => break,
+            }
+        }
+        false
+    }
+
     pub fn move_node(&mut self, id: NodeId, dx: f64, dy: f64) {
+        self.touch_index(id);
         if let Some(node) = self.nodes.get_mut(&id) {
             node.x += dx;
             node.y += dy;
         }
     }
 
+    /// Move a node together with all of its descendants
+    pub fn translate_subtree(&mut self, id: NodeId, dx: f64, dy: f64) {
+        for id in self.subtree_ids(id) {
+            self.touch_index(id);
+            if let Some(node) = self.nodes.get_mut(&id) {
+                node.x += dx;
+                node.y += dy;
+            }
+        }
+    }
+
+    /// Orbit a node and its descendants around (px, py) by `delta` radians, turning each by the same amount
+    pub fn rotate_subtree(&mut self, id: NodeId, px: f64, py: f64, delta: f64) {
+        let (sin, cos) = delta.sin_cos();
+        for id in self.subtree_ids(id) {
+            self.touch_index(id);
+            if let Some(node) = self.nodes.get_mut(&id) {
+                let (cx, cy) = node.pivot();
+                let (dx, dy) = (cx - px, cy - py);
+                node.x += px + dx * cos - dy * sin - cx;
+                node.y += py + dx * sin + dy * cos - cy;
+                node.rotation += delta;
+            }
+        }
+    }
+
+    /// Map a node and its descendants from box `from` onto box `to`, scaling positions and sizes
+    pub fn scale_subtree(&mut self, id: NodeId, from: Rect, to: Rect) {
+        let sx = if from.width > 0.0 { to.width / from.width } else { 1.0 };
+        let sy = if from.height > 0.0 { to.height / from.height } else { 1.0 };
+        for id in self.subtree_ids(id) {
+            self.touch_index(id);
+            if let Some(node) = self.nodes.get_mut(&id) {
+                node.x = to.x + (node.x - from.x) * sx;
+                node.y = to.y + (node.y - from.y) * sy;
+                node.width = (node.width * sx).max(1.0);
+                node.height = (node.height * sy).max(1.0);
+            }
+        }
+    }
+
     pub fn resize_node(&mut self, id: NodeId, width: f64, height: f64) {
+        self.touch_index(id);
         if let Some(node) = self.nodes.get_mut(&id) {
-            node.width = width.max(1.0);
-            node.height = height.max(1.0);
+            node.width = node.clamp_width(width).max(1.0);
+            node.height = node.clamp_height(height).max(1.0);
         }
     }
 
+    /// Union of the rotation-aware bounds of a node and its visible descendants; None if the
+    /// node is missing or hidden
+    pub fn subtree_bounds(&self, id: NodeId) -> Option<Rect> {
+        self.visible_bounds(id, &mut HashSet::new())
+    }
+
+    fn visible_bounds(&self, id: NodeId, visited: &mut HashSet<NodeId>) -> Option<Rect> {
+        let node = self.nodes.get(&id).filter(|n| n.visible && visited.insert(id))?;
+        let bounds = node.children.iter()
+            .filter_map(|&child| self.visible_bounds(child, visited))
+            .fold(node.rotated_bounds(), |acc, b| acc.union(&b));
+        Some(bounds)
+    }
+
+    /// Union of `subtree_bounds` over the top-level nodes (children of hidden nodes don't
+    /// count), or None for an empty scene
+    pub fn content_bounds(&self) -> Option<Rect> {
+        self.root_children.iter()
+            .filter_map(|&id| self.subtree_bounds(id))
+            .reduce(|a, b| a.union(&b))
+    }
+
+    /// Union of the rotation-aware bounds of every selected node
+    pub fn selection_bounds(&self) -> Option<Rect> {
+        self.selection.iter()
+            .filter_map(|&id| self.nodes.get(&id))
+            .map(|n| n.rotated_bounds())
+            .reduce(|a, b| a.union(&b))
+    }
+
     pub fn node_count(&self) -> usize {
         self.nodes.len()
     }
 
     pub fn export(&self) -> SceneData {
         SceneData {
+            version: SCENE_VERSION,
             nodes: self.nodes.values().cloned().collect(),
             root_children: self.root_children.clone(),
             next_id: self.next_id,
+            selection_sets: HashMap::new(),
+            guides: self.guides.clone(),
+            styles: StyleStore::default(),
         }
     }
 
-    pub fn import(data: SceneData) -> Self {
+    pub fn import(mut data: SceneData) -> Self {
+        migrate(&mut data);
         let mut nodes = HashMap::new();
         for node in data.nodes {
             nodes.insert(node.id, node);
@@ -129,6 +756,9 @@ impl Scene {
             root_children: data.root_children,
             next_id: data.next_id,
             selection: vec![],
+            guides: data.guides,
+            hit_invisible: false,
+            index: RefCell::default(),
         }
     }
 
@@ -140,14 +770,21 @@ impl Scene {
         }
     }
 
+    pub fn root_children(&self) -> &[NodeId] {
+        &self.root_children
+    }
+
     pub fn all_node_ids(&self) -> Vec<NodeId> {
         self.render_order()
     }
 
     pub fn reparent(&mut self, node_id: NodeId, new_parent: Option<NodeId>) {
+        self.touch_index(node_id);
+        self.restack_index();
         // Remove from old parent
         if let Some(node) = self.nodes.get(&node_id) {
             if let Some(old_parent) = node.parent {
+                self.touch_index(old_parent);
                 if let Some(p) = self.nodes.get_mut(&old_parent) {
                     p.children.retain(|&c| c != node_id);
                 }
@@ -157,6 +794,7 @@ impl Scene {
         }
         // Add to new parent
         if let Some(pid) = new_parent {
+            self.touch_index(pid);
             if let Some(p) = self.nodes.get_mut(&pid) {
                 p.children.push(node_id);
             }
@@ -171,3 +809,206 @@ impl Scene {
         }
     }
 }
+
+/// Upgrade an older document to `SCENE_VERSION`, one version step at a time
+fn migrate(data: &mut SceneData) {
+    if data.version < 1 {
+        // v0 documents could store a single `fill` instead of the `fills` layer list
+        for node in &mut data.nodes {
+            if let Some(fill) = node.legacy_fill.take() {
+                if node.fills.is_empty() {
+                    node.fills.push(fill);
+                }
+            }
+        }
+    }
+    data.version = SCENE_VERSION;
+}
+
+/// Bump a trailing number ("Rect 3" → "Rect 4"), or append one ("Button copy" → "Button copy 2")
+fn increment_name(name: &str) -> String {
+    if let Some((base, n)) = name.rsplit_once(' ') {
+        if !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit()) {
+            if let Ok(n) = n.parse::<u64>() {
+                return format!("{} {}", base, n + 1);
+            }
+        }
+    }
+    format!("{} 2", name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::NodeKind;
+
+    #[test]
+    fn child_cycle_does_not_hang_traversal() {
+        let mut a = Node::new(1, NodeKind::Frame);
+        let mut b = Node::new(2, NodeKind::Frame);
+        a.children = vec![2];
+        b.parent = Some(1);
+        b.children = vec![1];
+        let scene = Scene::import(SceneData {
+            version: SCENE_VERSION,
+            nodes: vec![a, b],
+            root_children: vec![1],
+            next_id: 3,
+            selection_sets: HashMap::new(),
+            guides: vec![],
+            styles: StyleStore::default(),
+        });
+        assert_eq!(scene.render_order(), vec![1, 2]);
+
+        let mut scene = scene;
+        scene.translate_subtree(1, 5.0, 0.0);
+        scene.rotate_subtree(1, 0.0, 0.0, 0.5);
+        scene.scale_subtree(1, Rect { x: 0.0, y: 0.0, width: 10.0, height: 10.0 }, Rect { x: 0.0, y: 0.0, width: 20.0, height: 20.0 });
+        crate::layout::apply_constraints(&mut scene, 1, Rect { x: 0.0, y: 0.0, width: 1.0, height: 1.0 });
+        assert!(scene.subtree_bounds(1).is_some());
+        assert_eq!(crate::svg::export_svg(&scene, &[1], Default::default()).matches("<g ").count(), 2);
+        let jsx = crate::jsx::export_jsx(&scene, &crate::component::ComponentStore::new(), 1);
+        assert_eq!(jsx.matches("<div").count(), 2);
+    }
+
+    #[test]
+    fn hit_test_follows_45_degree_rotation() {
+        let mut scene = Scene::new();
+        let mut node = Node::new(0, NodeKind::Rect);
+        node.width = 100.0;
+        node.height = 100.0;
+        node.rotation = std::f64::consts::FRAC_PI_4;
+        let id = scene.add_node(node);
+
+        // Corners of the unrotated box are now outside the diamond
+        assert_eq!(scene.hit_test(Point { x: 5.0, y: 5.0 }), None);
+        assert_eq!(scene.hit_test(Point { x: 95.0, y: 95.0 }), None);
+        // The diamond's tips reach past the original box
+        assert_eq!(scene.hit_test(Point { x: 50.0, y: -15.0 }), Some(id));
+        assert_eq!(scene.hit_test(Point { x: 115.0, y: 50.0 }), Some(id));
+        assert_eq!(scene.hit_test(Point { x: 50.0, y: 50.0 }), Some(id));
+    }
+
+    #[test]
+    fn hit_test_ellipse_ignores_box_corners() {
+        let mut scene = Scene::new();
+        let mut node = Node::new(0, NodeKind::Ellipse);
+        node.width = 100.0;
+        node.height = 50.0;
+        let id = scene.add_node(node);
+        assert_eq!(scene.hit_test(Point { x: 3.0, y: 3.0 }), None);
+        assert_eq!(scene.hit_test(Point { x: 95.0, y: 25.0 }), Some(id));
+    }
+
+    #[test]
+    fn copy_names_increment_numeric_suffix() {
+        assert_eq!(increment_name("Rect 3"), "Rect 4");
+        assert_eq!(increment_name("Button copy"), "Button copy 2");
+        assert_eq!(increment_name("Rect"), "Rect 2");
+        assert_eq!(increment_name("Layer 2b"), "Layer 2b 2");
+
+        let mut scene = Scene::new();
+        for name in ["Rect 3", "Rect 4"] {
+            let mut node = Node::new(0, NodeKind::Rect);
+            node.name = name.to_string();
+            scene.add_node(node);
+        }
+        assert_eq!(scene.copy_name("Rect 3"), "Rect 5");
+    }
+
+    #[test]
+    fn content_bounds_skip_children_of_hidden_nodes() {
+        let mut scene = Scene::new();
+        let mut frame = Node::new(0, NodeKind::Frame);
+        frame.x = 500.0;
+        frame.width = 100.0;
+        frame.height = 100.0;
+        frame.visible = false;
+        let frame = scene.add_node(frame);
+        let mut child = Node::new(0, NodeKind::Rect);
+        child.x = 520.0;
+        child.width = 10.0;
+        child.height = 10.0;
+        child.parent = Some(frame);
+        scene.add_node(child);
+        let mut rect = Node::new(0, NodeKind::Rect);
+        rect.width = 50.0;
+        rect.height = 40.0;
+        scene.add_node(rect);
+
+        let b = scene.content_bounds().unwrap();
+        assert_eq!((b.x, b.y, b.width, b.height), (0.0, 0.0, 50.0, 40.0));
+    }
+
+    #[test]
+    fn spatial_index_limits_hit_test_candidates() {
+        let mut scene = Scene::new();
+        let mut ids = vec![];
+        for i in 0..10_000 {
+            let mut node = Node::new(0, NodeKind::Rect);
+            node.x = (i % 100) as f64 * 50.0;
+            node.y = (i / 100) as f64 * 50.0;
+            node.width = 40.0;
+            node.height = 40.0;
+            ids.push(scene.add_node(node));
+        }
+        let p = Point { x: 2520.0, y: 2520.0 };
+        assert!(scene.hit_candidates(p).len() < 100);
+        assert_eq!(scene.hit_test(p), Some(ids[50 * 100 + 50]));
+        assert_eq!(scene.hit_test(Point { x: 2545.0, y: 2520.0 }), None);
+
+        scene.move_node(ids[0], 10_000.0, 10_000.0);
+        assert_eq!(scene.hit_test(Point { x: 10_020.0, y: 10_020.0 }), Some(ids[0]));
+        let marquee = Rect { x: 0.0, y: 0.0, width: 95.0, height: 95.0 };
+        assert_eq!(scene.nodes_in_rect(marquee, true), vec![ids[1], ids[100], ids[101]]);
+    }
+
+    #[test]
+    fn layout_passes_patch_the_index_without_rebuilding() {
+        use crate::layout::compute_layouts;
+        use crate::node::LayoutMode;
+        let mut scene = Scene::new();
+        let mut frame = Node::new(0, NodeKind::Frame);
+        frame.width = 300.0;
+        frame.height = 50.0;
+        frame.layout.mode = LayoutMode::Flex;
+        frame.clip_content = true;
+        let frame = scene.add_node(frame);
+        let mut kids = vec![];
+        for _ in 0..2 {
+            let mut child = Node::new(0, NodeKind::Rect);
+            child.width = 200.0;
+            child.height = 50.0;
+            child.parent = Some(frame);
+            kids.push(scene.add_node(child));
+        }
+        let mut far = Node::new(0, NodeKind::Rect);
+        far.x = 1000.0;
+        far.width = 50.0;
+        far.height = 50.0;
+        let far = scene.add_node(far);
+        let link = scene.add_node(Node::new(0, NodeKind::Connector { from: frame, to: far, kind: Default::default(), points: vec![] }));
+        compute_layouts(&mut scene);
+
+        let first = scene.get_node(kids[1]).unwrap().x + 10.0;
+        assert_eq!(scene.hit_test(Point { x: first, y: 10.0 }), Some(kids[1]));
+        for _ in 0..3 {
+            compute_layouts(&mut scene);
+            assert_eq!(scene.hit_test(Point { x: 600.0, y: 25.0 }), Some(link));
+            assert!(scene.index.borrow().dirty.is_empty());
+        }
+        assert_eq!(scene.index.borrow().builds, 1);
+
+        // Real edits are picked up in place: a moved endpoint re-routes the connector, and
+        // scrolling the frame shifts its children's hit boxes
+        scene.move_node(far, 0.0, 500.0);
+        compute_layouts(&mut scene);
+        assert_eq!(scene.hit_test(Point { x: 600.0, y: 25.0 }), None);
+        scene.scroll_frame(frame, 100.0, 0.0);
+        assert_eq!(scene.hit_test(Point { x: first - 100.0, y: 10.0 }), Some(kids[1]));
+        scene.move_to_index(kids[1], 0);
+        scene.remove_node(kids[1]);
+        assert_eq!(scene.hit_test(Point { x: first - 100.0, y: 10.0 }), Some(frame));
+        assert_eq!(scene.index.borrow().builds, 1);
+    }
+}
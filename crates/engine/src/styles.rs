@@ -0,0 +1,63 @@
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+use crate::css::{class_name, px};
+use crate::types::Color;
+
+/// Shared typography applied to text nodes by name
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TextStyle {
+    pub font_family: String,
+    pub font_size: f64,
+    #[serde(default = "default_font_weight")]
+    pub font_weight: u16,
+    #[serde(default = "default_line_height")]
+    pub line_height: f64,
+}
+
+fn default_font_weight() -> u16 { 400 }
+fn default_line_height() -> f64 { 1.2 }
+
+/// Named color and text styles shared across the document (owned by Engine, carried in SceneData)
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct StyleStore {
+    #[serde(default)]
+    pub colors: BTreeMap<String, Color>,
+    #[serde(default)]
+    pub texts: BTreeMap<String, TextStyle>,
+}
+
+/// CSS custom property name for a style ("Brand / Primary" → "--brand-primary")
+pub fn var_name(style: &str) -> String {
+    format!("--{}", class_name(style))
+}
+
+/// `var(--name)` reference to a style's custom property, with an optional suffix ("-font-size")
+pub fn var_ref(style: &str, suffix: &str) -> String {
+    format!("var({}{})", var_name(style), suffix)
+}
+
+/// A color style as seen through a fill layer's opacity: the plain `var()` when opaque,
+/// otherwise mixed toward transparent so the layer opacity isn't lost
+pub fn fill_var_ref(style: &str, opacity: f64) -> String {
+    if opacity >= 1.0 {
+        return var_ref(style, "");
+    }
+    format!("color-mix(in srgb, {} {}%, transparent)", var_ref(style, ""), (opacity.max(0.0) * 1000.0).round() / 10.0)
+}
+
+/// Every style as custom properties on `:root`
+pub fn export_css_vars(styles: &StyleStore) -> String {
+    let mut out = String::from(":root {\n");
+    for (name, color) in &styles.colors {
+        out.push_str(&format!("  {}: {};\n", var_name(name), color.to_css()));
+    }
+    for (name, text) in &styles.texts {
+        let var = var_name(name);
+        out.push_str(&format!("  {}-font-family: \"{}\", sans-serif;\n", var, text.font_family));
+        out.push_str(&format!("  {}-font-size: {};\n", var, px(text.font_size)));
+        out.push_str(&format!("  {}-font-weight: {};\n", var, text.font_weight));
+        out.push_str(&format!("  {}-line-height: {};\n", var, text.line_height));
+    }
+    out.push_str("}\n");
+    out
+}
@@ -0,0 +1,281 @@
+use std::collections::HashSet;
+use crate::node::{Effect, FontStyle, Node, NodeId, NodeKind, TextAlign};
+use crate::scene::Scene;
+use crate::types::{Color, Rect};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SvgOptions {
+    /// Replace text with vector shapes so the file doesn't depend on installed fonts.
+    /// Without a glyph source each line becomes a placeholder bar sized to the line.
+    pub outline_text: bool,
+}
+
+/// Serialize the given subtrees as a standalone SVG document whose viewBox covers
+/// their rotation-aware bounds. Coordinates stay in scene space.
+pub fn export_svg(scene: &Scene, roots: &[NodeId], opts: SvgOptions) -> String {
+    let bounds = roots.iter()
+        .filter_map(|&id| scene.subtree_bounds(id))
+        .reduce(|a, b| a.union(&b))
+        .unwrap_or(Rect { x: 0.0, y: 0.0, width: 0.0, height: 0.0 });
+    let mut body = String::new();
+    let mut defs = String::new();
+    let mut visited = HashSet::new();
+    for &id in roots {
+        write_node(scene, id, 1, &mut body, &mut defs, opts, &mut visited);
+    }
+    document(bounds, &defs, &body)
+}
+
+/// Serialize one subtree with the viewBox set to `clip` and everything outside it clipped away
+pub fn export_svg_clipped(scene: &Scene, root: NodeId, clip: Rect, opts: SvgOptions) -> String {
+    let mut body = String::new();
+    let mut defs = format!(
+        "    <clipPath id=\"clip-{}\"><rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" /></clipPath>\n",
+        root, num(clip.x), num(clip.y), num(clip.width), num(clip.height),
+    );
+    body.push_str(&format!("  <g clip-path=\"url(#clip-{})\">\n", root));
+    write_node(scene, root, 2, &mut body, &mut defs, opts, &mut HashSet::new());
+    body.push_str("  </g>\n");
+    document(clip, &defs, &body)
+}
+
+/// Root `<svg>` element around already-serialized defs and body
+fn document(bounds: Rect, defs: &str, body: &str) -> String {
+    let defs = if defs.is_empty() { String::new() } else { format!("  <defs>\n{}  </defs>\n", defs) };
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"{x} {y} {w} {h}\">\n{defs}{body}</svg>\n",
+        x = num(bounds.x), y = num(bounds.y), w = num(bounds.width), h = num(bounds.height), defs = defs, body = body,
+    )
+}
+
+/// Write a node and its descendants; ids already in `visited` are skipped so a child cycle terminates
+fn write_node(scene: &Scene, id: NodeId, depth: usize, out: &mut String, defs: &mut String, opts: SvgOptions, visited: &mut HashSet<NodeId>) {
+    let node = match scene.get_node(id) {
+        Some(n) if n.visible && visited.insert(id) => n,
+        _ => return,
+    };
+    let indent = "  ".repeat(depth);
+    // Like the canvas renderer, opacity and shadows apply to the node's own paint, not its children
+    let fx = effect_attrs(node, defs);
+    let paint = format!("{}{}", paint_attrs(node), fx);
+    if node.fills.iter().any(|f| f.gradient.is_some()) {
+        out.push_str(&format!("{}<!-- {}: gradient fill not supported, exported as its base color -->\n", indent, comment_text(&node.name)));
+    }
+
+    match &node.kind {
+        NodeKind::Rect => out.push_str(&format!("{}{}\n", indent, shape_rect(node, &paint, &fx))),
+        NodeKind::Ellipse => {
+            out.push_str(&format!(
+                "{}<ellipse cx=\"{}\" cy=\"{}\" rx=\"{}\" ry=\"{}\"{}{} />\n",
+                indent,
+                num(node.x + node.width / 2.0), num(node.y + node.height / 2.0),
+                num(node.width / 2.0), num(node.height / 2.0),
+                paint, rotate_attr(node),
+            ));
+        }
+        NodeKind::Path { points, closed } => {
+            if points.is_empty() {
+                return;
+            }
+            let pts: Vec<String> = points.iter()
+                .map(|p| format!("{},{}", num(node.x + p.x), num(node.y + p.y)))
+                .collect();
+            let tag = if *closed { "polygon" } else { "polyline" };
+            out.push_str(&format!("{}<{} points=\"{}\"{}{} />\n", indent, tag, pts.join(" "), paint, rotate_attr(node)));
+        }
+        NodeKind::Image { src, .. } => {
+            out.push_str(&format!(
+                "{}<image href=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" preserveAspectRatio=\"none\"{}{} />\n",
+                indent, escape(src), num(node.x), num(node.y), num(node.width), num(node.height), fx, rotate_attr(node),
+            ));
+        }
+        NodeKind::Connector { points, .. } => {
+            let Some(stroke) = &node.stroke else { return };
+            if points.len() < 2 {
+                return;
+            }
+            let pts: Vec<String> = points.iter()
+                .map(|p| format!("{},{}", num(node.x + p.x), num(node.y + p.y)))
+                .collect();
+            out.push_str(&format!("{}<g id=\"{}\"{}>\n", indent, escape(&node.name), fx));
+            out.push_str(&format!("{}  <polyline points=\"{}\"{} />\n", indent, pts.join(" "), paint_attrs(node)));
+            if let Some(head) = node.connector_arrowhead() {
+                let pts: Vec<String> = head.iter().map(|p| format!("{},{}", num(p.x), num(p.y))).collect();
+                out.push_str(&format!("{}  <polygon points=\"{}\"{} />\n", indent, pts.join(" "), color_attrs("fill", stroke.color)));
+            }
+            out.push_str(&format!("{}</g>\n", indent));
+        }
+        NodeKind::Polygon { .. } | NodeKind::Star { .. } => {
+            let Some(points) = node.shape_vertices() else { return };
+            let pts: Vec<String> = points.iter()
+                .map(|p| format!("{},{}", num(node.x + p.x), num(node.y + p.y)))
+                .collect();
+            out.push_str(&format!("{}<polygon points=\"{}\"{}{} />\n", indent, pts.join(" "), paint, rotate_attr(node)));
+        }
+        NodeKind::Line { .. } => {
+            let (Some(stroke), Some((start, end))) = (&node.stroke, node.line_endpoints()) else { return };
+            out.push_str(&format!("{}<g id=\"{}\"{}{}>\n", indent, escape(&node.name), fx, rotate_attr(node)));
+            out.push_str(&format!(
+                "{}  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\"{} />\n",
+                indent, num(start.x), num(start.y), num(end.x), num(end.y), paint_attrs(node),
+            ));
+            for head in node.line_arrowheads() {
+                let pts: Vec<String> = head.iter().map(|p| format!("{},{}", num(p.x), num(p.y))).collect();
+                out.push_str(&format!("{}  <polygon points=\"{}\"{} />\n", indent, pts.join(" "), color_attrs("fill", stroke.color)));
+            }
+            out.push_str(&format!("{}</g>\n", indent));
+        }
+        NodeKind::Text { content, font_size, line_height, text_align, .. } if opts.outline_text => {
+            out.push_str(&format!("{}<g id=\"{}\"{}{}>\n", indent, escape(&node.name), fx, rotate_attr(node)));
+            write_text_outline(node, content, *font_size, *line_height, text_align, &format!("{}  ", indent), out);
+            out.push_str(&format!("{}</g>\n", indent));
+        }
+        NodeKind::Text { content, font_size, font_family, line_height, font_weight, font_style, .. } => {
+            let fill = node.top_fill().map(|f| color_attrs("fill", f.layer_color())).unwrap_or_else(|| " fill=\"none\"".to_string());
+            let style = if *font_style == FontStyle::Italic { " font-style=\"italic\"" } else { "" };
+            out.push_str(&format!(
+                "{}<text x=\"{}\" y=\"{}\" font-family=\"{}\" font-size=\"{}\" font-weight=\"{}\"{}{}{}>",
+                indent, num(node.x), num(node.y), escape(font_family), num(*font_size), font_weight, style, fill, fx,
+            ));
+            // One tspan per hard line break; the first baseline sits roughly one ascent down
+            for (i, line) in content.split('\n').enumerate() {
+                let dy = if i == 0 { font_size * 0.8 } else { font_size * line_height };
+                out.push_str(&format!("<tspan x=\"{}\" dy=\"{}\">{}</tspan>", num(node.x), num(dy), escape(line)));
+            }
+            out.push_str("</text>\n");
+        }
+        NodeKind::Frame | NodeKind::Group | NodeKind::Instance(_) | NodeKind::Slot { .. } => {
+            out.push_str(&format!("{}<g id=\"{}\">\n", indent, escape(&node.name)));
+            if !matches!(node.kind, NodeKind::Group) {
+                out.push_str(&format!("{}  {}\n", indent, shape_rect(node, &paint, &fx)));
+            }
+            for &child in &node.children {
+                write_node(scene, child, depth + 1, out, defs, opts, visited);
+            }
+            out.push_str(&format!("{}</g>\n", indent));
+        }
+    }
+}
+
+/// Placeholder outlines for text: one bar per hard line spanning roughly the x-height band.
+/// Line widths are estimated from character counts relative to the node's measured width.
+fn write_text_outline(node: &Node, content: &str, font_size: f64, line_height: f64, align: &TextAlign, indent: &str, out: &mut String) {
+    let fill = node.top_fill().map(|f| color_attrs("fill", f.layer_color())).unwrap_or_else(|| " fill=\"none\"".to_string());
+    let lines: Vec<&str> = content.split('\n').collect();
+    let longest = lines.iter().map(|l| l.chars().count()).max().unwrap_or(0).max(1) as f64;
+    let line_h = font_size * line_height;
+    let bar_h = font_size * 0.5;
+    for (i, line) in lines.iter().enumerate() {
+        let chars = line.chars().count();
+        if chars == 0 {
+            continue;
+        }
+        let w = node.width * chars as f64 / longest;
+        let x = match align {
+            TextAlign::Left => node.x,
+            TextAlign::Center => node.x + (node.width - w) / 2.0,
+            TextAlign::Right => node.x + node.width - w,
+        };
+        // Baseline sits about 0.8em into the line; the bar covers the x-height above it
+        let y = node.y + line_h * i as f64 + font_size * 0.8 - bar_h;
+        out.push_str(&format!(
+            "{}<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"{} />\n",
+            indent, num(x), num(y), num(w), num(bar_h), fill,
+        ));
+    }
+}
+
+/// `<rect>` for a node's box, or an `<image>` (carrying only the effect attributes) when it has an image fill
+fn shape_rect(node: &Node, paint: &str, fx: &str) -> String {
+    let radius = if node.corner_radius > 0.0 {
+        format!(" rx=\"{}\"", num(node.corner_radius.min(node.width / 2.0).min(node.height / 2.0)))
+    } else {
+        String::new()
+    };
+    if let Some(src) = node.top_fill().and_then(|f| f.image.as_ref()) {
+        return format!(
+            "<image href=\"{}\" x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" preserveAspectRatio=\"xMidYMid slice\"{}{} />",
+            escape(src), num(node.x), num(node.y), num(node.width), num(node.height), fx, rotate_attr(node),
+        );
+    }
+    format!(
+        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"{}{}{} />",
+        num(node.x), num(node.y), num(node.width), num(node.height), radius, paint, rotate_attr(node),
+    )
+}
+
+/// Paint attributes from the topmost fill layer (SVG shapes take a single fill) and the stroke
+fn paint_attrs(node: &Node) -> String {
+    let mut attrs = match node.top_fill() {
+        Some(fill) if fill.image.is_none() => color_attrs("fill", fill.layer_color()),
+        _ => " fill=\"none\"".to_string(),
+    };
+    if let Some(stroke) = &node.stroke {
+        attrs.push_str(&color_attrs("stroke", stroke.color));
+        attrs.push_str(&format!(" stroke-width=\"{}\"", num(stroke.width)));
+        if !stroke.dash.is_empty() {
+            let dash: Vec<String> = stroke.dash.iter().map(|d| num(*d)).collect();
+            attrs.push_str(&format!(" stroke-dasharray=\"{}\"", dash.join(" ")));
+        }
+    }
+    attrs
+}
+
+/// `opacity` and a `filter` reference for the node's drop shadow, whose `<filter>` is appended
+/// to `defs`. Only the first shadow is exported, matching what the canvas renderer draws.
+fn effect_attrs(node: &Node, defs: &mut String) -> String {
+    let mut attrs = String::new();
+    if node.opacity < 1.0 {
+        attrs.push_str(&format!(" opacity=\"{}\"", num(node.opacity)));
+    }
+    if let Some(Effect::DropShadow { color, offset_x, offset_y, blur }) = node.effects.first() {
+        let id = format!("shadow-{}", node.id);
+        defs.push_str(&format!(
+            "    <filter id=\"{}\" x=\"-50%\" y=\"-50%\" width=\"200%\" height=\"200%\"><feDropShadow dx=\"{}\" dy=\"{}\" stdDeviation=\"{}\" flood-color=\"#{:02x}{:02x}{:02x}\" flood-opacity=\"{}\" /></filter>\n",
+            id, num(*offset_x), num(*offset_y), num(blur / 2.0), color.r, color.g, color.b, num(color.a),
+        ));
+        attrs.push_str(&format!(" filter=\"url(#{})\"", id));
+    }
+    if let Some(mode) = node.composite_operation() {
+        attrs.push_str(&format!(" style=\"mix-blend-mode:{}\"", mode));
+    }
+    attrs
+}
+
+/// `fill="#rrggbb"` plus a separate opacity attribute when translucent
+fn color_attrs(prop: &str, color: Color) -> String {
+    let hex = format!("#{:02x}{:02x}{:02x}", color.r, color.g, color.b);
+    if color.a < 1.0 {
+        format!(" {}=\"{}\" {}-opacity=\"{}\"", prop, hex, prop, num(color.a))
+    } else {
+        format!(" {}=\"{}\"", prop, hex)
+    }
+}
+
+/// Rotation about the node's transform origin, as the canvas renderer applies it
+fn rotate_attr(node: &Node) -> String {
+    if node.rotation == 0.0 {
+        return String::new();
+    }
+    let (px, py) = node.pivot();
+    format!(" transform=\"rotate({} {} {})\"", num(node.rotation.to_degrees()), num(px), num(py))
+}
+
+/// Compact number formatting: integers without a fraction, others to 3 decimals
+fn num(v: f64) -> String {
+    let rounded = (v * 1000.0).round() / 1000.0;
+    if rounded.fract() == 0.0 {
+        format!("{}", rounded as i64)
+    } else {
+        format!("{}", rounded)
+    }
+}
+
+/// Text safe inside `<!-- -->` (no double hyphens)
+fn comment_text(s: &str) -> String {
+    s.replace("--", "- -")
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
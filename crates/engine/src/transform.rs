@@ -1,4 +1,81 @@
-use crate::types::Point;
+use crate::types::{Point, Rect};
+
+/// Round an angle (radians) to the nearest multiple of `snap_degrees`. A step of 0 disables snapping.
+pub fn snap_angle(radians: f64, snap_degrees: f64) -> f64 {
+    if snap_degrees <= 0.0 {
+        return radians;
+    }
+    let step = snap_degrees.to_radians();
+    (radians / step).round() * step
+}
+
+/// Smallest width/height an interactive resize can produce
+pub const MIN_RESIZE_SIZE: f64 = 1.0;
+
+/// New box when dragging a resize handle to `p`. Handles: 0 top-left, 1 top-right,
+/// 2 bottom-left, 3 bottom-right, 4 top, 5 right, 6 bottom, 7 left. The opposite edge
+/// (or the center with `from_center`) stays put; `keep_ratio` preserves the aspect ratio,
+/// centering the cross axis for edge handles. Returns None for an unknown handle.
+pub fn resize_from_handle(b: Rect, handle: usize, p: Point, keep_ratio: bool, from_center: bool) -> Option<Rect> {
+    let (mx, my) = handle_axes(handle)?;
+    let (cx, cy) = (b.x + b.width / 2.0, b.y + b.height / 2.0);
+    let factor = if from_center { 2.0 } else { 1.0 };
+    let anchor_x = if from_center { cx } else if mx < 0.0 { b.x + b.width } else { b.x };
+    let anchor_y = if from_center { cy } else if my < 0.0 { b.y + b.height } else { b.y };
+
+    let mut w = if mx != 0.0 { (p.x - anchor_x) * mx * factor } else { b.width };
+    let mut h = if my != 0.0 { (p.y - anchor_y) * my * factor } else { b.height };
+    w = w.max(MIN_RESIZE_SIZE);
+    h = h.max(MIN_RESIZE_SIZE);
+
+    if keep_ratio && b.width > 0.0 && b.height > 0.0 {
+        let ratio = b.width / b.height;
+        if mx != 0.0 && my != 0.0 {
+            let s = (w / b.width).max(h / b.height);
+            w = b.width * s;
+            h = b.height * s;
+        } else if mx != 0.0 {
+            h = w / ratio;
+        } else {
+            w = h * ratio;
+        }
+        w = w.max(MIN_RESIZE_SIZE);
+        h = h.max(MIN_RESIZE_SIZE);
+    }
+    place_resized(b, handle, from_center, w, h)
+}
+
+/// Box of size (w, h) placed the way dragging `handle` of `b` places it: the opposite edge
+/// (or the center with `from_center`) stays put, and edge handles center the cross axis.
+/// Returns None for an unknown handle.
+pub fn place_resized(b: Rect, handle: usize, from_center: bool, w: f64, h: f64) -> Option<Rect> {
+    let (mx, my) = handle_axes(handle)?;
+    let place = |dir: f64, start: f64, size: f64, new_size: f64| {
+        if from_center || dir == 0.0 {
+            start + (size - new_size) / 2.0
+        } else if dir < 0.0 {
+            start + size - new_size
+        } else {
+            start
+        }
+    };
+    Some(Rect { x: place(mx, b.x, b.width, w), y: place(my, b.y, b.height, h), width: w, height: h })
+}
+
+/// Direction each axis' moving edge faces for a handle: -1 left/top, 1 right/bottom, 0 fixed
+fn handle_axes(handle: usize) -> Option<(f64, f64)> {
+    match handle {
+        0 => Some((-1.0, -1.0)),
+        1 => Some((1.0, -1.0)),
+        2 => Some((-1.0, 1.0)),
+        3 => Some((1.0, 1.0)),
+        4 => Some((0.0, -1.0)),
+        5 => Some((1.0, 0.0)),
+        6 => Some((0.0, 1.0)),
+        7 => Some((-1.0, 0.0)),
+        _ => None,
+    }
+}
 
 /// 2D affine transform matrix [a, b, c, d, tx, ty]
 #[derive(Clone, Copy, Debug)]
@@ -8,6 +85,7 @@ pub struct Transform {
     pub tx: f64, pub ty: f64,
 }
 
+#[allow(dead_code)]
 impl Transform {
     pub fn identity() -> Self {
         Self { a: 1.0, b: 0.0, c: 0.0, d: 1.0, tx: 0.0, ty: 0.0 }
@@ -53,3 +131,49 @@ impl Transform {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn snap_angle_rounds_to_step() {
+        let snapped = snap_angle(47f64.to_radians(), 15.0);
+        assert!((snapped.to_degrees() - 45.0).abs() < 1e-9);
+        let snapped = snap_angle(-53f64.to_radians(), 15.0);
+        assert!((snapped.to_degrees() + 60.0).abs() < 1e-9);
+        // Zero step disables snapping
+        assert_eq!(snap_angle(0.82, 0.0), 0.82);
+    }
+
+    fn rect(r: Rect) -> (f64, f64, f64, f64) {
+        (r.x, r.y, r.width, r.height)
+    }
+
+    #[test]
+    fn resize_from_each_handle() {
+        let b = Rect { x: 100.0, y: 100.0, width: 100.0, height: 50.0 };
+        let drag = |handle, x, y| rect(resize_from_handle(b, handle, Point { x, y }, false, false).unwrap());
+        assert_eq!(drag(0, 80.0, 90.0), (80.0, 90.0, 120.0, 60.0));
+        assert_eq!(drag(1, 220.0, 90.0), (100.0, 90.0, 120.0, 60.0));
+        assert_eq!(drag(2, 80.0, 160.0), (80.0, 100.0, 120.0, 60.0));
+        assert_eq!(drag(3, 220.0, 160.0), (100.0, 100.0, 120.0, 60.0));
+        assert_eq!(drag(4, 0.0, 80.0), (100.0, 80.0, 100.0, 70.0));
+        assert_eq!(drag(5, 250.0, 0.0), (100.0, 100.0, 150.0, 50.0));
+        assert_eq!(drag(6, 0.0, 170.0), (100.0, 100.0, 100.0, 70.0));
+        assert_eq!(drag(7, 50.0, 0.0), (50.0, 100.0, 150.0, 50.0));
+        // Dragging past the opposite edge stops at the minimum size
+        assert_eq!(drag(3, 0.0, 0.0), (100.0, 100.0, 1.0, 1.0));
+        assert!(resize_from_handle(b, 8, Point { x: 0.0, y: 0.0 }, false, false).is_none());
+    }
+
+    #[test]
+    fn resize_keeps_ratio_and_center() {
+        let b = Rect { x: 100.0, y: 100.0, width: 100.0, height: 50.0 };
+        let p = |x, y| Point { x, y };
+        assert_eq!(rect(resize_from_handle(b, 3, p(300.0, 160.0), true, false).unwrap()), (100.0, 100.0, 200.0, 100.0));
+        assert_eq!(rect(resize_from_handle(b, 5, p(300.0, 0.0), true, false).unwrap()), (100.0, 75.0, 200.0, 100.0));
+        assert_eq!(rect(resize_from_handle(b, 3, p(210.0, 135.0), false, true).unwrap()), (90.0, 115.0, 120.0, 20.0));
+        assert_eq!(rect(resize_from_handle(b, 7, p(140.0, 0.0), false, true).unwrap()), (140.0, 100.0, 20.0, 50.0));
+    }
+}
@@ -6,13 +6,14 @@ pub struct Point {
     pub y: f64,
 }
 
+#[allow(dead_code)]
 #[derive(Clone, Copy, Debug, Serialize, Deserialize)]
 pub struct Size {
     pub width: f64,
     pub height: f64,
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Rect {
     pub x: f64,
     pub y: f64,
@@ -26,6 +27,30 @@ impl Rect {
             && p.y >= self.y && p.y <= self.y + self.height
     }
 
+    /// True if `other` lies entirely inside this rect
+    pub fn contains_rect(&self, other: &Rect) -> bool {
+        other.x >= self.x && other.y >= self.y
+            && other.x + other.width <= self.x + self.width
+            && other.y + other.height <= self.y + self.height
+    }
+
+    /// True if the rects overlap (touching edges count)
+    pub fn intersects(&self, other: &Rect) -> bool {
+        other.x <= self.x + self.width && other.x + other.width >= self.x
+            && other.y <= self.y + self.height && other.y + other.height >= self.y
+    }
+
+    /// Smallest rect enclosing both rects
+    pub fn union(&self, other: &Rect) -> Self {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        Self {
+            x, y,
+            width: (self.x + self.width).max(other.x + other.width) - x,
+            height: (self.y + self.height).max(other.y + other.height) - y,
+        }
+    }
+
     pub fn from_two_points(a: Point, b: Point) -> Self {
         let x = a.x.min(b.x);
         let y = a.y.min(b.y);
@@ -37,7 +62,7 @@ impl Rect {
     }
 }
 
-#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -46,10 +71,38 @@ pub struct Color {
 }
 
 impl Color {
-    pub fn to_css(&self) -> String {
+    pub fn to_css(self) -> String {
         format!("rgba({},{},{},{})", self.r, self.g, self.b, self.a)
     }
 
+    /// Parse `#rgb`, `#rrggbb`, or `#rrggbbaa` (leading `#` optional)
+    pub fn from_hex(hex: &str) -> Option<Self> {
+        let hex = hex.trim().trim_start_matches('#');
+        if !hex.is_ascii() { return None; }
+        let byte = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        match hex.len() {
+            3 => {
+                let nib = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|v| v * 17);
+                Some(Self { r: nib(0)?, g: nib(1)?, b: nib(2)?, a: 1.0 })
+            }
+            6 => Some(Self { r: byte(0)?, g: byte(2)?, b: byte(4)?, a: 1.0 }),
+            8 => Some(Self { r: byte(0)?, g: byte(2)?, b: byte(4)?, a: byte(6)? as f64 / 255.0 }),
+            _ => None,
+        }
+    }
+
+    /// Same color within a couple of 8-bit steps per channel and ~1% alpha
+    pub fn approx_eq(self, other: Self) -> bool {
+        let close = |a: u8, b: u8| a.abs_diff(b) <= 2;
+        close(self.r, other.r) && close(self.g, other.g) && close(self.b, other.b) && (self.a - other.a).abs() <= 0.01
+    }
+
+    /// Format as `#rrggbbaa`
+    pub fn to_hex(self) -> String {
+        let a = (self.a.clamp(0.0, 1.0) * 255.0).round() as u8;
+        format!("#{:02x}{:02x}{:02x}{:02x}", self.r, self.g, self.b, a)
+    }
+
     pub fn white() -> Self { Self { r: 255, g: 255, b: 255, a: 1.0 } }
     pub fn black() -> Self { Self { r: 0, g: 0, b: 0, a: 1.0 } }
     pub fn transparent() -> Self { Self { r: 0, g: 0, b: 0, a: 0.0 } }
@@ -13,6 +13,8 @@ interface DragState {
   originalY?: number;
   originalW?: number;
   originalH?: number;
+  /** Dragging the shared handles of a multi-selection */
+  multi?: boolean;
 }
 
 export class Editor {
@@ -173,6 +175,19 @@ export class Editor {
       const handle = this.engine.hit_test_handle(x, y);
       if (handle >= 0) {
         const sel = this.engine.get_selection();
+        const boundsJson = sel.length > 1 ? this.engine.get_selection_bounds() : undefined;
+        if (boundsJson) {
+          const b = JSON.parse(boundsJson);
+          this.engine.push_undo();
+          this.drag = {
+            startX: x, startY: y, currentX: x, currentY: y,
+            nodeId: sel[0]!, handleIndex: handle, multi: true,
+            originalX: b.x, originalY: b.y,
+            originalW: b.width, originalH: b.height,
+          };
+          this.canvas.setPointerCapture(e.pointerId);
+          return;
+        }
         if (sel.length > 0) {
           const nodeJson = this.engine.get_node_json(sel[0]!);
           if (nodeJson) {
@@ -246,23 +261,31 @@ export class Editor {
         const oy = this.drag.originalY!;
         const ow = this.drag.originalW!;
         const oh = this.drag.originalH!;
-        let nx = ox, ny = oy, nw = ow, nh = oh;
 
-        switch (this.drag.handleIndex) {
-          case 0: nx = sx; ny = sy; nw = ox + ow - sx; nh = oy + oh - sy; break;
-          case 1: ny = sy; nw = sx - ox; nh = oy + oh - sy; break;
-          case 2: nx = sx; nw = ox + ow - sx; nh = sy - oy; break;
-          case 3: nw = sx - ox; nh = sy - oy; break;
-        }
-        if (nw > 0 && nh > 0) {
-          this.engine.set_node_position(this.drag.nodeId, nx, ny);
-          this.engine.resize_node(this.drag.nodeId, nw, nh);
+        if (this.drag.multi) {
+          let nw = ow, nh = oh;
+          switch (this.drag.handleIndex) {
+            case 0: nw = ox + ow - sx; nh = oy + oh - sy; break;
+            case 1: nw = sx - ox; nh = oy + oh - sy; break;
+            case 2: nw = ox + ow - sx; nh = sy - oy; break;
+            case 3: nw = sx - ox; nh = sy - oy; break;
+          }
+          // Handles are 0 TL, 1 TR, 2 BL, 3 BR; the opposite corner stays put
+          if (nw > 0 && nh > 0) this.engine.resize_selection(nw, nh, 3 - this.drag.handleIndex);
+        } else if (this.drag.handleIndex === 8) {
+          // The rotation handle sits above top-center, so pointing straight up is 0
+          const angle = Math.atan2(sy - (oy + oh / 2), sx - (ox + ow / 2)) + Math.PI / 2;
+          this.engine.set_rotation(this.drag.nodeId, angle);
+        } else {
+          // The engine keeps the opposite edge fixed and snaps only the dragged one
+          this.engine.resize_from_handle(this.drag.nodeId, this.drag.handleIndex, sx, sy, false, false, false);
         }
       } else {
+        // Position from the total drag so snapping doesn't swallow small per-event deltas
         const zoom = this.engine.get_zoom();
-        const dx = (x - this.drag.currentX) / zoom;
-        const dy = (y - this.drag.currentY) / zoom;
-        this.engine.move_node(this.drag.nodeId, dx, dy);
+        const dx = (x - this.drag.startX) / zoom;
+        const dy = (y - this.drag.startY) / zoom;
+        this.engine.set_node_position(this.drag.nodeId, this.drag.originalX! + dx, this.drag.originalY! + dy, false);
         this.drag.currentX = x;
         this.drag.currentY = y;
       }
@@ -743,7 +766,7 @@ export class Editor {
     const w = node.width;
     const h = node.height;
     const cr = node.corner_radius || 0;
-    const fill = node.fill?.color;
+    const fill = node.fills?.[node.fills.length - 1]?.color;
     const stroke = node.stroke;
 
     // Draw shape
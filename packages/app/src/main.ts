@@ -99,8 +99,8 @@ async function main() {
   engine.set_justify_content(btnFrame, "center");
   const btnLabel = engine.add_text(0, 0, "Click Me", 16);
   engine.set_fill_color(btnLabel, 255, 255, 255, 1.0);
-  engine.resize_node(btnLabel, 80, 20);
-  engine.reparent_node(btnLabel, btnFrame);
+  engine.resize_node(btnLabel, 80, 20, false);
+  engine.reparent_node(btnLabel, btnFrame, false);
 
   const btnCompId = engine.create_component(btnFrame, "Button");
   engine.add_variant_prop(btnCompId, "variant", '{"type":"string","options":["primary","secondary","danger"],"default":"primary"}');
@@ -119,8 +119,8 @@ async function main() {
   engine.set_justify_content(btn2Frame, "center");
   const btn2Label = engine.add_text(0, 0, "Secondary", 16);
   engine.set_fill_color(btn2Label, 255, 255, 255, 1.0);
-  engine.resize_node(btn2Label, 90, 20);
-  engine.reparent_node(btn2Label, btn2Frame);
+  engine.resize_node(btn2Label, 90, 20, false);
+  engine.reparent_node(btn2Label, btn2Frame, false);
   engine.add_variant(btnCompId, '{"variant":{"String":"secondary"},"disabled":{"Boolean":false}}', btn2Frame);
 
   // Danger variant frame
@@ -133,8 +133,8 @@ async function main() {
   engine.set_justify_content(btn3Frame, "center");
   const btn3Label = engine.add_text(0, 0, "Delete", 16);
   engine.set_fill_color(btn3Label, 255, 255, 255, 1.0);
-  engine.resize_node(btn3Label, 60, 20);
-  engine.reparent_node(btn3Label, btn3Frame);
+  engine.resize_node(btn3Label, 60, 20, false);
+  engine.reparent_node(btn3Label, btn3Frame, false);
   engine.add_variant(btnCompId, '{"variant":{"String":"danger"},"disabled":{"Boolean":false}}', btn3Frame);
 
   // Button instances
@@ -159,12 +159,12 @@ async function main() {
   engine.set_fill_color(cbBox, 51, 51, 51, 1.0);
   engine.set_corner_radius(cbBox, 6);
   engine.set_stroke(cbBox, 100, 100, 100, 1.0, 1.5);
-  engine.reparent_node(cbBox, cbFrame);
+  engine.reparent_node(cbBox, cbFrame, false);
 
   // Label
   const cbLabel = engine.add_text(0, 0, "Remember me", 14);
   engine.set_fill_color(cbLabel, 200, 200, 200, 1.0);
-  engine.reparent_node(cbLabel, cbFrame);
+  engine.reparent_node(cbLabel, cbFrame, false);
 
   const cbCompId = engine.create_component(cbFrame, "Checkbox");
   engine.add_variant_prop(cbCompId, "checked", '{"type":"boolean","default":false}');
@@ -187,15 +187,15 @@ async function main() {
   engine.set_layout_mode(cbCheckedBox, "flex");
   engine.set_align_items(cbCheckedBox, "center");
   engine.set_justify_content(cbCheckedBox, "center");
-  engine.reparent_node(cbCheckedBox, cbCheckedFrame);
+  engine.reparent_node(cbCheckedBox, cbCheckedFrame, false);
 
   const cbCheckmark = engine.add_text(0, 0, "✓", 16);
   engine.set_fill_color(cbCheckmark, 255, 255, 255, 1.0);
-  engine.reparent_node(cbCheckmark, cbCheckedBox);
+  engine.reparent_node(cbCheckmark, cbCheckedBox, false);
 
   const cbCheckedLabel = engine.add_text(0, 0, "Remember me", 14);
   engine.set_fill_color(cbCheckedLabel, 200, 200, 200, 1.0);
-  engine.reparent_node(cbCheckedLabel, cbCheckedFrame);
+  engine.reparent_node(cbCheckedLabel, cbCheckedFrame, false);
 
   engine.add_variant(cbCompId, '{"checked":{"Boolean":true}}', cbCheckedFrame);
 
@@ -219,18 +219,18 @@ async function main() {
   // Title
   const modalTitle = engine.add_text(0, 0, "Dialog Title", 18);
   engine.set_fill_color(modalTitle, 230, 230, 230, 1.0);
-  engine.reparent_node(modalTitle, modalFrame);
+  engine.reparent_node(modalTitle, modalFrame, false);
 
   // Divider
   const modalDiv = engine.add_rect(0, 0, 320, 1);
   engine.set_fill_color(modalDiv, 60, 60, 60, 1.0);
-  engine.reparent_node(modalDiv, modalFrame);
+  engine.reparent_node(modalDiv, modalFrame, false);
 
   // Content slot placeholder
   const modalSlot = engine.add_frame(0, 0, 320, 100);
   engine.set_node_name(modalSlot, "content");
   engine.set_fill_color(modalSlot, 0, 0, 0, 0.0);
-  engine.reparent_node(modalSlot, modalFrame);
+  engine.reparent_node(modalSlot, modalFrame, false);
 
   // Footer buttons area — flex row, end-aligned
   const modalFooter = engine.add_frame(540, 248, 320, 40);
@@ -241,7 +241,7 @@ async function main() {
   engine.set_justify_content(modalFooter, "end");
   engine.set_align_items(modalFooter, "center");
   engine.set_layout_gap(modalFooter, 12);
-  engine.reparent_node(modalFooter, modalFrame);
+  engine.reparent_node(modalFooter, modalFrame, false);
 
   // Cancel button in footer — flex centered
   const cancelBtn = engine.add_frame(0, 0, 80, 32);
@@ -251,10 +251,10 @@ async function main() {
   engine.set_layout_mode(cancelBtn, "flex");
   engine.set_align_items(cancelBtn, "center");
   engine.set_justify_content(cancelBtn, "center");
-  engine.reparent_node(cancelBtn, modalFooter);
+  engine.reparent_node(cancelBtn, modalFooter, false);
   const cancelLbl = engine.add_text(0, 0, "Cancel", 13);
   engine.set_fill_color(cancelLbl, 200, 200, 200, 1.0);
-  engine.reparent_node(cancelLbl, cancelBtn);
+  engine.reparent_node(cancelLbl, cancelBtn, false);
 
   // Confirm button in footer — flex centered
   const confirmBtn = engine.add_frame(0, 0, 100, 32);
@@ -264,10 +264,10 @@ async function main() {
   engine.set_layout_mode(confirmBtn, "flex");
   engine.set_align_items(confirmBtn, "center");
   engine.set_justify_content(confirmBtn, "center");
-  engine.reparent_node(confirmBtn, modalFooter);
+  engine.reparent_node(confirmBtn, modalFooter, false);
   const confirmLbl = engine.add_text(0, 0, "Confirm", 13);
   engine.set_fill_color(confirmLbl, 255, 255, 255, 1.0);
-  engine.reparent_node(confirmLbl, confirmBtn);
+  engine.reparent_node(confirmLbl, confirmBtn, false);
 
   const modalCompId = engine.create_component(modalFrame, "Modal");
   engine.add_slot(modalCompId, "content", modalSlot);
@@ -287,6 +287,12 @@ async function main() {
   // (The slot is a child of the instance)
 
   editor.requestRender();
+
+  // Text measured before web fonts load has fallback-font widths; re-measure once they're ready
+  document.fonts.ready.then(() => {
+    engine.mark_fonts_dirty();
+    editor.requestRender();
+  });
 }
 
 main().catch(console.error);
@@ -135,18 +135,18 @@ function buildTools(): Tool[] {
       execute: (m, editor) => {
         const nodeId = BigInt(m[1]!);
         const parent = m[2]!.toLowerCase() === "root" ? undefined : BigInt(m[2]!);
-        editor.engine.reparent_node(nodeId, parent);
+        editor.engine.reparent_node(nodeId, parent, false);
         editor.requestRender();
         return `Moved node #${m[1]} ${parent ? `into frame #${m[2]}` : "to root"}`;
       },
     },
     {
       name: "duplicate",
-      description: "Duplicate a node",
+      description: "Duplicate a node with its children",
       usage: "duplicate <node_id>",
       pattern: /^(?:duplicate|dup)\s+(\d+)$/i,
       execute: (m, editor) => {
-        const newId = editor.engine.duplicate_node(BigInt(m[1]!));
+        const newId = editor.engine.duplicate_node_deep(BigInt(m[1]!));
         if (newId === BigInt(0)) return "Node not found.";
         editor.engine.select(newId);
         editor.requestRender();
@@ -304,7 +304,7 @@ function buildTools(): Tool[] {
       execute: (m, editor) => {
         const sel = editor.engine.get_selection();
         if (sel.length === 0) return "No selection.";
-        for (const id of sel) editor.engine.move_node(id, +m[1]!, +m[2]!);
+        for (const id of sel) editor.engine.move_node(id, +m[1]!, +m[2]!, false);
         editor.requestRender();
         return `Moved by (${m[1]}, ${m[2]})`;
       },
@@ -317,7 +317,7 @@ function buildTools(): Tool[] {
       execute: (m, editor) => {
         const sel = editor.engine.get_selection();
         if (sel.length === 0) return "No selection.";
-        for (const id of sel) editor.engine.resize_node(id, +m[1]!, +m[2]!);
+        for (const id of sel) editor.engine.resize_node(id, +m[1]!, +m[2]!, false);
         editor.requestRender();
         return `Resized → ${m[1]}×${m[2]}`;
       },
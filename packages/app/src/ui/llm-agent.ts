@@ -107,7 +107,7 @@ export function buildToolDefs(): ToolDef[] {
     tool("reparent_node", "Move node into another frame", {
       node_id: num("Node ID"), parent_id: num("New parent frame ID"),
     }, ["node_id", "parent_id"]),
-    tool("duplicate_node", "Duplicate a node", { node_id: num("Node ID") }, ["node_id"]),
+    tool("duplicate_node", "Duplicate a node with its children", { node_id: num("Node ID") }, ["node_id"]),
     tool("set_text_content", "Change text content", {
       node_id: num("Node ID"), text: str("New text"),
     }, ["node_id", "text"]),
@@ -239,15 +239,15 @@ export function executeTool(name: string, args: Record<string, any>, editor: any
         editor.requestRender();
         return "ok";
       case "move_node":
-        engine.move_node(bi(args.node_id), args.dx, args.dy);
+        engine.move_node(bi(args.node_id), args.dx, args.dy, false);
         editor.requestRender();
         return "ok";
       case "set_position":
-        engine.set_node_position(bi(args.node_id), args.x, args.y);
+        engine.set_node_position(bi(args.node_id), args.x, args.y, false);
         editor.requestRender();
         return "ok";
       case "resize_node":
-        engine.resize_node(bi(args.node_id), args.w, args.h);
+        engine.resize_node(bi(args.node_id), args.w, args.h, false);
         editor.requestRender();
         return "ok";
       case "rename_node":
@@ -260,11 +260,11 @@ export function executeTool(name: string, args: Record<string, any>, editor: any
         editor.requestRender();
         return "ok";
       case "reparent_node":
-        engine.reparent_node(bi(args.node_id), bi(args.parent_id));
+        engine.reparent_node(bi(args.node_id), bi(args.parent_id), false);
         editor.requestRender();
         return "ok";
       case "duplicate_node": {
-        const id = engine.duplicate_node(bi(args.node_id));
+        const id = engine.duplicate_node_deep(bi(args.node_id));
         editor.requestRender();
         return JSON.stringify({ duplicated: Number(id) });
       }
@@ -169,11 +169,11 @@ export function setupPropertiesPanel(container: HTMLElement, editor: Editor) {
     const posRow = document.createElement("div");
     posRow.style.cssText = "display:grid;grid-template-columns:1fr 1fr;gap:6px;";
     posRow.appendChild(createLabeledInput("X", node.x.toFixed(0), (v) => {
-      editor.engine.set_node_position(id, parseFloat(v), node.y);
+      editor.engine.set_node_position(id, parseFloat(v), node.y, false);
       editor.requestRender();
     }));
     posRow.appendChild(createLabeledInput("Y", node.y.toFixed(0), (v) => {
-      editor.engine.set_node_position(id, node.x, parseFloat(v));
+      editor.engine.set_node_position(id, node.x, parseFloat(v), false);
       editor.requestRender();
     }));
     posSection.appendChild(posRow);
@@ -184,11 +184,11 @@ export function setupPropertiesPanel(container: HTMLElement, editor: Editor) {
     const sizeRow = document.createElement("div");
     sizeRow.style.cssText = "display:grid;grid-template-columns:1fr 1fr;gap:6px;";
     sizeRow.appendChild(createLabeledInput("W", node.width.toFixed(0), (v) => {
-      editor.engine.resize_node(id, parseFloat(v), node.height);
+      editor.engine.resize_node(id, parseFloat(v), node.height, false);
       editor.requestRender();
     }));
     sizeRow.appendChild(createLabeledInput("H", node.height.toFixed(0), (v) => {
-      editor.engine.resize_node(id, node.width, parseFloat(v));
+      editor.engine.resize_node(id, node.width, parseFloat(v), false);
       editor.requestRender();
     }));
     sizeSection.appendChild(sizeRow);
@@ -250,11 +250,12 @@ export function setupPropertiesPanel(container: HTMLElement, editor: Editor) {
     appSection.appendChild(opacityRow);
     container.appendChild(appSection);
 
-    // --- Fill ---
-    if (node.fill) {
+    // --- Fill (topmost layer) ---
+    const topFill = node.fills?.[node.fills.length - 1];
+    if (topFill) {
       const fillSection = createSection("Fill");
       fillSection.appendChild(createColorRow(
-        node.fill.color,
+        topFill.color,
         (r, g, b, a) => {
           editor.engine.set_fill_color(id, r, g, b, a);
           editor.requestRender();
@@ -503,7 +504,7 @@ export function setupPropertiesPanel(container: HTMLElement, editor: Editor) {
         wIn.style.cssText = "width:50px;";
         wIn.value = String(Math.round(node.width));
         wIn.addEventListener("change", () => {
-          editor.engine.resize_node(BigInt(id), parseFloat(wIn.value) || node.width, node.height);
+          editor.engine.resize_node(BigInt(id), parseFloat(wIn.value) || node.width, node.height, false);
           editor.requestRender();
         });
         dimRow.appendChild(wIn);
@@ -517,7 +518,7 @@ export function setupPropertiesPanel(container: HTMLElement, editor: Editor) {
         hIn.style.cssText = "width:50px;";
         hIn.value = String(Math.round(node.height));
         hIn.addEventListener("change", () => {
-          editor.engine.resize_node(BigInt(id), node.width, parseFloat(hIn.value) || node.height);
+          editor.engine.resize_node(BigInt(id), node.width, parseFloat(hIn.value) || node.height, false);
           editor.requestRender();
         });
         dimRow.appendChild(hIn);